@@ -7,7 +7,24 @@ use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
 use rand::Rng;
 use sha2::{Digest, Sha256};
 
-const SCOPES: &str = "https://www.googleapis.com/auth/calendar.readonly https://www.googleapis.com/auth/tasks";
+const READ_SCOPES: &[&str] = &[
+    "https://www.googleapis.com/auth/calendar.readonly",
+    "https://www.googleapis.com/auth/tasks",
+];
+const CALENDAR_WRITE_SCOPE: &str = "https://www.googleapis.com/auth/calendar.events";
+
+// OpenID Connect scopes aren't always echoed back in the token response
+const OIDC_SCOPES: &[&str] = &["openid", "profile", "email", "offline_access"];
+
+const GOOGLE_TOKEN_FILE: &str = "token.json";
+
+fn required_scopes() -> Vec<&'static str> {
+    let mut scopes = READ_SCOPES.to_vec();
+    if crate::settings::load_settings().events.allow_editing {
+        scopes.push(CALENDAR_WRITE_SCOPE);
+    }
+    scopes
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Credentials {
@@ -22,11 +39,65 @@ pub struct InstalledCredentials {
     pub token_uri: String,
 }
 
+/// An OAuth client for the loopback + PKCE sign-in flow
+#[derive(Debug, Clone)]
+pub struct OAuthClient {
+    pub client_id: String,
+    /// Empty for public clients, which rely on PKCE alone
+    pub client_secret: String,
+    pub auth_uri: String,
+    pub token_uri: String,
+    /// Provider-specific query parameters for the consent page
+    pub auth_params: Vec<(&'static str, &'static str)>,
+    /// File next to the executable that holds this client's token
+    pub token_file: &'static str,
+}
+
+impl OAuthClient {
+    /// Form fields identifying the client on token requests
+    fn token_params<'a>(&'a self, params: &[(&'a str, &'a str)]) -> Vec<(&'a str, &'a str)> {
+        let mut form = vec![("client_id", self.client_id.as_str())];
+        if !self.client_secret.is_empty() {
+            form.push(("client_secret", self.client_secret.as_str()));
+        }
+        form.extend_from_slice(params);
+        form
+    }
+}
+
+impl Credentials {
+    fn oauth_client(&self) -> OAuthClient {
+        OAuthClient {
+            client_id: self.installed.client_id.clone(),
+            client_secret: self.installed.client_secret.clone(),
+            auth_uri: self.installed.auth_uri.clone(),
+            token_uri: self.installed.token_uri.clone(),
+            auth_params: vec![("access_type", "offline"), ("prompt", "consent")],
+            token_file: GOOGLE_TOKEN_FILE,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Token {
     pub access_token: String,
     pub refresh_token: Option<String>,
     pub expires_at: Option<i64>,
+    /// Space-separated scopes granted to this token
+    pub scope: Option<String>,
+}
+
+impl Token {
+    fn has_scopes(&self, scopes: &[&str]) -> bool {
+        match &self.scope {
+            Some(granted) => scopes
+                .iter()
+                .filter(|s| !OIDC_SCOPES.contains(s))
+                .all(|s| granted.split(' ').any(|g| g == *s)),
+            // Tokens saved before scopes were recorded only cover the read scopes
+            None => scopes.iter().all(|s| READ_SCOPES.contains(s)),
+        }
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -34,6 +105,7 @@ struct TokenResponse {
     access_token: String,
     refresh_token: Option<String>,
     expires_in: Option<i64>,
+    scope: Option<String>,
 }
 
 fn get_base_dir() -> PathBuf {
@@ -45,8 +117,8 @@ fn get_credentials_path() -> PathBuf {
     get_base_dir().join("credentials.json")
 }
 
-fn get_token_path() -> PathBuf {
-    get_base_dir().join("token.json")
+fn get_token_path(token_file: &str) -> PathBuf {
+    get_base_dir().join(token_file)
 }
 
 pub fn load_credentials() -> Result<Credentials, String> {
@@ -57,8 +129,8 @@ pub fn load_credentials() -> Result<Credentials, String> {
         .map_err(|e| format!("Failed to parse credentials.json: {}", e))
 }
 
-pub fn load_token() -> Option<Token> {
-    let path = get_token_path();
+pub fn load_token(token_file: &str) -> Option<Token> {
+    let path = get_token_path(token_file);
     if path.exists() {
         if let Ok(content) = fs::read_to_string(&path) {
             return serde_json::from_str(&content).ok();
@@ -67,12 +139,12 @@ pub fn load_token() -> Option<Token> {
     None
 }
 
-fn save_token(token: &Token) -> Result<(), String> {
-    let path = get_token_path();
+fn save_token(token_file: &str, token: &Token) -> Result<(), String> {
+    let path = get_token_path(token_file);
     let content = serde_json::to_string_pretty(token)
         .map_err(|e| format!("Failed to serialize token: {}", e))?;
     fs::write(&path, content)
-        .map_err(|e| format!("Failed to write token.json: {}", e))
+        .map_err(|e| format!("Failed to write {}: {}", token_file, e))
 }
 
 fn generate_code_verifier() -> String {
@@ -90,9 +162,13 @@ fn generate_code_challenge(verifier: &str) -> String {
 
 pub async fn get_access_token() -> Result<String, String> {
     let creds = load_credentials()?;
+    access_token(&creds.oauth_client(), &required_scopes()).await
+}
 
-    // Check if we have a valid token
-    if let Some(mut token) = load_token() {
+/// A valid access token for `client`: the stored one, a refreshed one, or a new sign-in
+pub async fn access_token(client: &OAuthClient, scopes: &[&str]) -> Result<String, String> {
+    // Check if we have a valid token that covers the enabled features
+    if let Some(token) = load_token(client.token_file).filter(|t| t.has_scopes(scopes)) {
         let now = chrono::Utc::now().timestamp();
 
         // Token still valid (with 60 second buffer)
@@ -104,9 +180,9 @@ pub async fn get_access_token() -> Result<String, String> {
 
         // Try to refresh
         if let Some(ref refresh_token) = token.refresh_token {
-            match refresh_access_token(&creds, refresh_token).await {
+            match refresh_access_token(client, refresh_token).await {
                 Ok(new_token) => {
-                    save_token(&new_token)?;
+                    save_token(client.token_file, &new_token)?;
                     return Ok(new_token.access_token);
                 }
                 Err(e) => {
@@ -118,23 +194,45 @@ pub async fn get_access_token() -> Result<String, String> {
     }
 
     // Need to do full OAuth flow
-    let token = perform_oauth_flow(&creds).await?;
-    save_token(&token)?;
+    let token = perform_oauth_flow(client, scopes).await?;
+    save_token(client.token_file, &token)?;
     Ok(token.access_token)
 }
 
-async fn refresh_access_token(creds: &Credentials, refresh_token: &str) -> Result<Token, String> {
+/// Signs in even when a token is stored, replacing it
+pub async fn sign_in(client: &OAuthClient, scopes: &[&str]) -> Result<(), String> {
+    let token = perform_oauth_flow(client, scopes).await?;
+    save_token(client.token_file, &token)
+}
+
+/// Forgets the stored token; false when there was none
+pub fn sign_out(token_file: &str) -> Result<bool, String> {
+    match fs::remove_file(get_token_path(token_file)) {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+        Err(e) => Err(format!("Failed to remove {}: {}", token_file, e)),
+    }
+}
+
+pub async fn sign_in_google() -> Result<(), String> {
+    let creds = load_credentials()?;
+    sign_in(&creds.oauth_client(), &required_scopes()).await
+}
+
+pub fn sign_out_google() -> Result<bool, String> {
+    sign_out(GOOGLE_TOKEN_FILE)
+}
+
+async fn refresh_access_token(oauth: &OAuthClient, refresh_token: &str) -> Result<Token, String> {
     let client = reqwest::Client::new();
 
-    let params = [
-        ("client_id", creds.installed.client_id.as_str()),
-        ("client_secret", creds.installed.client_secret.as_str()),
+    let params = oauth.token_params(&[
         ("refresh_token", refresh_token),
         ("grant_type", "refresh_token"),
-    ];
+    ]);
 
     let response = client
-        .post(&creds.installed.token_uri)
+        .post(&oauth.token_uri)
         .form(&params)
         .send()
         .await
@@ -158,10 +256,11 @@ async fn refresh_access_token(creds: &Credentials, refresh_token: &str) -> Resul
         access_token: token_response.access_token,
         refresh_token: token_response.refresh_token.or(Some(refresh_token.to_string())),
         expires_at,
+        scope: token_response.scope,
     })
 }
 
-async fn perform_oauth_flow(creds: &Credentials) -> Result<Token, String> {
+async fn perform_oauth_flow(oauth: &OAuthClient, scopes: &[&str]) -> Result<Token, String> {
     let code_verifier = generate_code_verifier();
     let code_challenge = generate_code_challenge(&code_verifier);
 
@@ -173,14 +272,17 @@ async fn perform_oauth_flow(creds: &Credentials) -> Result<Token, String> {
     let redirect_uri = format!("http://127.0.0.1:{}", port);
 
     // Build auth URL
-    let auth_url = format!(
-        "{}?client_id={}&redirect_uri={}&response_type=code&scope={}&code_challenge={}&code_challenge_method=S256&access_type=offline&prompt=consent",
-        creds.installed.auth_uri,
-        urlencoding::encode(&creds.installed.client_id),
+    let mut auth_url = format!(
+        "{}?client_id={}&redirect_uri={}&response_type=code&scope={}&code_challenge={}&code_challenge_method=S256",
+        oauth.auth_uri,
+        urlencoding::encode(&oauth.client_id),
         urlencoding::encode(&redirect_uri),
-        urlencoding::encode(SCOPES),
+        urlencoding::encode(&scopes.join(" ")),
         urlencoding::encode(&code_challenge)
     );
+    for (key, value) in &oauth.auth_params {
+        auth_url.push_str(&format!("&{}={}", key, urlencoding::encode(value)));
+    }
 
     // Open browser
     if let Err(e) = open::that(&auth_url) {
@@ -220,17 +322,15 @@ async fn perform_oauth_flow(creds: &Credentials) -> Result<Token, String> {
     // Exchange code for token
     let client = reqwest::Client::new();
 
-    let params = [
-        ("client_id", creds.installed.client_id.as_str()),
-        ("client_secret", creds.installed.client_secret.as_str()),
+    let params = oauth.token_params(&[
         ("code", code.as_str()),
         ("code_verifier", code_verifier.as_str()),
         ("redirect_uri", redirect_uri.as_str()),
         ("grant_type", "authorization_code"),
-    ];
+    ]);
 
     let response = client
-        .post(&creds.installed.token_uri)
+        .post(&oauth.token_uri)
         .form(&params)
         .send()
         .await
@@ -254,5 +354,6 @@ async fn perform_oauth_flow(creds: &Credentials) -> Result<Token, String> {
         access_token: token_response.access_token,
         refresh_token: token_response.refresh_token,
         expires_at,
+        scope: token_response.scope,
     })
 }
@@ -0,0 +1,406 @@
+//! Free-slot computation from cached events, optionally merged with Google freeBusy data.
+
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Datelike, Duration, FixedOffset, NaiveDate, NaiveTime, Weekday};
+
+use crate::auth::get_access_token;
+use crate::calendar::{self, Event};
+use crate::settings::LocaleSettings;
+
+const FREEBUSY_URL: &str = "https://www.googleapis.com/calendar/v3/freeBusy";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AvailabilityQuery {
+    /// First and last day to search, `YYYY-MM-DD`, inclusive
+    pub start_date: String,
+    pub end_date: String,
+    /// Working hours as `HH:MM`
+    pub work_start: String,
+    pub work_end: String,
+    pub working_days: Vec<Weekday>,
+    pub min_slot_minutes: i64,
+    /// Whether all-day events block the whole day
+    pub include_all_day: bool,
+    /// Calendar ids whose events count as busy; empty means all
+    pub calendars: Vec<String>,
+    /// Other people's calendars (usually email addresses) to check through freeBusy
+    pub people: Vec<String>,
+}
+
+impl Default for AvailabilityQuery {
+    fn default() -> Self {
+        AvailabilityQuery {
+            start_date: String::new(),
+            end_date: String::new(),
+            work_start: "09:00".to_string(),
+            work_end: "17:00".to_string(),
+            working_days: vec![Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri],
+            min_slot_minutes: 30,
+            include_all_day: false,
+            calendars: Vec::new(),
+            people: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FreeSlot {
+    pub date: String,
+    /// RFC 3339
+    pub start: String,
+    pub end: String,
+    pub date_formatted: String,
+    pub time_range: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Availability {
+    pub slots: Vec<FreeSlot>,
+    /// Copy-pasteable summary, one line per day
+    pub text: String,
+}
+
+type Interval = (DateTime<FixedOffset>, DateTime<FixedOffset>);
+
+#[derive(Debug, Deserialize)]
+struct FreeBusyResponse {
+    calendars: Option<std::collections::HashMap<String, FreeBusyCalendar>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FreeBusyCalendar {
+    busy: Option<Vec<FreeBusyPeriod>>,
+    errors: Option<Vec<serde_json::Value>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FreeBusyPeriod {
+    start: String,
+    end: String,
+}
+
+fn parse_date(s: &str, field: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d").map_err(|e| format!("Invalid {} '{}': {}", field, s, e))
+}
+
+fn parse_time(s: &str, field: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(s, "%H:%M").map_err(|e| format!("Invalid {} '{}': {}", field, s, e))
+}
+
+/// Busy intervals from the cached events selected by the query. Multi-day events count with
+/// their real span, so an overnight shift blocks the evening and the morning after.
+fn busy_from_events(events: &[Event], query: &AvailabilityQuery, locale: &LocaleSettings) -> Vec<Interval> {
+    calendar::whole_events(events)
+        .iter()
+        .filter(|e| query.calendars.is_empty() || query.calendars.contains(&e.calendar_id))
+        .filter(|e| e.response_status != "declined" && e.transparency != "transparent")
+        .filter_map(|e| {
+            if e.is_all_day {
+                if !query.include_all_day {
+                    return None;
+                }
+                let first = NaiveDate::parse_from_str(&e.start, "%Y-%m-%d").ok()?;
+                let last = NaiveDate::parse_from_str(&e.end, "%Y-%m-%d").unwrap_or(first).max(first);
+                let start = locale.localize(first.and_hms_opt(0, 0, 0)?)?;
+                let end = locale.localize((last + Duration::days(1)).and_hms_opt(0, 0, 0)?)?;
+                Some((start, end))
+            } else {
+                let start = DateTime::parse_from_rfc3339(&e.start).ok()?;
+                let end = DateTime::parse_from_rfc3339(&e.end).ok()?;
+                Some((start, end))
+            }
+        })
+        .collect()
+}
+
+/// Pure slot computation: working hours on working days, minus busy intervals
+pub fn compute_free_slots(
+    busy: &[Interval],
+    query: &AvailabilityQuery,
+    locale: &LocaleSettings,
+) -> Result<Vec<FreeSlot>, String> {
+    let start_date = parse_date(&query.start_date, "start_date")?;
+    let end_date = parse_date(&query.end_date, "end_date")?;
+    let work_start = parse_time(&query.work_start, "work_start")?;
+    let work_end = parse_time(&query.work_end, "work_end")?;
+    if work_end <= work_start {
+        return Err("work_end must be after work_start".to_string());
+    }
+    let min_slot = Duration::minutes(query.min_slot_minutes.max(1));
+
+    let mut busy = busy.to_vec();
+    busy.sort();
+
+    let mut slots = Vec::new();
+    let mut day = start_date;
+    while day <= end_date {
+        if query.working_days.contains(&day.weekday()) {
+            if let (Some(day_start), Some(day_end)) = (
+                locale.localize(day.and_time(work_start)),
+                locale.localize(day.and_time(work_end)),
+            ) {
+                let mut cursor = day_start;
+                for (busy_start, busy_end) in busy.iter().filter(|(s, e)| *e > day_start && *s < day_end) {
+                    if *busy_start > cursor {
+                        push_slot(&mut slots, cursor, (*busy_start).min(day_end), min_slot, locale);
+                    }
+                    cursor = cursor.max(*busy_end);
+                }
+                if cursor < day_end {
+                    push_slot(&mut slots, cursor, day_end, min_slot, locale);
+                }
+            }
+        }
+        day += Duration::days(1);
+    }
+
+    Ok(slots)
+}
+
+fn push_slot(
+    slots: &mut Vec<FreeSlot>,
+    start: DateTime<FixedOffset>,
+    end: DateTime<FixedOffset>,
+    min_slot: Duration,
+    locale: &LocaleSettings,
+) {
+    if end - start < min_slot {
+        return;
+    }
+    slots.push(FreeSlot {
+        date: start.format("%Y-%m-%d").to_string(),
+        start: start.to_rfc3339(),
+        end: end.to_rfc3339(),
+        date_formatted: locale.format_date(start.date_naive()),
+        time_range: format!("{} - {}", locale.format_time(&start), locale.format_time(&end)),
+    });
+}
+
+/// One line per day: "Monday, 19. October: 09:00 - 10:30, 13:00 - 17:00"
+pub fn format_slots(slots: &[FreeSlot]) -> String {
+    let mut lines: Vec<String> = Vec::new();
+    let mut current_date = "";
+    for slot in slots {
+        if slot.date == current_date {
+            if let Some(line) = lines.last_mut() {
+                line.push_str(&format!(", {}", slot.time_range));
+            }
+        } else {
+            current_date = &slot.date;
+            lines.push(format!("{}: {}", slot.date_formatted, slot.time_range));
+        }
+    }
+    lines.join("\n")
+}
+
+/// Busy intervals for other people's calendars from the Google freeBusy endpoint
+async fn query_free_busy(
+    people: &[String],
+    time_min: DateTime<FixedOffset>,
+    time_max: DateTime<FixedOffset>,
+) -> Result<Vec<Interval>, String> {
+    let access_token = get_access_token().await?;
+    let client = reqwest::Client::new();
+
+    let body = serde_json::json!({
+        "timeMin": time_min.to_rfc3339(),
+        "timeMax": time_max.to_rfc3339(),
+        "items": people.iter().map(|id| serde_json::json!({ "id": id })).collect::<Vec<_>>(),
+    });
+
+    let response = client
+        .post(FREEBUSY_URL)
+        .bearer_auth(&access_token)
+        .json(&body)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to query free/busy: {}", e))?;
+
+    if !response.status().is_success() {
+        let error = response.text().await.unwrap_or_default();
+        return Err(format!("Calendar API error: {}", error));
+    }
+
+    let data: FreeBusyResponse = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse free/busy response: {}", e))?;
+
+    let mut busy = Vec::new();
+    for (id, calendar) in data.calendars.unwrap_or_default() {
+        if let Some(errors) = calendar.errors.filter(|e| !e.is_empty()) {
+            return Err(format!("Free/busy unavailable for {}: {:?}", id, errors));
+        }
+        for period in calendar.busy.unwrap_or_default() {
+            if let (Ok(start), Ok(end)) = (
+                DateTime::parse_from_rfc3339(&period.start),
+                DateTime::parse_from_rfc3339(&period.end),
+            ) {
+                busy.push((start, end));
+            }
+        }
+    }
+
+    Ok(busy)
+}
+
+pub async fn find_free_slots(
+    events: &[Event],
+    query: &AvailabilityQuery,
+    locale: &LocaleSettings,
+) -> Result<Availability, String> {
+    let mut busy = busy_from_events(events, query, locale);
+
+    if !query.people.is_empty() {
+        let start_date = parse_date(&query.start_date, "start_date")?;
+        let end_date = parse_date(&query.end_date, "end_date")?;
+        let time_min = start_date
+            .and_hms_opt(0, 0, 0)
+            .and_then(|d| locale.localize(d))
+            .ok_or_else(|| "Invalid start_date".to_string())?;
+        let time_max = (end_date + Duration::days(1))
+            .and_hms_opt(0, 0, 0)
+            .and_then(|d| locale.localize(d))
+            .ok_or_else(|| "Invalid end_date".to_string())?;
+        busy.extend(query_free_busy(&query.people, time_min, time_max).await?);
+    }
+
+    let slots = compute_free_slots(&busy, query, locale)?;
+    let text = format_slots(&slots);
+    Ok(Availability { slots, text })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn locale() -> LocaleSettings {
+        LocaleSettings {
+            timezone: "Europe/Oslo".to_string(),
+            ..LocaleSettings::default()
+        }
+    }
+
+    /// Wednesday 14 to Saturday 17 October
+    fn query() -> AvailabilityQuery {
+        AvailabilityQuery {
+            start_date: "2026-10-14".to_string(),
+            end_date: "2026-10-17".to_string(),
+            ..AvailabilityQuery::default()
+        }
+    }
+
+    /// Timed event in Oslo time (+02:00)
+    fn event(id: &str, start: &str, end: &str) -> Event {
+        Event {
+            id: id.to_string(),
+            calendar_id: "work".to_string(),
+            date: start[..10].to_string(),
+            start: format!("{}:00+02:00", start),
+            end: format!("{}:00+02:00", end),
+            ..Event::default()
+        }
+    }
+
+    fn slots(events: &[Event], query: &AvailabilityQuery) -> Vec<String> {
+        let busy = busy_from_events(events, query, &locale());
+        compute_free_slots(&busy, query, &locale())
+            .unwrap()
+            .iter()
+            .map(|s| format!("{} {}", &s.date[8..], s.time_range))
+            .collect()
+    }
+
+    #[test]
+    fn clips_to_working_hours() {
+        let events = [
+            event("early", "2026-10-14T08:00", "2026-10-14T09:30"),
+            event("late", "2026-10-14T16:30", "2026-10-14T18:00"),
+            event("evening", "2026-10-15T18:00", "2026-10-15T20:00"),
+        ];
+        // The weekend is skipped
+        assert_eq!(slots(&events, &query()), ["14 09:30 - 16:30", "15 09:00 - 17:00", "16 09:00 - 17:00"]);
+
+        let query = AvailabilityQuery {
+            work_start: "17:00".to_string(),
+            ..query()
+        };
+        assert!(compute_free_slots(&[], &query, &locale()).is_err());
+    }
+
+    #[test]
+    fn drops_gaps_shorter_than_the_minimum() {
+        let events = [
+            event("a", "2026-10-14T09:00", "2026-10-14T10:00"),
+            event("b", "2026-10-14T10:20", "2026-10-14T16:40"),
+        ];
+        let query = AvailabilityQuery {
+            end_date: "2026-10-14".to_string(),
+            ..query()
+        };
+        assert!(slots(&events, &query).is_empty());
+
+        let short = AvailabilityQuery {
+            min_slot_minutes: 20,
+            ..query
+        };
+        assert_eq!(slots(&events, &short), ["14 10:00 - 10:20", "14 16:40 - 17:00"]);
+    }
+
+    #[test]
+    fn free_and_declined_events_do_not_block() {
+        let mut focus = event("focus", "2026-10-14T09:00", "2026-10-14T12:00");
+        focus.transparency = "transparent".to_string();
+        let mut declined = event("declined", "2026-10-14T13:00", "2026-10-14T15:00");
+        declined.response_status = "declined".to_string();
+        let mut other = event("other", "2026-10-14T15:00", "2026-10-14T16:00");
+        other.calendar_id = "family".to_string();
+
+        let query = AvailabilityQuery {
+            end_date: "2026-10-14".to_string(),
+            ..query()
+        };
+        let events = [focus, declined, other];
+        assert_eq!(slots(&events, &query), ["14 09:00 - 15:00", "14 16:00 - 17:00"]);
+
+        // Only the selected calendars count
+        let work_only = AvailabilityQuery {
+            calendars: vec!["work".to_string()],
+            ..query
+        };
+        assert_eq!(slots(&events, &work_only), ["14 09:00 - 17:00"]);
+    }
+
+    #[test]
+    fn multi_day_events_block_their_whole_span() {
+        // 15:00 on the 14th to 11:00 on the 15th, cached as an all-day copy per day
+        let trip: Vec<Event> = ["2026-10-14", "2026-10-15"]
+            .iter()
+            .map(|day| Event {
+                date: day.to_string(),
+                start: day.to_string(),
+                end: day.to_string(),
+                is_all_day: true,
+                span_start: "2026-10-14T15:00:00+02:00".to_string(),
+                span_end: "2026-10-15T11:00:00+02:00".to_string(),
+                ..event("trip", "2026-10-14T15:00", "2026-10-15T11:00")
+            })
+            .collect();
+        assert_eq!(slots(&trip, &query()), ["14 09:00 - 15:00", "15 11:00 - 17:00", "16 09:00 - 17:00"]);
+
+        // All-day events only block when asked to
+        let holiday = Event {
+            start: "2026-10-15".to_string(),
+            end: "2026-10-15".to_string(),
+            is_all_day: true,
+            ..event("holiday", "2026-10-15T00:00", "2026-10-15T00:00")
+        };
+        assert_eq!(slots(std::slice::from_ref(&holiday), &query()).len(), 3);
+        let blocking = AvailabilityQuery {
+            include_all_day: true,
+            ..query()
+        };
+        assert_eq!(slots(&[holiday], &blocking), ["14 09:00 - 17:00", "16 09:00 - 17:00"]);
+    }
+}
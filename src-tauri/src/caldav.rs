@@ -0,0 +1,746 @@
+//! CalDAV calendars (Nextcloud, Fastmail, iCloud, Radicale, ...): discovery over PROPFIND,
+//! time-range queries over REPORT, and ctag/ETag checks so unchanged events aren't downloaded again.
+
+use async_trait::async_trait;
+use chrono::{DateTime, FixedOffset, Utc};
+use quick_xml::events::Event as XmlEvent;
+use quick_xml::Reader;
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
+use url::Url;
+
+use crate::calendar::{self, Calendar, Event};
+use crate::ics;
+use crate::provider::CalendarProvider;
+use crate::settings::{CalDavAccount, Settings};
+
+const PRINCIPAL_PROPFIND: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<d:propfind xmlns:d="DAV:" xmlns:c="urn:ietf:params:xml:ns:caldav">
+  <d:prop>
+    <d:current-user-principal/>
+    <c:calendar-home-set/>
+    <d:resourcetype/>
+  </d:prop>
+</d:propfind>"#;
+
+const CALENDARS_PROPFIND: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<d:propfind xmlns:d="DAV:" xmlns:c="urn:ietf:params:xml:ns:caldav" xmlns:cs="http://calendarserver.org/ns/" xmlns:ic="http://apple.com/ns/ical/">
+  <d:prop>
+    <d:resourcetype/>
+    <d:displayname/>
+    <cs:getctag/>
+    <ic:calendar-color/>
+    <c:supported-calendar-component-set/>
+  </d:prop>
+</d:propfind>"#;
+
+/// One `<d:response>` from a multistatus body, keeping only properties reported with 200
+#[derive(Debug, Default)]
+pub struct DavResponse {
+    pub href: String,
+    pub props: HashMap<String, DavProp>,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct DavProp {
+    pub text: String,
+    /// `<d:href>` values inside the property
+    pub hrefs: Vec<String>,
+    /// Local names of child elements, e.g. "calendar" inside `resourcetype`
+    pub children: Vec<String>,
+    /// `name` attributes of `<c:comp>` children
+    pub comps: Vec<String>,
+}
+
+impl DavResponse {
+    pub fn text(&self, prop: &str) -> Option<&str> {
+        self.props
+            .get(prop)
+            .map(|p| p.text.trim())
+            .filter(|t| !t.is_empty())
+    }
+
+    pub fn href_of(&self, prop: &str) -> Option<&str> {
+        self.props.get(prop).and_then(|p| p.hrefs.first()).map(|h| h.as_str())
+    }
+
+    pub fn has_child(&self, prop: &str, child: &str) -> bool {
+        self.props.get(prop).is_some_and(|p| p.children.iter().any(|c| c == child))
+    }
+}
+
+/// What was downloaded for one calendar, reused while its ctag and ETags stay the same
+struct CalendarState {
+    ctag: String,
+    window: (String, String),
+    /// href -> (ETag, iCalendar data)
+    resources: HashMap<String, (String, String)>,
+}
+
+static SYNC_STATE: LazyLock<Mutex<HashMap<String, CalendarState>>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+
+pub struct DavClient {
+    http: reqwest::Client,
+    username: String,
+    password: String,
+}
+
+impl DavClient {
+    pub fn new(username: &str, password: &str) -> Self {
+        DavClient {
+            http: reqwest::Client::new(),
+            username: username.to_string(),
+            password: password.to_string(),
+        }
+    }
+
+    /// Sends a PROPFIND or REPORT and parses the multistatus reply
+    pub async fn request(&self, method: &str, url: &Url, depth: &str, body: String) -> Result<Vec<DavResponse>, String> {
+        let method = reqwest::Method::from_bytes(method.as_bytes()).map_err(|e| e.to_string())?;
+        let response = self
+            .http
+            .request(method.clone(), url.clone())
+            .basic_auth(&self.username, Some(&self.password))
+            .header("Depth", depth)
+            .header("Content-Type", "application/xml; charset=utf-8")
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| format!("CalDAV {} {} failed: {}", method, url, e))?;
+
+        let status = response.status();
+        if status == reqwest::StatusCode::UNAUTHORIZED {
+            return Err(format!("CalDAV server rejected the credentials for {}", self.username));
+        }
+        if !status.is_success() {
+            let error = response.text().await.unwrap_or_default();
+            return Err(format!("CalDAV error {} from {}: {}", status, url, error));
+        }
+
+        let text = response
+            .text()
+            .await
+            .map_err(|e| format!("Failed to read CalDAV response: {}", e))?;
+        parse_multistatus(&text)
+    }
+
+    /// Downloads one calendar object with its ETag, empty if the server sent none
+    pub async fn get(&self, url: &Url) -> Result<(String, String), String> {
+        let response = self
+            .http
+            .get(url.clone())
+            .basic_auth(&self.username, Some(&self.password))
+            .send()
+            .await
+            .map_err(|e| format!("CalDAV GET {} failed: {}", url, e))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let error = response.text().await.unwrap_or_default();
+            return Err(format!("CalDAV error {} from {}: {}", status, url, error));
+        }
+
+        let etag = response
+            .headers()
+            .get(reqwest::header::ETAG)
+            .and_then(|e| e.to_str().ok())
+            .unwrap_or_default()
+            .to_string();
+        let data = response
+            .text()
+            .await
+            .map_err(|e| format!("Failed to read CalDAV response: {}", e))?;
+        Ok((etag, data))
+    }
+
+    /// Replaces an iCalendar object, but only if nobody changed it since it was read as `etag`.
+    /// Without an ETag there is nothing to check against, so the write is refused.
+    pub async fn put(&self, url: &Url, etag: &str, data: String) -> Result<(), String> {
+        if etag.is_empty() {
+            return Err(format!("The server gave no version for {}, so it was left unchanged", url));
+        }
+        let request = self
+            .http
+            .put(url.clone())
+            .basic_auth(&self.username, Some(&self.password))
+            .header("Content-Type", "text/calendar; charset=utf-8")
+            .header(reqwest::header::IF_MATCH, etag)
+            .body(data);
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| format!("CalDAV PUT {} failed: {}", url, e))?;
+
+        let status = response.status();
+        if status == reqwest::StatusCode::PRECONDITION_FAILED {
+            return Err("The item was changed on the server; refresh and try again".to_string());
+        }
+        if !status.is_success() {
+            let error = response.text().await.unwrap_or_default();
+            return Err(format!("CalDAV error {} from {}: {}", status, url, error));
+        }
+
+        Ok(())
+    }
+}
+
+/// Parses a `DAV:multistatus` document. Namespaces are ignored and elements matched by local name.
+pub fn parse_multistatus(xml: &str) -> Result<Vec<DavResponse>, String> {
+    let mut reader = Reader::from_str(xml);
+    let mut responses = Vec::new();
+
+    let mut stack: Vec<String> = Vec::new();
+    let mut text = String::new();
+    let mut response: Option<DavResponse> = None;
+    let mut propstat_props: HashMap<String, DavProp> = HashMap::new();
+    let mut propstat_status = String::new();
+    let mut current_prop: Option<(String, DavProp)> = None;
+
+    loop {
+        let event = reader
+            .read_event()
+            .map_err(|e| format!("Invalid CalDAV response at {}: {}", reader.buffer_position(), e))?;
+        match event {
+            XmlEvent::Start(ref e) | XmlEvent::Empty(ref e) => {
+                let name = String::from_utf8_lossy(e.local_name().as_ref()).to_string();
+                let is_empty = matches!(event, XmlEvent::Empty(_));
+                text.clear();
+
+                if name == "response" {
+                    response = Some(DavResponse::default());
+                } else if stack.last().map(|s| s.as_str()) == Some("prop") {
+                    current_prop = Some((name.clone(), DavProp::default()));
+                } else if let Some((_, prop)) = current_prop.as_mut() {
+                    if name == "comp" {
+                        for attr in e.attributes().flatten() {
+                            if attr.key.local_name().as_ref() == b"name" {
+                                prop.comps.push(String::from_utf8_lossy(&attr.value).to_string());
+                            }
+                        }
+                    }
+                    prop.children.push(name.clone());
+                }
+
+                if is_empty {
+                    // An empty element closes right away
+                    close_element(&name, &stack, "", &mut current_prop, &mut propstat_props);
+                } else {
+                    stack.push(name);
+                }
+            }
+            XmlEvent::Text(e) => {
+                let decoded = e.decode().map_err(|e| e.to_string())?;
+                push_text(&decoded, &mut text, &mut current_prop, &stack);
+            }
+            XmlEvent::CData(e) => {
+                let decoded = String::from_utf8_lossy(&e.into_inner()).to_string();
+                push_text(&decoded, &mut text, &mut current_prop, &stack);
+            }
+            XmlEvent::GeneralRef(e) => {
+                let resolved = match e.resolve_char_ref().map_err(|e| e.to_string())? {
+                    Some(c) => c.to_string(),
+                    None => match e.decode().map_err(|e| e.to_string())?.as_ref() {
+                        "lt" => "<".to_string(),
+                        "gt" => ">".to_string(),
+                        "amp" => "&".to_string(),
+                        "quot" => "\"".to_string(),
+                        "apos" => "'".to_string(),
+                        other => format!("&{};", other),
+                    },
+                };
+                push_text(&resolved, &mut text, &mut current_prop, &stack);
+            }
+            XmlEvent::End(_) => {
+                let name = stack.pop().unwrap_or_default();
+                match name.as_str() {
+                    "href" if current_prop.is_none() && stack.last().map(|s| s.as_str()) == Some("response") => {
+                        if let Some(response) = response.as_mut() {
+                            response.href = text.trim().to_string();
+                        }
+                    }
+                    "status" if current_prop.is_none() => propstat_status = text.trim().to_string(),
+                    "propstat" => {
+                        let props = std::mem::take(&mut propstat_props);
+                        // Properties the server doesn't have come back as 404 and are dropped
+                        if propstat_status.contains(" 200") {
+                            if let Some(response) = response.as_mut() {
+                                response.props.extend(props);
+                            }
+                        }
+                        propstat_status.clear();
+                    }
+                    "response" => {
+                        if let Some(response) = response.take() {
+                            responses.push(response);
+                        }
+                    }
+                    _ => close_element(&name, &stack, text.trim(), &mut current_prop, &mut propstat_props),
+                }
+                text.clear();
+            }
+            XmlEvent::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(responses)
+}
+
+fn push_text(value: &str, text: &mut String, current_prop: &mut Option<(String, DavProp)>, stack: &[String]) {
+    text.push_str(value);
+    if let Some((name, prop)) = current_prop.as_mut() {
+        // Only direct text of the property itself, not of nested hrefs
+        if stack.last() == Some(name) {
+            prop.text.push_str(value);
+        }
+    }
+}
+
+fn close_element(
+    name: &str,
+    stack: &[String],
+    text: &str,
+    current_prop: &mut Option<(String, DavProp)>,
+    propstat_props: &mut HashMap<String, DavProp>,
+) {
+    let Some((prop_name, prop)) = current_prop.as_mut() else {
+        return;
+    };
+    if name == prop_name && stack.last().map(|s| s.as_str()) == Some("prop") {
+        if let Some((prop_name, prop)) = current_prop.take() {
+            propstat_props.insert(prop_name, prop);
+        }
+    } else if name == "href" && !text.is_empty() {
+        prop.hrefs.push(text.to_string());
+    }
+}
+
+pub fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// CalDAV time-range format, always UTC
+pub fn caldav_time(dt: DateTime<FixedOffset>) -> String {
+    dt.with_timezone(&Utc).format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+pub fn resolve_href(base: &Url, href: &str) -> Result<Url, String> {
+    base.join(href).map_err(|e| format!("Invalid href '{}': {}", href, e))
+}
+
+/// Finds the calendar collections for an account. The configured URL may point at the server
+/// root, the principal, the calendar home or a single calendar.
+pub async fn discover_collections(client: &DavClient, url: &str) -> Result<Vec<(Url, DavResponse)>, String> {
+    let base = Url::parse(url).map_err(|e| format!("Invalid CalDAV URL '{}': {}", url, e))?;
+
+    let root = client.request("PROPFIND", &base, "0", PRINCIPAL_PROPFIND.to_string()).await?;
+    let root = root.into_iter().next().unwrap_or_default();
+
+    let home = if root.has_child("resourcetype", "calendar") {
+        // Already a calendar; list it on its own
+        None
+    } else if let Some(home) = root.href_of("calendar-home-set") {
+        Some(resolve_href(&base, home)?)
+    } else if let Some(principal) = root.href_of("current-user-principal") {
+        let principal = resolve_href(&base, principal)?;
+        let found = client.request("PROPFIND", &principal, "0", PRINCIPAL_PROPFIND.to_string()).await?;
+        let home = found
+            .iter()
+            .find_map(|r| r.href_of("calendar-home-set"))
+            .map(|h| h.to_string());
+        Some(match home {
+            Some(home) => resolve_href(&principal, &home)?,
+            None => principal,
+        })
+    } else {
+        Some(base.clone())
+    };
+
+    let (collection_url, depth) = match &home {
+        Some(home) => (home.clone(), "1"),
+        None => (base.clone(), "0"),
+    };
+    let listing = client
+        .request("PROPFIND", &collection_url, depth, CALENDARS_PROPFIND.to_string())
+        .await?;
+
+    let mut collections = Vec::new();
+    for response in listing {
+        if !response.has_child("resourcetype", "calendar") {
+            continue;
+        }
+        let url = resolve_href(&collection_url, &response.href)?;
+        collections.push((url, response));
+    }
+    Ok(collections)
+}
+
+/// Display name of a collection, or the last segment of its URL
+pub fn collection_name(url: &Url, response: &DavResponse) -> String {
+    response
+        .text("displayname")
+        .map(|n| n.to_string())
+        .unwrap_or_else(|| {
+            url.path_segments()
+                .and_then(|mut s| s.rfind(|p| !p.is_empty()))
+                .unwrap_or("Calendar")
+                .to_string()
+        })
+}
+
+/// Whether a collection holds the given component type. Servers that don't list their
+/// supported components are assumed to take any.
+pub fn supports_component(response: &DavResponse, component: &str) -> bool {
+    response
+        .props
+        .get("supported-calendar-component-set")
+        .map(|p| p.comps.is_empty() || p.comps.iter().any(|c| c == component))
+        .unwrap_or(true)
+}
+
+/// Everything downloaded for a calendar before, when its ctag says nothing changed since.
+/// Servers without a ctag are always listed again.
+fn unchanged_objects(
+    state: &HashMap<String, CalendarState>,
+    url: &Url,
+    ctag: &str,
+    window: &(String, String),
+) -> Option<Vec<String>> {
+    let cached = state.get(url.as_str())?;
+    (!ctag.is_empty() && cached.ctag == ctag && cached.window == *window)
+        .then(|| cached.resources.values().map(|(_, data)| data.clone()).collect())
+}
+
+/// Splits an ETag listing into the resources that can be kept from `previous` and the hrefs
+/// that are new or changed and have to be downloaded. Deleted resources drop out.
+fn split_by_etag(
+    listed: &[DavResponse],
+    mut previous: HashMap<String, (String, String)>,
+) -> (HashMap<String, (String, String)>, Vec<String>) {
+    let mut resources = HashMap::new();
+    let mut changed = Vec::new();
+    for response in listed {
+        let etag = response.text("getetag").unwrap_or_default().to_string();
+        match previous.remove(&response.href) {
+            Some((old_etag, data)) if !etag.is_empty() && old_etag == etag => {
+                resources.insert(response.href.clone(), (etag, data));
+            }
+            _ => changed.push(response.href.clone()),
+        }
+    }
+    (resources, changed)
+}
+
+pub struct CalDavProvider {
+    account: CalDavAccount,
+}
+
+impl CalDavProvider {
+    pub fn new(account: CalDavAccount) -> Self {
+        CalDavProvider { account }
+    }
+
+    fn to_calendar(&self, url: &Url, response: &DavResponse) -> Calendar {
+        let name = collection_name(url, response);
+        // Apple's calendar-color may carry an alpha channel: #RRGGBBAA
+        let color = response
+            .text("calendar-color")
+            .map(|c| c.chars().take(7).collect::<String>())
+            .unwrap_or_else(|| "#3b82f6".to_string());
+        Calendar {
+            id: url.to_string(),
+            name,
+            color,
+            foreground_color: "#ffffff".to_string(),
+            primary: false,
+            writable: false,
+        }
+    }
+
+    /// iCalendar objects in the window, downloading only those whose ETag changed
+    async fn sync_calendar(
+        &self,
+        client: &DavClient,
+        url: &Url,
+        ctag: &str,
+        window: (String, String),
+    ) -> Result<Vec<String>, String> {
+        if let Some(objects) = unchanged_objects(&SYNC_STATE.lock().unwrap(), url, ctag, &window) {
+            return Ok(objects);
+        }
+
+        let query = format!(
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<c:calendar-query xmlns:d="DAV:" xmlns:c="urn:ietf:params:xml:ns:caldav">
+  <d:prop>
+    <d:getetag/>
+  </d:prop>
+  <c:filter>
+    <c:comp-filter name="VCALENDAR">
+      <c:comp-filter name="VEVENT">
+        <c:time-range start="{}" end="{}"/>
+      </c:comp-filter>
+    </c:comp-filter>
+  </c:filter>
+</c:calendar-query>"#,
+            window.0, window.1
+        );
+        let listed = client.request("REPORT", url, "1", query).await?;
+
+        let previous = SYNC_STATE
+            .lock()
+            .unwrap()
+            .remove(url.as_str())
+            .filter(|state| state.window == window)
+            .map(|state| state.resources)
+            .unwrap_or_default();
+
+        let (mut resources, changed) = split_by_etag(&listed, previous);
+        if !changed.is_empty() {
+            let hrefs: String = changed
+                .iter()
+                .map(|h| format!("  <d:href>{}</d:href>\n", xml_escape(h)))
+                .collect();
+            // Let the server expand recurring events into instances within the window
+            let multiget = format!(
+                r#"<?xml version="1.0" encoding="utf-8"?>
+<c:calendar-multiget xmlns:d="DAV:" xmlns:c="urn:ietf:params:xml:ns:caldav">
+  <d:prop>
+    <d:getetag/>
+    <c:calendar-data>
+      <c:expand start="{}" end="{}"/>
+    </c:calendar-data>
+  </d:prop>
+{}</c:calendar-multiget>"#,
+                window.0, window.1, hrefs
+            );
+            for response in client.request("REPORT", url, "1", multiget).await? {
+                if let Some(data) = response.text("calendar-data") {
+                    let etag = response.text("getetag").unwrap_or_default().to_string();
+                    resources.insert(response.href.clone(), (etag, data.to_string()));
+                }
+            }
+        }
+
+        let data = resources.values().map(|(_, data)| data.clone()).collect();
+        SYNC_STATE.lock().unwrap().insert(
+            url.to_string(),
+            CalendarState {
+                ctag: ctag.to_string(),
+                window,
+                resources,
+            },
+        );
+        Ok(data)
+    }
+}
+
+#[async_trait]
+impl CalendarProvider for CalDavProvider {
+    fn id(&self) -> String {
+        format!("caldav:{}", self.account.name)
+    }
+
+    async fn get_events(&self, days: i32, settings: &Settings) -> Result<Vec<Event>, String> {
+        let client = DavClient::new(&self.account.username, &self.account.password);
+        let collections = discover_collections(&client, &self.account.url).await?;
+
+        let (time_min, time_max) = calendar::event_window(days, &settings.locale);
+        let window = (caldav_time(time_min), caldav_time(time_max));
+        let self_email = if self.account.email.is_empty() {
+            &self.account.username
+        } else {
+            &self.account.email
+        };
+
+        let mut all_events = Vec::new();
+        for (url, response) in collections {
+            if !supports_component(&response, "VEVENT") {
+                continue;
+            }
+
+            let calendar = self.to_calendar(&url, &response);
+            if !self.account.calendars.is_empty() && !self.account.calendars.contains(&calendar.name) {
+                continue;
+            }
+
+            let ctag = response.text("getctag").unwrap_or_default();
+            let objects = match self.sync_calendar(&client, &url, ctag, window.clone()).await {
+                Ok(objects) => objects,
+                Err(e) => {
+                    eprintln!("Failed to fetch events from {}: {}", calendar.name, e);
+                    continue;
+                }
+            };
+
+            for data in objects {
+                // Servers that ignore <c:expand> return the series master, which is expanded here
+                match ics::events_from_ics(&data, &calendar, (time_min, time_max), self_email, settings) {
+                    Ok(events) => all_events.extend(events),
+                    Err(e) => eprintln!("Skipping unreadable event in {}: {}", calendar.name, e),
+                }
+            }
+        }
+
+        calendar::sort_events(&mut all_events);
+        Ok(all_events)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NEXTCLOUD_CALENDARS: &str = include_str!("../tests/fixtures/caldav/nextcloud_calendars.xml");
+    const NEXTCLOUD_ETAGS: &str = include_str!("../tests/fixtures/caldav/nextcloud_etags.xml");
+    const NEXTCLOUD_MULTIGET: &str = include_str!("../tests/fixtures/caldav/nextcloud_multiget.xml");
+    const RADICALE_CALENDARS: &str = include_str!("../tests/fixtures/caldav/radicale_calendars.xml");
+    const ICLOUD_CALENDARS: &str = include_str!("../tests/fixtures/caldav/icloud_calendars.xml");
+    const ICLOUD_MULTIGET: &str = include_str!("../tests/fixtures/caldav/icloud_multiget.xml");
+
+    /// The calendar collections of a PROPFIND listing, as `discover_collections` picks them
+    fn calendars(xml: &str, base: &str) -> Vec<(Url, DavResponse)> {
+        let base = Url::parse(base).unwrap();
+        parse_multistatus(xml)
+            .unwrap()
+            .into_iter()
+            .filter(|r| r.has_child("resourcetype", "calendar"))
+            .map(|r| (resolve_href(&base, &r.href).unwrap(), r))
+            .collect()
+    }
+
+    fn provider() -> CalDavProvider {
+        CalDavProvider::new(CalDavAccount::default())
+    }
+
+    #[test]
+    fn nextcloud_collections() {
+        let responses = parse_multistatus(NEXTCLOUD_CALENDARS).unwrap();
+        assert_eq!(responses.len(), 4);
+        // Properties reported as 404 are dropped
+        assert!(responses[0].props.keys().eq(["resourcetype"].iter()));
+
+        let found = calendars(NEXTCLOUD_CALENDARS, "https://cloud.example.com/remote.php/dav/calendars/alice/");
+        assert_eq!(found.len(), 2);
+
+        let (url, personal) = &found[0];
+        assert_eq!(url.as_str(), "https://cloud.example.com/remote.php/dav/calendars/alice/personal/");
+        assert_eq!(personal.text("getctag"), Some("http://sabre.io/ns/sync/42"));
+        assert!(supports_component(personal, "VEVENT"));
+        assert!(!supports_component(personal, "VTODO"));
+        let calendar = provider().to_calendar(url, personal);
+        assert_eq!((calendar.name.as_str(), calendar.color.as_str()), ("Personal", "#0082c9"));
+
+        let (url, tasks) = &found[1];
+        assert_eq!(collection_name(url, tasks), "Tasks & chores");
+        assert!(supports_component(tasks, "VTODO"));
+        assert!(!supports_component(tasks, "VEVENT"));
+    }
+
+    #[test]
+    fn radicale_collections() {
+        let found = calendars(RADICALE_CALENDARS, "http://localhost:5232/alice/");
+        assert_eq!(found.len(), 2);
+
+        let (url, work) = &found[0];
+        assert_eq!(work.text("getctag"), Some("\"a5f4c2e1b8d3a6f9c0e7b4d1a8f5c2e9\""));
+        assert!(supports_component(work, "VEVENT") && supports_component(work, "VTODO"));
+        // The alpha channel is dropped
+        assert_eq!(provider().to_calendar(url, work).color, "#ff8800");
+
+        // Without a display name or component set: named after the URL, holding anything
+        let (url, birthdays) = &found[1];
+        assert_eq!(collection_name(url, birthdays), "birthdays");
+        assert!(supports_component(birthdays, "VEVENT"));
+        assert_eq!(provider().to_calendar(url, birthdays).color, "#3b82f6");
+    }
+
+    #[test]
+    fn icloud_collections() {
+        let found = calendars(ICLOUD_CALENDARS, "https://p42-caldav.icloud.com/123456789/calendars/");
+        assert_eq!(found.len(), 2);
+
+        let (url, home) = &found[0];
+        assert_eq!(url.as_str(), "https://p42-caldav.icloud.com/123456789/calendars/home/");
+        assert_eq!(home.text("getctag"), Some("HwoQEgwAAEd2UMTXOQAAAAEYAhgAIhUIkOfH/Oi8u7XWARDn0fmZ6p6h3vYBKAA="));
+        assert_eq!(provider().to_calendar(url, home).color, "#1BADF8");
+        assert!(supports_component(home, "VEVENT"));
+
+        let (url, reminders) = &found[1];
+        assert_eq!(collection_name(url, reminders), "Reminders");
+        assert!(!supports_component(reminders, "VEVENT"));
+    }
+
+    #[test]
+    fn calendar_data() {
+        // Nextcloud escapes the data, carriage returns included
+        let responses = parse_multistatus(NEXTCLOUD_MULTIGET).unwrap();
+        assert_eq!(responses.len(), 2);
+        let data = responses[0].text("calendar-data").unwrap();
+        assert!(data.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+        assert!(data.contains("SUMMARY:Standup <team>\r\n"));
+        assert_eq!(responses[0].text("getetag"), Some("\"5d1e0b1c7f3a2e9d8c4b6a1f0e2d3c4b\""));
+        // A resource deleted in the meantime has nothing but its status
+        assert_eq!(responses[1].href, "/remote.php/dav/calendars/alice/personal/gone.ics");
+        assert!(responses[1].props.is_empty());
+
+        // iCloud wraps it in CDATA
+        let responses = parse_multistatus(ICLOUD_MULTIGET).unwrap();
+        let data = responses[0].text("calendar-data").unwrap();
+        assert!(data.starts_with("BEGIN:VCALENDAR\n"));
+        assert!(data.contains("SUMMARY:Cabin trip\n"));
+        assert_eq!(responses[0].text("getetag"), Some("\"mfwy8l2c\""));
+    }
+
+    #[test]
+    fn downloads_only_changed_etags() {
+        let listed = parse_multistatus(NEXTCLOUD_ETAGS).unwrap();
+        let standup = "/remote.php/dav/calendars/alice/personal/standup.ics".to_string();
+        let dentist = "/remote.php/dav/calendars/alice/personal/dentist.ics".to_string();
+        let removed = "/remote.php/dav/calendars/alice/personal/removed.ics".to_string();
+        let previous = HashMap::from([
+            (standup.clone(), ("\"5d1e0b1c7f3a2e9d8c4b6a1f0e2d3c4b\"".to_string(), "standup".to_string())),
+            (dentist.clone(), ("\"0000\"".to_string(), "old dentist".to_string())),
+            (removed.clone(), ("\"1111\"".to_string(), "removed".to_string())),
+        ]);
+
+        let (kept, changed) = split_by_etag(&listed, previous);
+        assert_eq!(changed, std::slice::from_ref(&dentist));
+        assert_eq!(kept.len(), 1);
+        assert_eq!(kept[&standup].1, "standup");
+
+        // Nothing cached yet: everything is downloaded
+        let (kept, changed) = split_by_etag(&listed, HashMap::new());
+        assert!(kept.is_empty());
+        assert_eq!(changed, [standup, dentist]);
+    }
+
+    #[test]
+    fn unchanged_ctag_reuses_everything() {
+        let found = calendars(NEXTCLOUD_CALENDARS, "https://cloud.example.com/remote.php/dav/calendars/alice/");
+        let (url, personal) = &found[0];
+        let ctag = personal.text("getctag").unwrap();
+        let window = ("20261012T000000Z".to_string(), "20261019T000000Z".to_string());
+        let state = HashMap::from([(
+            url.to_string(),
+            CalendarState {
+                ctag: ctag.to_string(),
+                window: window.clone(),
+                resources: HashMap::from([("a.ics".to_string(), ("\"1\"".to_string(), "data".to_string()))]),
+            },
+        )]);
+
+        assert_eq!(unchanged_objects(&state, url, ctag, &window), Some(vec!["data".to_string()]));
+        assert_eq!(unchanged_objects(&state, url, "http://sabre.io/ns/sync/43", &window), None);
+        // Without a ctag every sync lists the ETags
+        assert_eq!(unchanged_objects(&state, url, "", &window), None);
+        let later = ("20261019T000000Z".to_string(), "20261026T000000Z".to_string());
+        assert_eq!(unchanged_objects(&state, url, ctag, &later), None);
+        assert_eq!(unchanged_objects(&state, &found[1].0, ctag, &window), None);
+    }
+}
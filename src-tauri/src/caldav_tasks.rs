@@ -0,0 +1,405 @@
+//! CalDAV task lists (VTODO), for servers such as Nextcloud, Fastmail or Radicale that keep tasks
+//! next to calendars. Completion writes the VTODO back with If-Match so concurrent edits aren't lost.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use url::Url;
+
+use crate::caldav::{self, DavClient, DavResponse};
+use crate::ics::{self, Component, IcsTime, Property, Timezones};
+use crate::provider::TaskProvider;
+use crate::settings::{CalDavAccount, LocaleSettings, Settings};
+use crate::tasks::{self, Task, TaskFetch};
+
+/// One calendar object resource holding a VTODO
+struct TodoResource {
+    href: String,
+    etag: String,
+    data: String,
+}
+
+/// VTODOs in a collection, optionally narrowed by a filter placed inside the VTODO comp-filter
+async fn fetch_todos(client: &DavClient, url: &Url, filter: &str) -> Result<Vec<TodoResource>, String> {
+    let query = format!(
+        r#"<?xml version="1.0" encoding="utf-8"?>
+<c:calendar-query xmlns:d="DAV:" xmlns:c="urn:ietf:params:xml:ns:caldav">
+  <d:prop>
+    <d:getetag/>
+    <c:calendar-data/>
+  </d:prop>
+  <c:filter>
+    <c:comp-filter name="VCALENDAR">
+      <c:comp-filter name="VTODO">{}</c:comp-filter>
+    </c:comp-filter>
+  </c:filter>
+</c:calendar-query>"#,
+        filter
+    );
+
+    Ok(todo_resources(client.request("REPORT", url, "1", query).await?))
+}
+
+fn todo_resources(responses: Vec<DavResponse>) -> Vec<TodoResource> {
+    responses
+        .into_iter()
+        .filter_map(|response| {
+            let data = response.text("calendar-data")?.to_string();
+            Some(TodoResource {
+                etag: response.text("getetag").unwrap_or_default().to_string(),
+                href: response.href,
+                data,
+            })
+        })
+        .collect()
+}
+
+/// The series master of each VTODO; recurring tasks are shown once
+fn todos(vcalendar: &Component) -> impl Iterator<Item = &Component> {
+    vcalendar
+        .children("VTODO")
+        .filter(|c| c.property("RECURRENCE-ID").is_none() && c.property("UID").is_some())
+}
+
+fn timestamp(vtodo: &Component, name: &str, timezones: &Timezones, locale: &LocaleSettings) -> String {
+    vtodo
+        .property(name)
+        .and_then(|p| IcsTime::parse(p).ok())
+        .and_then(|t| timezones.resolve(&t, locale))
+        .map(|dt| dt.to_rfc3339())
+        .unwrap_or_default()
+}
+
+fn to_task(vtodo: &Component, timezones: &Timezones, tasklist_id: &str, provider_id: &str, locale: &LocaleSettings) -> Task {
+    let due = match vtodo.property("DUE").and_then(|p| IcsTime::parse(p).ok()) {
+        Some(IcsTime::Date(date)) => date.format("%Y-%m-%d").to_string(),
+        Some(time) => timezones
+            .resolve(&time, locale)
+            .map(|dt| locale.to_local(&dt).format("%Y-%m-%d").to_string())
+            .unwrap_or_default(),
+        None => String::new(),
+    };
+
+    // RELATED-TO defaults to a parent relation when RELTYPE is left out
+    let parent = vtodo
+        .properties("RELATED-TO")
+        .find(|p| p.param("RELTYPE").is_none_or(|t| t.eq_ignore_ascii_case("PARENT")))
+        .map(|p| p.value.trim().to_string())
+        .unwrap_or_default();
+
+    let status = vtodo.text("STATUS").unwrap_or_default().to_ascii_uppercase();
+
+    Task {
+        id: vtodo.text("UID").unwrap_or_default(),
+        title: vtodo.text("SUMMARY").unwrap_or_default(),
+        completed: status == "COMPLETED" || vtodo.property("COMPLETED").is_some(),
+        tasklist_id: tasklist_id.to_string(),
+        provider: provider_id.to_string(),
+        notes: vtodo.text("DESCRIPTION").unwrap_or_default(),
+        due,
+        parent,
+        updated: timestamp(vtodo, "LAST-MODIFIED", timezones, locale),
+        web_view_link: vtodo.text("URL").unwrap_or_default(),
+        completed_at: timestamp(vtodo, "COMPLETED", timezones, locale),
+        priority: vtodo
+            .text("PRIORITY")
+            .and_then(|p| p.trim().parse::<u8>().ok())
+            .filter(|p| *p <= 9)
+            .unwrap_or(0),
+        ..Task::default()
+    }
+}
+
+/// Marks the VTODO `task_id` completed at `now` and returns it as a task. Recurring tasks are
+/// refused, as marking the master COMPLETED would close every future occurrence as well.
+fn complete_vtodo(
+    calendars: &mut [Component],
+    task_id: &str,
+    now: DateTime<Utc>,
+    tasklist_id: &str,
+    provider_id: &str,
+    locale: &LocaleSettings,
+) -> Result<Task, String> {
+    let missing = || format!("Task {} no longer exists on the server", task_id);
+    let vcalendar = calendars
+        .iter_mut()
+        .find(|c| c.name == "VCALENDAR" && todos(c).any(|t| t.text("UID").as_deref() == Some(task_id)))
+        .ok_or_else(missing)?;
+    let timezones = Timezones::from_calendar(vcalendar);
+    let vtodo = vcalendar
+        .components
+        .iter_mut()
+        .find(|c| {
+            c.name == "VTODO" && c.property("RECURRENCE-ID").is_none() && c.text("UID").as_deref() == Some(task_id)
+        })
+        .ok_or_else(missing)?;
+
+    if vtodo.property("RRULE").is_some() || vtodo.property("RDATE").is_some() {
+        return Err(format!(
+            "\"{}\" is a recurring task; complete it in your task app so only this occurrence is closed",
+            vtodo.text("SUMMARY").unwrap_or_default()
+        ));
+    }
+
+    let now = IcsTime::Utc(now);
+    vtodo.set_property(Property {
+        name: "STATUS".to_string(),
+        params: Vec::new(),
+        value: "COMPLETED".to_string(),
+    });
+    vtodo.set_property(Property {
+        name: "PERCENT-COMPLETE".to_string(),
+        params: Vec::new(),
+        value: "100".to_string(),
+    });
+    vtodo.set_property(now.to_property("COMPLETED"));
+    vtodo.set_property(now.to_property("LAST-MODIFIED"));
+    vtodo.set_property(now.to_property("DTSTAMP"));
+
+    Ok(to_task(vtodo, &timezones, tasklist_id, provider_id, locale))
+}
+
+pub struct CalDavTaskProvider {
+    account: CalDavAccount,
+}
+
+impl CalDavTaskProvider {
+    pub fn new(account: CalDavAccount) -> Self {
+        CalDavTaskProvider { account }
+    }
+}
+
+#[async_trait]
+impl TaskProvider for CalDavTaskProvider {
+    fn id(&self) -> String {
+        format!("caldav:{}", self.account.name)
+    }
+
+    async fn get_tasks(&self, settings: &Settings) -> Result<TaskFetch, String> {
+        let client = DavClient::new(&self.account.username, &self.account.password);
+        let collections = caldav::discover_collections(&client, &self.account.url).await?;
+        let limit = settings.tasks.max_tasks_per_list;
+        let completed_min = tasks::completed_min(settings).and_then(|m| DateTime::parse_from_rfc3339(&m).ok());
+        let provider_id = self.id();
+
+        let mut fetch = TaskFetch::default();
+        for (url, response) in collections {
+            if !caldav::supports_component(&response, "VTODO") {
+                continue;
+            }
+            let list_name = caldav::collection_name(&url, &response);
+            if !self.account.task_lists.is_empty() && !self.account.task_lists.contains(&list_name) {
+                continue;
+            }
+
+            let resources = match fetch_todos(&client, &url, "").await {
+                Ok(resources) => resources,
+                Err(e) => {
+                    eprintln!("Failed to fetch tasks from {}: {}", list_name, e);
+                    continue;
+                }
+            };
+
+            let mut list_tasks = Vec::new();
+            for resource in resources {
+                let calendars = match ics::parse(&resource.data) {
+                    Ok(calendars) => calendars,
+                    Err(e) => {
+                        eprintln!("Skipping unreadable task {}: {}", resource.href, e);
+                        continue;
+                    }
+                };
+                for vcalendar in calendars.iter().filter(|c| c.name == "VCALENDAR") {
+                    let timezones = Timezones::from_calendar(vcalendar);
+                    for vtodo in todos(vcalendar) {
+                        if vtodo.text("STATUS").is_some_and(|s| s.eq_ignore_ascii_case("CANCELLED")) {
+                            continue;
+                        }
+                        let task = to_task(vtodo, &timezones, url.as_str(), &provider_id, &settings.locale);
+                        if task.title.is_empty() {
+                            continue;
+                        }
+                        if !task.completed {
+                            list_tasks.push(task);
+                        } else if let Some(min) = completed_min {
+                            let recent = DateTime::parse_from_rfc3339(&task.completed_at).is_ok_and(|at| at >= min);
+                            if recent {
+                                fetch.completed.push(task);
+                            }
+                        }
+                    }
+                }
+            }
+
+            // Servers return objects in no particular order: dated tasks first, soonest first, so
+            // the limit cuts off the tasks due last
+            list_tasks.sort_by(|a, b| {
+                (a.due.is_empty(), &a.due, &a.title).cmp(&(b.due.is_empty(), &b.due, &b.title))
+            });
+            if list_tasks.len() > limit {
+                eprintln!(
+                    "Task list {} has more than {} tasks; the rest were skipped",
+                    list_name, limit
+                );
+                list_tasks.truncate(limit);
+                fetch.truncated_lists.push(list_name);
+            }
+            fetch.tasks.extend(tasks::nest_tasks(tasks::flatten_parents(list_tasks)));
+        }
+
+        fetch.completed.sort_by(|a, b| b.completed_at.cmp(&a.completed_at));
+        Ok(fetch)
+    }
+
+    async fn complete_task(&self, task_id: &str, tasklist_id: &str, settings: &Settings) -> Result<Task, String> {
+        let client = DavClient::new(&self.account.username, &self.account.password);
+        let url = Url::parse(tasklist_id).map_err(|e| format!("Invalid task list '{}': {}", tasklist_id, e))?;
+
+        // Read the current version so the write is based on what the server has now. The UID
+        // filter is a substring match, so the resource is picked by its exact UID.
+        let filter = format!(
+            r#"<c:prop-filter name="UID"><c:text-match collation="i;octet">{}</c:text-match></c:prop-filter>"#,
+            caldav::xml_escape(task_id)
+        );
+        let mut resource = fetch_todos(&client, &url, &filter)
+            .await?
+            .into_iter()
+            .find(|resource| {
+                ics::parse(&resource.data).is_ok_and(|calendars| {
+                    calendars
+                        .iter()
+                        .filter(|c| c.name == "VCALENDAR")
+                        .any(|c| todos(c).any(|t| t.text("UID").as_deref() == Some(task_id)))
+                })
+            })
+            .ok_or_else(|| format!("Task {} no longer exists on the server", task_id))?;
+
+        // Some servers leave the ETag out of REPORT replies; a plain GET carries it as a header
+        let resource_url = caldav::resolve_href(&url, &resource.href)?;
+        if resource.etag.is_empty() {
+            (resource.etag, resource.data) = client.get(&resource_url).await?;
+        }
+
+        let mut calendars = ics::parse(&resource.data)?;
+        let task = complete_vtodo(&mut calendars, task_id, Utc::now(), tasklist_id, &self.id(), &settings.locale)?;
+
+        let data: String = calendars.iter().map(|c| c.to_ics()).collect();
+        client.put(&resource_url, &resource.etag, data).await?;
+
+        Ok(task)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NEXTCLOUD_TODOS: &str = include_str!("../tests/fixtures/caldav/nextcloud_todos.xml");
+    const LIST: &str = "https://cloud.example.com/remote.php/dav/calendars/alice/tasks/";
+
+    fn locale() -> LocaleSettings {
+        LocaleSettings {
+            timezone: "Europe/Oslo".to_string(),
+            ..LocaleSettings::default()
+        }
+    }
+
+    fn resources() -> Vec<TodoResource> {
+        todo_resources(caldav::parse_multistatus(NEXTCLOUD_TODOS).unwrap())
+    }
+
+    /// The tasks of every resource, as `get_tasks` reads them
+    fn tasks(locale: &LocaleSettings) -> Vec<Task> {
+        let mut tasks = Vec::new();
+        for resource in resources() {
+            for vcalendar in ics::parse(&resource.data).unwrap() {
+                let timezones = Timezones::from_calendar(&vcalendar);
+                tasks.extend(todos(&vcalendar).map(|t| to_task(t, &timezones, LIST, "caldav:Work", locale)));
+            }
+        }
+        tasks
+    }
+
+    #[test]
+    fn reads_nextcloud_todos() {
+        let resources = resources();
+        let etags: Vec<&str> = resources.iter().map(|r| r.etag.as_str()).collect();
+        assert_eq!(
+            etags,
+            ["\"8f2c1a0d9e7b6c5a4f3e2d1c0b9a8f7e\"", "\"1b2c3d4e5f60718293a4b5c6d7e8f901\"", "\"0a9b8c7d6e5f4a3b2c1d0e9f8a7b6c5d\"", ""]
+        );
+
+        let tasks = tasks(&locale());
+        let summary: Vec<(&str, &str, bool, &str, u8, &str)> = tasks
+            .iter()
+            .map(|t| (t.id.as_str(), t.title.as_str(), t.completed, t.due.as_str(), t.priority, t.parent.as_str()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("report", "Quarterly report", false, "2026-10-16", 1, ""),
+                // RELATED-TO without RELTYPE is the parent; the sibling relation is skipped
+                ("slides", "Slides", false, "2026-10-15", 5, "report"),
+                // 23:00 UTC is past midnight in Oslo; PRIORITY above 9 is invalid
+                ("invoice", "Send invoice", true, "2026-10-15", 0, ""),
+                ("plants", "Water the plants", false, "2026-10-01", 0, ""),
+            ]
+        );
+        assert_eq!(tasks[0].notes, "Numbers from finance, then the summary");
+        assert_eq!(tasks[0].updated, "2026-10-12T09:00:00+00:00");
+        assert_eq!(tasks[2].completed_at, "2026-10-13T10:15:00+00:00");
+        assert!(tasks.iter().all(|t| t.tasklist_id == LIST && t.provider == "caldav:Work"));
+    }
+
+    #[test]
+    fn due_times_follow_the_locale() {
+        let new_york = LocaleSettings {
+            timezone: "America/New_York".to_string(),
+            ..LocaleSettings::default()
+        };
+        let due: Vec<String> = tasks(&new_york).into_iter().map(|t| t.due).collect();
+        // Dates stay as they are, while times fall on the 15th and 14th in New York
+        assert_eq!(due, ["2026-10-16", "2026-10-15", "2026-10-14", "2026-10-01"]);
+    }
+
+    fn complete(index: usize, task_id: &str) -> (Result<Task, String>, Vec<Component>) {
+        let mut calendars = ics::parse(&resources()[index].data).unwrap();
+        let now = DateTime::parse_from_rfc3339("2026-10-14T12:00:00Z").unwrap().with_timezone(&Utc);
+        let result = complete_vtodo(&mut calendars, task_id, now, LIST, "caldav:Work", &locale());
+        (result, calendars)
+    }
+
+    #[test]
+    fn writes_the_completion_into_the_vtodo() {
+        let (task, calendars) = complete(1, "slides");
+        let task = task.unwrap();
+        assert!(task.completed);
+        assert_eq!(task.completed_at, "2026-10-14T12:00:00+00:00");
+        assert_eq!(task.parent, "report");
+
+        // Read back from what would be uploaded
+        let written: String = calendars.iter().map(|c| c.to_ics()).collect();
+        let vcalendar = ics::parse(&written).unwrap().remove(0);
+        let vtodo = todos(&vcalendar).next().unwrap();
+        assert_eq!(vtodo.text("STATUS").as_deref(), Some("COMPLETED"));
+        assert_eq!(vtodo.text("PERCENT-COMPLETE").as_deref(), Some("100"));
+        assert_eq!(vtodo.property("COMPLETED").unwrap().value, "20261014T120000Z");
+        assert_eq!(vtodo.property("LAST-MODIFIED").unwrap().value, "20261014T120000Z");
+        assert_eq!(vtodo.properties("STATUS").count(), 1);
+        // Everything else is kept
+        assert_eq!(vtodo.property("DUE").unwrap().value, "20261015T233000");
+        assert_eq!(vtodo.properties("RELATED-TO").count(), 2);
+    }
+
+    #[test]
+    fn refuses_recurring_and_missing_tasks() {
+        let (result, calendars) = complete(3, "plants");
+        let error = result.unwrap_err();
+        assert!(error.contains("\"Water the plants\" is a recurring task"), "{}", error);
+        // Left untouched
+        assert!(todos(&calendars[0]).next().unwrap().property("STATUS").is_none());
+
+        // The UID filter is a substring match; only the exact UID counts
+        let (result, _) = complete(0, "repo");
+        assert_eq!(result.unwrap_err(), "Task repo no longer exists on the server");
+    }
+}
@@ -1,31 +1,92 @@
 use serde::{Deserialize, Serialize};
-use chrono::{DateTime, Local, Duration, Datelike, NaiveDate};
+use chrono::{DateTime, FixedOffset, Local, Duration, Datelike, NaiveDate, NaiveTime};
+use std::collections::{HashMap, HashSet};
+use tokio::sync::OnceCell;
 
 use crate::auth::get_access_token;
+use crate::provider;
+use crate::recurrence;
+use crate::settings::{LocaleSettings, Settings};
 
 const CALENDAR_API_BASE: &str = "https://www.googleapis.com/calendar/v3";
 
+// Google's event colorId palette, fetched once per run
+static EVENT_COLORS: OnceCell<HashMap<String, ColorDefinition>> = OnceCell::const_new();
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Calendar {
     pub id: String,
     pub name: String,
     pub color: String,
+    pub foreground_color: String,
     pub primary: bool,
+    pub writable: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Event {
     pub id: String,
+    pub calendar_id: String,
+    /// Id of the provider the event came from, see `provider.rs`
+    pub provider: String,
     pub title: String,
     pub date: String,
     pub time: String,
     pub time_range: String,
     pub date_formatted: String,
+    /// RFC 3339 start (or `YYYY-MM-DD` for all-day events), independent of the display locale
+    pub start: String,
+    pub end: String,
+    /// For one day of an event spanning several days, which is cached as an all-day copy per
+    /// day: the whole event's `start` and `end`. Empty otherwise
+    pub span_start: String,
+    pub span_end: String,
+    /// Event colorId background if set, otherwise the calendar's color
     pub color: String,
+    pub text_color: String,
     pub calendar: String,
     pub location: String,
     pub description: String,
     pub is_all_day: bool,
+    pub organizer: String,
+    pub attendees: Vec<Attendee>,
+    /// "confirmed", "tentative" or "cancelled"
+    pub status: String,
+    /// "opaque" (busy) or "transparent" (shown as free)
+    pub transparency: String,
+    /// The current user's own RSVP ("accepted", "declined", ...), empty if not invited
+    pub response_status: String,
+    pub dimmed: bool,
+    /// Other events overlapping this one, as `calendar_id/id`
+    pub conflicts: Vec<String>,
+    /// Id of the series master for instances of a recurring event
+    pub recurring_event_id: String,
+    /// The series' RRULE/EXDATE/RDATE lines, taken from the master event
+    pub recurrence: Vec<String>,
+    /// e.g. "Every weekday" or "Monthly on the 2nd Tuesday"
+    pub recurrence_summary: String,
+    pub conference_links: Vec<ConferenceLink>,
+    /// Best video link for a one-click "Join", including Zoom/Teams links found in the text
+    pub join_url: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Attendee {
+    pub name: String,
+    pub email: String,
+    pub response_status: String,
+    pub is_self: bool,
+    pub is_organizer: bool,
+    pub optional: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConferenceLink {
+    /// "video", "phone", "sip" or "more", as in Google's entryPointType
+    pub kind: String,
+    pub label: String,
+    pub uri: String,
 }
 
 #[derive(Debug, Deserialize)]
@@ -41,22 +102,97 @@ struct CalendarListEntry {
     summary: Option<String>,
     #[serde(rename = "backgroundColor")]
     background_color: Option<String>,
+    #[serde(rename = "foregroundColor")]
+    foreground_color: Option<String>,
     primary: Option<bool>,
+    #[serde(rename = "accessRole")]
+    access_role: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ColorsResponse {
+    event: Option<HashMap<String, ColorDefinition>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ColorDefinition {
+    background: String,
+    foreground: String,
 }
 
 #[derive(Debug, Deserialize)]
 struct EventsListResponse {
     items: Option<Vec<EventEntry>>,
+    #[serde(rename = "nextPageToken")]
+    next_page_token: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
 struct EventEntry {
     id: Option<String>,
+    status: Option<String>,
+    #[serde(rename = "colorId")]
+    color_id: Option<String>,
+    transparency: Option<String>,
     summary: Option<String>,
     start: Option<EventDateTime>,
     end: Option<EventDateTime>,
     location: Option<String>,
     description: Option<String>,
+    attendees: Option<Vec<AttendeeEntry>>,
+    organizer: Option<PersonEntry>,
+    #[serde(rename = "conferenceData")]
+    conference_data: Option<ConferenceData>,
+    #[serde(rename = "hangoutLink")]
+    hangout_link: Option<String>,
+    #[serde(rename = "recurringEventId")]
+    recurring_event_id: Option<String>,
+    recurrence: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AttendeeEntry {
+    email: Option<String>,
+    #[serde(rename = "displayName")]
+    display_name: Option<String>,
+    #[serde(rename = "responseStatus")]
+    response_status: Option<String>,
+    #[serde(rename = "self")]
+    is_self: Option<bool>,
+    organizer: Option<bool>,
+    optional: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PersonEntry {
+    email: Option<String>,
+    #[serde(rename = "displayName")]
+    display_name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ConferenceData {
+    #[serde(rename = "entryPoints")]
+    entry_points: Option<Vec<EntryPoint>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EntryPoint {
+    #[serde(rename = "entryPointType")]
+    entry_point_type: Option<String>,
+    uri: Option<String>,
+    label: Option<String>,
+}
+
+struct EventTime {
+    date: String,
+    time: String,
+    time_range: String,
+    date_formatted: String,
+    start: String,
+    end: String,
+    is_all_day: bool,
+    end_date: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -97,14 +233,7 @@ pub async fn get_calendars() -> Result<Vec<Calendar>, String> {
             .map_err(|e| format!("Failed to parse calendar list: {}", e))?;
 
         if let Some(items) = data.items {
-            for item in items {
-                calendars.push(Calendar {
-                    id: item.id,
-                    name: item.summary.unwrap_or_else(|| "Unnamed".to_string()),
-                    color: item.background_color.unwrap_or_else(|| "#3b82f6".to_string()),
-                    primary: item.primary.unwrap_or(false),
-                });
-            }
+            calendars.extend(items.into_iter().map(to_calendar));
         }
 
         page_token = data.next_page_token;
@@ -116,19 +245,122 @@ pub async fn get_calendars() -> Result<Vec<Calendar>, String> {
     Ok(calendars)
 }
 
-pub async fn get_events(days: i32) -> Result<Vec<Event>, String> {
+pub async fn get_calendar(calendar_id: &str) -> Result<Calendar, String> {
     let access_token = get_access_token().await?;
     let client = reqwest::Client::new();
 
-    let calendars = get_calendars().await?;
+    let url = format!(
+        "{}/users/me/calendarList/{}",
+        CALENDAR_API_BASE,
+        urlencoding::encode(calendar_id)
+    );
+
+    let response = client
+        .get(&url)
+        .bearer_auth(&access_token)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to fetch calendar: {}", e))?;
+
+    if !response.status().is_success() {
+        let error = response.text().await.unwrap_or_default();
+        return Err(format!("Calendar API error: {}", error));
+    }
+
+    let entry: CalendarListEntry = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse calendar: {}", e))?;
+
+    Ok(to_calendar(entry))
+}
+
+fn to_calendar(entry: CalendarListEntry) -> Calendar {
+    let writable = matches!(entry.access_role.as_deref(), Some("owner") | Some("writer"));
+    Calendar {
+        id: entry.id,
+        name: entry.summary.unwrap_or_else(|| "Unnamed".to_string()),
+        color: entry.background_color.unwrap_or_else(|| "#3b82f6".to_string()),
+        foreground_color: entry.foreground_color.unwrap_or_else(|| "#ffffff".to_string()),
+        primary: entry.primary.unwrap_or(false),
+        writable,
+    }
+}
+
+async fn get_event_colors(
+    client: &reqwest::Client,
+    access_token: &str,
+) -> Result<&'static HashMap<String, ColorDefinition>, String> {
+    EVENT_COLORS
+        .get_or_try_init(|| async {
+            let url = format!("{}/colors", CALENDAR_API_BASE);
+            let response = client
+                .get(&url)
+                .bearer_auth(access_token)
+                .send()
+                .await
+                .map_err(|e| format!("Failed to fetch colors: {}", e))?;
+
+            if !response.status().is_success() {
+                let error = response.text().await.unwrap_or_default();
+                return Err(format!("Calendar API error: {}", error));
+            }
+
+            let data: ColorsResponse = response
+                .json()
+                .await
+                .map_err(|e| format!("Failed to parse colors: {}", e))?;
+
+            Ok(data.event.unwrap_or_default())
+        })
+        .await
+}
 
-    // Start from beginning of current week (Monday)
+/// Range every provider loads: from the start of the current week to the end of the day `days` ahead
+pub fn event_window(days: i32, locale: &LocaleSettings) -> (DateTime<FixedOffset>, DateTime<FixedOffset>) {
     let now = Local::now();
-    let days_since_monday = now.weekday().num_days_from_monday() as i64;
-    let start_of_week = now - Duration::days(days_since_monday);
-    let start_of_week = start_of_week.format("%Y-%m-%dT00:00:00%:z").to_string();
+    let days_since_week_start = locale.days_since_week_start(now.weekday());
+    let start_of_week = (now - Duration::days(days_since_week_start))
+        .with_time(NaiveTime::MIN)
+        .earliest()
+        .unwrap_or(now);
+    let end_of_range = (now + Duration::days(days as i64))
+        .with_time(NaiveTime::from_hms_opt(23, 59, 59).unwrap())
+        .latest()
+        .unwrap_or(now);
+    (start_of_week.fixed_offset(), end_of_range.fixed_offset())
+}
 
-    let time_max = (now + Duration::days(days as i64)).format("%Y-%m-%dT23:59:59%:z").to_string();
+/// Builds events from an event resource in the Calendar API's JSON shape, which other
+/// providers map their own formats onto
+pub fn events_from_resource(
+    resource: serde_json::Value,
+    calendar: &Calendar,
+    settings: &Settings,
+) -> Result<Vec<Event>, String> {
+    let entry: EventEntry =
+        serde_json::from_value(resource).map_err(|e| format!("Invalid event resource: {}", e))?;
+    Ok(expand_event(entry, calendar, settings, None, &HashMap::new()))
+}
+
+pub async fn get_events(days: i32, settings: &Settings) -> Result<Vec<Event>, String> {
+    let access_token = get_access_token().await?;
+    let client = reqwest::Client::new();
+
+    let calendars = get_calendars().await?;
+
+    // Per-event colors are cosmetic, so fall back to calendar colors if the palette is unavailable
+    let event_colors = match get_event_colors(&client, &access_token).await {
+        Ok(colors) => Some(colors),
+        Err(e) => {
+            eprintln!("{}", e);
+            None
+        }
+    };
+
+    let (time_min, time_max) = event_window(days, &settings.locale);
+    let start_of_week = time_min.to_rfc3339();
+    let time_max = time_max.to_rfc3339();
 
     let mut all_events = Vec::new();
 
@@ -168,77 +400,85 @@ pub async fn get_events(days: i32) -> Result<Vec<Event>, String> {
         };
 
         if let Some(items) = data.items {
+            // Instances don't carry the RRULE, so look up the masters of any series in range
+            let recurrences = if items.iter().any(|i| i.recurring_event_id.is_some()) {
+                fetch_recurrences(&client, &access_token, &calendar, &start_of_week, &time_max).await
+            } else {
+                HashMap::new()
+            };
+
             for item in items {
-                let (date, time, time_range, date_formatted, is_all_day, end_date) = parse_event_time(&item);
-
-                let event_id = item.id.unwrap_or_default();
-                let title = item.summary.unwrap_or_else(|| "(No title)".to_string());
-                let location = item.location.unwrap_or_default();
-                let description = item.description.unwrap_or_default();
-
-                if let Some(ref end_d) = end_date {
-                    // Multi-day event: expand across each day
-                    if let (Ok(start_naive), Ok(end_naive)) = (
-                        NaiveDate::parse_from_str(&date, "%Y-%m-%d"),
-                        NaiveDate::parse_from_str(end_d, "%Y-%m-%d"),
-                    ) {
-                        let mut current = start_naive;
-                        while current <= end_naive {
-                            let day_str = current.format("%Y-%m-%d").to_string();
-                            let day_formatted = current.format("%A, %d. %B").to_string();
-                            all_events.push(Event {
-                                id: event_id.clone(),
-                                title: title.clone(),
-                                date: day_str,
-                                time: "All day".to_string(),
-                                time_range: "All day".to_string(),
-                                date_formatted: day_formatted,
-                                color: calendar.color.clone(),
-                                calendar: calendar.name.clone(),
-                                location: location.clone(),
-                                description: description.clone(),
-                                is_all_day: true,
-                            });
-                            current += Duration::days(1);
-                        }
-                    } else {
-                        // Fallback: couldn't parse dates, just push the single event
-                        all_events.push(Event {
-                            id: event_id,
-                            title,
-                            date,
-                            time,
-                            time_range,
-                            date_formatted,
-                            color: calendar.color.clone(),
-                            calendar: calendar.name.clone(),
-                            location,
-                            description,
-                            is_all_day,
-                        });
-                    }
-                } else {
-                    // Single-day event
-                    all_events.push(Event {
-                        id: event_id,
-                        title,
-                        date,
-                        time,
-                        time_range,
-                        date_formatted,
-                        color: calendar.color.clone(),
-                        calendar: calendar.name.clone(),
-                        location,
-                        description,
-                        is_all_day,
-                    });
-                }
+                all_events.extend(expand_event(item, &calendar, settings, event_colors, &recurrences));
             }
         }
     }
 
+    sort_events(&mut all_events);
+
+    Ok(all_events)
+}
+
+/// Maps series master ids to their recurrence lines, using a non-expanded listing of the range
+async fn fetch_recurrences(
+    client: &reqwest::Client,
+    access_token: &str,
+    calendar: &Calendar,
+    time_min: &str,
+    time_max: &str,
+) -> HashMap<String, Vec<String>> {
+    let mut recurrences = HashMap::new();
+    let mut page_token: Option<String> = None;
+
+    loop {
+        let mut url = format!(
+            "{}/calendars/{}/events?timeMin={}&timeMax={}&singleEvents=false",
+            CALENDAR_API_BASE,
+            urlencoding::encode(&calendar.id),
+            urlencoding::encode(time_min),
+            urlencoding::encode(time_max)
+        );
+        if let Some(ref token) = page_token {
+            url.push_str(&format!("&pageToken={}", urlencoding::encode(token)));
+        }
+
+        let response = match client.get(&url).bearer_auth(access_token).send().await {
+            Ok(r) if r.status().is_success() => r,
+            Ok(r) => {
+                eprintln!("Error fetching recurring events from {}: {}", calendar.name, r.status());
+                break;
+            }
+            Err(e) => {
+                eprintln!("Failed to fetch recurring events from {}: {}", calendar.name, e);
+                break;
+            }
+        };
+
+        let data: EventsListResponse = match response.json().await {
+            Ok(d) => d,
+            Err(e) => {
+                eprintln!("Failed to parse recurring events from {}: {}", calendar.name, e);
+                break;
+            }
+        };
+
+        for item in data.items.unwrap_or_default() {
+            if let (Some(id), Some(recurrence)) = (item.id, item.recurrence) {
+                recurrences.insert(id, recurrence);
+            }
+        }
+
+        page_token = data.next_page_token;
+        if page_token.is_none() {
+            break;
+        }
+    }
+
+    recurrences
+}
+
+pub fn sort_events(events: &mut [Event]) {
     // Sort by date and time
-    all_events.sort_by(|a, b| {
+    events.sort_by(|a, b| {
         let date_cmp = a.date.cmp(&b.date);
         if date_cmp != std::cmp::Ordering::Equal {
             return date_cmp;
@@ -250,74 +490,708 @@ pub async fn get_events(days: i32) -> Result<Vec<Event>, String> {
         if !a.is_all_day && b.is_all_day {
             return std::cmp::Ordering::Greater;
         }
-        a.time.cmp(&b.time)
+        a.start.cmp(&b.start)
     });
+}
 
-    Ok(all_events)
+/// The event as its provider has it: a day of a multi-day event gets the whole span back
+pub fn whole_event(event: &Event) -> Event {
+    if event.span_start.is_empty() {
+        return event.clone();
+    }
+    Event {
+        date: event.span_start.get(..10).unwrap_or(&event.date).to_string(),
+        start: event.span_start.clone(),
+        end: event.span_end.clone(),
+        span_start: String::new(),
+        span_end: String::new(),
+        is_all_day: NaiveDate::parse_from_str(&event.span_start, "%Y-%m-%d").is_ok(),
+        ..event.clone()
+    }
+}
+
+/// One whole event per calendar and id, merging the per-day copies of multi-day events
+pub fn whole_events<'a>(events: impl IntoIterator<Item = &'a Event>) -> Vec<Event> {
+    let mut seen = HashSet::new();
+    events
+        .into_iter()
+        .filter(|e| seen.insert((e.calendar_id.as_str(), e.id.as_str())))
+        .map(whole_event)
+        .collect()
+}
+
+/// Converts one API event into widget events, one per day for multi-day events.
+/// Returns nothing for events that should not be shown.
+fn expand_event(
+    item: EventEntry,
+    calendar: &Calendar,
+    settings: &Settings,
+    event_colors: Option<&HashMap<String, ColorDefinition>>,
+    recurrences: &HashMap<String, Vec<String>>,
+) -> Vec<Event> {
+    let locale = &settings.locale;
+    let status = item.status.clone().unwrap_or_else(|| "confirmed".to_string());
+    // Cancelled exceptions of recurring series are never shown
+    if status == "cancelled" {
+        return Vec::new();
+    }
+
+    let EventTime { date, time, time_range, date_formatted, start, end, is_all_day, end_date } =
+        parse_event_time(&item, locale);
+
+    let location = item.location.unwrap_or_default();
+    let description = item.description.unwrap_or_default();
+    let attendees: Vec<Attendee> = item
+        .attendees
+        .unwrap_or_default()
+        .into_iter()
+        .map(|a| Attendee {
+            name: a.display_name.unwrap_or_else(|| a.email.clone().unwrap_or_default()),
+            email: a.email.unwrap_or_default(),
+            response_status: a.response_status.unwrap_or_else(|| "needsAction".to_string()),
+            is_self: a.is_self.unwrap_or(false),
+            is_organizer: a.organizer.unwrap_or(false),
+            optional: a.optional.unwrap_or(false),
+        })
+        .collect();
+    let response_status = attendees
+        .iter()
+        .find(|a| a.is_self)
+        .map(|a| a.response_status.clone())
+        .unwrap_or_default();
+    if settings.events.hide_declined && response_status == "declined" {
+        return Vec::new();
+    }
+    let dimmed = settings.events.dim_tentative
+        && (status == "tentative" || response_status == "tentative");
+    let organizer = item
+        .organizer
+        .map(|o| o.display_name.or(o.email).unwrap_or_default())
+        .unwrap_or_default();
+    let conference_links = conference_links(
+        item.conference_data.as_ref(),
+        item.hangout_link.as_deref(),
+        &location,
+        &description,
+    );
+    let join_url = conference_links
+        .iter()
+        .find(|l| l.kind == "video")
+        .map(|l| l.uri.clone())
+        .unwrap_or_default();
+
+    let (color, text_color) = item
+        .color_id
+        .as_ref()
+        .and_then(|id| event_colors.and_then(|colors| colors.get(id)))
+        .map(|c| (c.background.clone(), c.foreground.clone()))
+        .unwrap_or_else(|| (calendar.color.clone(), calendar.foreground_color.clone()));
+
+    let recurring_event_id = item.recurring_event_id.unwrap_or_default();
+    let recurrence = item
+        .recurrence
+        .or_else(|| recurrences.get(&recurring_event_id).cloned())
+        .unwrap_or_default();
+    let recurrence_summary = NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+        .map(|d| recurrence::summarize(&recurrence, d))
+        .unwrap_or_default();
+
+    let event = Event {
+        id: item.id.unwrap_or_default(),
+        calendar_id: calendar.id.clone(),
+        provider: provider::GOOGLE.to_string(),
+        title: item.summary.unwrap_or_else(|| "(No title)".to_string()),
+        date,
+        time,
+        time_range,
+        date_formatted,
+        start,
+        end,
+        span_start: String::new(),
+        span_end: String::new(),
+        color,
+        text_color,
+        calendar: calendar.name.clone(),
+        location,
+        description,
+        is_all_day,
+        organizer,
+        attendees,
+        status,
+        transparency: item.transparency.unwrap_or_else(|| "opaque".to_string()),
+        response_status,
+        dimmed,
+        conflicts: Vec::new(),
+        recurring_event_id,
+        recurrence,
+        recurrence_summary,
+        conference_links,
+        join_url,
+    };
+
+    let mut events = Vec::new();
+    if let Some(ref end_d) = end_date {
+        // Multi-day event: expand across each day
+        if let (Ok(start_naive), Ok(end_naive)) = (
+            NaiveDate::parse_from_str(&event.date, "%Y-%m-%d"),
+            NaiveDate::parse_from_str(end_d, "%Y-%m-%d"),
+        ) {
+            let mut current = start_naive;
+            while current <= end_naive {
+                let day_str = current.format("%Y-%m-%d").to_string();
+                events.push(Event {
+                    date: day_str.clone(),
+                    time: locale.all_day_label.clone(),
+                    time_range: locale.all_day_label.clone(),
+                    date_formatted: locale.format_date(current),
+                    start: day_str.clone(),
+                    end: day_str,
+                    span_start: event.start.clone(),
+                    span_end: event.end.clone(),
+                    is_all_day: true,
+                    ..event.clone()
+                });
+                current += Duration::days(1);
+            }
+        } else {
+            // Fallback: couldn't parse dates, just push the single event
+            events.push(event);
+        }
+    } else {
+        // Single-day event
+        events.push(event);
+    }
+    events
 }
 
-fn parse_event_time(event: &EventEntry) -> (String, String, String, String, bool, Option<String>) {
+/// Event fields editable from the widget. `start`/`end` are RFC 3339 date-times, or
+/// `YYYY-MM-DD` dates (end inclusive) for all-day events.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EventDraft {
+    pub title: String,
+    pub start: String,
+    pub end: String,
+    pub is_all_day: bool,
+    pub location: String,
+    pub description: String,
+}
+
+impl EventDraft {
+    fn to_request_body(&self) -> Result<serde_json::Value, String> {
+        let (start, end) = if self.is_all_day {
+            let start = NaiveDate::parse_from_str(&self.start, "%Y-%m-%d")
+                .map_err(|e| format!("Invalid start date '{}': {}", self.start, e))?;
+            let end = NaiveDate::parse_from_str(&self.end, "%Y-%m-%d").unwrap_or(start);
+            // Google's end date is exclusive
+            (
+                serde_json::json!({ "date": start.format("%Y-%m-%d").to_string() }),
+                serde_json::json!({ "date": (end + Duration::days(1)).format("%Y-%m-%d").to_string() }),
+            )
+        } else {
+            let start = parse_draft_datetime(&self.start)?;
+            let end = parse_draft_datetime(&self.end).unwrap_or(start + Duration::hours(1));
+            (
+                serde_json::json!({ "dateTime": start.to_rfc3339() }),
+                serde_json::json!({ "dateTime": end.to_rfc3339() }),
+            )
+        };
+
+        Ok(serde_json::json!({
+            "summary": self.title,
+            "location": self.location,
+            "description": self.description,
+            "start": start,
+            "end": end,
+        }))
+    }
+
+    /// Builds the API representation of this draft, used to show it before the server confirms
+    fn to_entry(&self, id: &str) -> Result<EventEntry, String> {
+        let body = self.to_request_body()?;
+        let mut entry: EventEntry = serde_json::from_value(body)
+            .map_err(|e| format!("Failed to build event: {}", e))?;
+        entry.id = Some(id.to_string());
+        Ok(entry)
+    }
+}
+
+/// Accepts RFC 3339, or a date-time without offset which is taken as local time
+fn parse_draft_datetime(s: &str) -> Result<DateTime<Local>, String> {
+    if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
+        return Ok(dt.with_timezone(&Local));
+    }
+    ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M"]
+        .iter()
+        .find_map(|fmt| chrono::NaiveDateTime::parse_from_str(s, fmt).ok())
+        .and_then(|naive| naive.and_local_timezone(Local).earliest())
+        .ok_or_else(|| format!("Invalid date-time '{}'", s))
+}
+
+/// Widget events for a draft that hasn't been saved yet
+pub fn draft_events(draft: &EventDraft, id: &str, calendar: &Calendar, settings: &Settings) -> Result<Vec<Event>, String> {
+    let entry = draft.to_entry(id)?;
+    Ok(expand_event(entry, calendar, settings, EVENT_COLORS.get(), &HashMap::new()))
+}
+
+async fn send_event_request(
+    request: reqwest::RequestBuilder,
+    calendar: &Calendar,
+    settings: &Settings,
+) -> Result<Vec<Event>, String> {
+    let access_token = get_access_token().await?;
+
+    let response = request
+        .bearer_auth(&access_token)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send event request: {}", e))?;
+
+    if !response.status().is_success() {
+        let error = response.text().await.unwrap_or_default();
+        return Err(format!("Calendar API error: {}", error));
+    }
+
+    let entry: EventEntry = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse event: {}", e))?;
+
+    let mut recurrences = HashMap::new();
+    if let Some(ref master_id) = entry.recurring_event_id {
+        match fetch_event_entry(&calendar.id, master_id).await {
+            Ok(master) => {
+                recurrences.insert(master_id.clone(), master.recurrence.unwrap_or_default());
+            }
+            Err(e) => eprintln!("Failed to fetch series master: {}", e),
+        }
+    }
+
+    Ok(expand_event(entry, calendar, settings, EVENT_COLORS.get(), &recurrences))
+}
+
+async fn fetch_event_entry(calendar_id: &str, event_id: &str) -> Result<EventEntry, String> {
+    let access_token = get_access_token().await?;
+    let client = reqwest::Client::new();
+
+    let response = client
+        .get(event_url(calendar_id, event_id))
+        .bearer_auth(&access_token)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to fetch event: {}", e))?;
+
+    if !response.status().is_success() {
+        let error = response.text().await.unwrap_or_default();
+        return Err(format!("Calendar API error: {}", error));
+    }
+
+    response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse event: {}", e))
+}
+
+/// The master event of a recurring series, carrying the RRULE itself
+pub async fn get_series_master(
+    calendar: &Calendar,
+    recurring_event_id: &str,
+    settings: &Settings,
+) -> Result<Vec<Event>, String> {
+    let master = fetch_event_entry(&calendar.id, recurring_event_id).await?;
+    Ok(expand_event(master, calendar, settings, EVENT_COLORS.get(), &HashMap::new()))
+}
+
+pub async fn insert_event(calendar: &Calendar, draft: &EventDraft, settings: &Settings) -> Result<Vec<Event>, String> {
+    let client = reqwest::Client::new();
+    let url = format!(
+        "{}/calendars/{}/events",
+        CALENDAR_API_BASE,
+        urlencoding::encode(&calendar.id)
+    );
+    let body = draft.to_request_body()?;
+
+    send_event_request(client.post(&url).json(&body), calendar, settings).await
+}
+
+pub async fn patch_event(
+    calendar: &Calendar,
+    event_id: &str,
+    draft: &EventDraft,
+    settings: &Settings,
+) -> Result<Vec<Event>, String> {
+    let client = reqwest::Client::new();
+    let url = event_url(&calendar.id, event_id);
+    let body = draft.to_request_body()?;
+
+    send_event_request(client.patch(&url).json(&body), calendar, settings).await
+}
+
+pub async fn delete_event(calendar_id: &str, event_id: &str) -> Result<(), String> {
+    let access_token = get_access_token().await?;
+    let client = reqwest::Client::new();
+
+    let url = event_url(calendar_id, event_id);
+
+    let response = client
+        .delete(&url)
+        .bearer_auth(&access_token)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to delete event: {}", e))?;
+
+    // Already gone counts as deleted
+    if !response.status().is_success() && response.status() != reqwest::StatusCode::GONE {
+        let error = response.text().await.unwrap_or_default();
+        return Err(format!("Calendar API error: {}", error));
+    }
+
+    Ok(())
+}
+
+fn event_url(calendar_id: &str, event_id: &str) -> String {
+    format!(
+        "{}/calendars/{}/events/{}",
+        CALENDAR_API_BASE,
+        urlencoding::encode(calendar_id),
+        urlencoding::encode(event_id)
+    )
+}
+
+pub async fn get_event(calendar: &Calendar, event_id: &str, settings: &Settings) -> Result<Vec<Event>, String> {
+    let client = reqwest::Client::new();
+    let url = event_url(&calendar.id, event_id);
+
+    send_event_request(client.get(&url), calendar, settings).await
+}
+
+/// Sets the current user's attendee `responseStatus` and returns the re-fetched event
+pub async fn respond_to_event(
+    calendar: &Calendar,
+    event_id: &str,
+    response: &str,
+    settings: &Settings,
+) -> Result<Vec<Event>, String> {
+    if !matches!(response, "accepted" | "declined" | "tentative") {
+        return Err(format!("Invalid response '{}'", response));
+    }
+
+    let access_token = get_access_token().await?;
+    let client = reqwest::Client::new();
+    let url = event_url(&calendar.id, event_id);
+
+    let response_data = client
+        .get(&url)
+        .bearer_auth(&access_token)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to fetch event: {}", e))?;
+
+    if !response_data.status().is_success() {
+        let error = response_data.text().await.unwrap_or_default();
+        return Err(format!("Calendar API error: {}", error));
+    }
+
+    let event: serde_json::Value = response_data
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse event: {}", e))?;
+
+    // The API replaces the whole attendee list on patch, so send it back with only our entry changed
+    let mut attendees = event
+        .get("attendees")
+        .and_then(|a| a.as_array())
+        .cloned()
+        .unwrap_or_default();
+    let own = attendees
+        .iter_mut()
+        .find(|a| a.get("self").and_then(|s| s.as_bool()) == Some(true))
+        .ok_or_else(|| "You are not invited to this event".to_string())?;
+    own["responseStatus"] = serde_json::Value::String(response.to_string());
+
+    let patch = client
+        .patch(&url)
+        .bearer_auth(&access_token)
+        .json(&serde_json::json!({ "attendees": attendees }))
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send response: {}", e))?;
+
+    if !patch.status().is_success() {
+        let error = patch.text().await.unwrap_or_default();
+        return Err(format!("Calendar API error: {}", error));
+    }
+
+    // Re-fetch to confirm what the server stored
+    get_event(calendar, event_id, settings).await
+}
+
+fn parse_event_time(event: &EventEntry, locale: &LocaleSettings) -> EventTime {
     let start = event.start.as_ref();
     let end = event.end.as_ref();
 
     if let Some(start) = start {
         if let Some(date) = &start.date {
             // All-day event
-            let date_formatted = format_date_string(date);
+            let date_formatted = format_date_string(date, locale);
             // Google's end date is exclusive, so subtract 1 day to get the last actual day
-            let end_date = end
+            let last_day = end
                 .and_then(|e| e.date.as_ref())
                 .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
-                .map(|d| (d - Duration::days(1)).format("%Y-%m-%d").to_string())
-                .filter(|d| d != date);
-            return (date.clone(), "All day".to_string(), "All day".to_string(), date_formatted, true, end_date);
+                .map(|d| (d - Duration::days(1)).format("%Y-%m-%d").to_string());
+            let end_date = last_day.clone().filter(|d| d != date);
+            return EventTime {
+                date: date.clone(),
+                time: locale.all_day_label.clone(),
+                time_range: locale.all_day_label.clone(),
+                date_formatted,
+                start: date.clone(),
+                end: last_day.unwrap_or_else(|| date.clone()),
+                is_all_day: true,
+                end_date,
+            };
         }
 
         if let Some(dt_str) = &start.date_time {
             // Timed event
             if let Ok(dt) = DateTime::parse_from_rfc3339(dt_str) {
-                let local = dt.with_timezone(&chrono::Local);
+                let local = locale.in_zone(&dt);
                 let date = local.format("%Y-%m-%d").to_string();
-                let time = local.format("%H:%M").to_string();
-                let date_formatted = local.format("%A, %d. %B").to_string();
+                let time = locale.format_time(&local);
+                let date_formatted = locale.format_date(local.date_naive());
 
-                // Check if end date differs from start date (multi-day timed event)
-                let end_date = end
+                let end_local = end
                     .and_then(|e| e.date_time.as_ref())
                     .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
-                    .map(|d| d.with_timezone(&chrono::Local).format("%Y-%m-%d").to_string())
+                    .map(|d| locale.in_zone(&d));
+
+                // Check if end date differs from start date (multi-day timed event)
+                let end_date = end_local
+                    .map(|d| d.format("%Y-%m-%d").to_string())
                     .filter(|d| d != &date);
 
-                let time_range = if let Some(end) = end {
-                    if let Some(end_dt_str) = &end.date_time {
-                        if let Ok(end_dt) = DateTime::parse_from_rfc3339(end_dt_str) {
-                            let end_local = end_dt.with_timezone(&chrono::Local);
-                            format!("{} - {}", local.format("%H:%M"), end_local.format("%H:%M"))
-                        } else {
-                            time.clone()
-                        }
-                    } else {
-                        time.clone()
-                    }
-                } else {
-                    time.clone()
+                let time_range = match end_local {
+                    Some(end_local) => format!("{} - {}", time, locale.format_time(&end_local)),
+                    None => time.clone(),
                 };
 
-                return (date, time, time_range, date_formatted, false, end_date);
+                return EventTime {
+                    date,
+                    time,
+                    time_range,
+                    date_formatted,
+                    start: local.to_rfc3339(),
+                    end: end_local.unwrap_or(local).to_rfc3339(),
+                    is_all_day: false,
+                    end_date,
+                };
             }
         }
     }
 
-    // Fallback
-    let now = Local::now();
-    let date = now.format("%Y-%m-%d").to_string();
-    (date, "All day".to_string(), "All day".to_string(), "Unknown".to_string(), true, None)
+    // No usable start: shown as an all-day event today
+    let today = locale.now().date();
+    let date = today.format("%Y-%m-%d").to_string();
+    EventTime {
+        date: date.clone(),
+        time: locale.all_day_label.clone(),
+        time_range: locale.all_day_label.clone(),
+        date_formatted: locale.format_date(today),
+        start: date.clone(),
+        end: date,
+        is_all_day: true,
+        end_date: None,
+    }
 }
 
-fn format_date_string(date_str: &str) -> String {
+fn format_date_string(date_str: &str, locale: &LocaleSettings) -> String {
     if let Ok(date) = NaiveDate::parse_from_str(date_str, "%Y-%m-%d") {
-        date.format("%A, %d. %B").to_string()
+        locale.format_date(date)
     } else {
         date_str.to_string()
     }
 }
+
+// Hosts whose links are treated as video meetings when found in free text
+const MEETING_HOSTS: &[&str] = &[
+    "meet.google.com",
+    "zoom.us",
+    "teams.microsoft.com",
+    "teams.live.com",
+];
+
+fn conference_links(
+    conference_data: Option<&ConferenceData>,
+    hangout_link: Option<&str>,
+    location: &str,
+    description: &str,
+) -> Vec<ConferenceLink> {
+    let mut links = Vec::new();
+
+    if let Some(entry_points) = conference_data.and_then(|c| c.entry_points.as_ref()) {
+        for entry in entry_points {
+            if let Some(uri) = &entry.uri {
+                links.push(ConferenceLink {
+                    kind: entry.entry_point_type.clone().unwrap_or_else(|| "more".to_string()),
+                    label: entry.label.clone().unwrap_or_else(|| uri.clone()),
+                    uri: uri.clone(),
+                });
+            }
+        }
+    }
+
+    if let Some(uri) = hangout_link {
+        if !links.iter().any(|l| l.uri == uri) {
+            links.push(ConferenceLink {
+                kind: "video".to_string(),
+                label: uri.to_string(),
+                uri: uri.to_string(),
+            });
+        }
+    }
+
+    for uri in find_meeting_urls(location).into_iter().chain(find_meeting_urls(description)) {
+        if !links.iter().any(|l| l.uri == uri) {
+            links.push(ConferenceLink {
+                kind: "video".to_string(),
+                label: uri.clone(),
+                uri,
+            });
+        }
+    }
+
+    links
+}
+
+fn find_meeting_urls(text: &str) -> Vec<String> {
+    // Descriptions are often HTML, so split on markup characters as well as whitespace
+    text.split(|c: char| c.is_whitespace() || "<>\"'()[]".contains(c))
+        .filter_map(|token| {
+            let start = token.find("https://")?;
+            let url = token[start..].trim_end_matches(['.', ',', ';', ':', '!', '?']);
+            let parsed = url::Url::parse(url).ok()?;
+            let host = parsed.host_str()?;
+            MEETING_HOSTS
+                .iter()
+                .any(|h| host == *h || host.ends_with(&format!(".{}", h)))
+                .then(|| url.to_string())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn locale(timezone: &str) -> LocaleSettings {
+        LocaleSettings {
+            timezone: timezone.to_string(),
+            ..LocaleSettings::default()
+        }
+    }
+
+    fn entry(json: serde_json::Value) -> EventEntry {
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn event_times_follow_the_locale_zone() {
+        let late = entry(serde_json::json!({
+            "start": { "dateTime": "2026-10-14T23:30:00Z" },
+            "end": { "dateTime": "2026-10-15T00:30:00Z" },
+        }));
+        let cases = [
+            ("Europe/Oslo", "2026-10-15", "01:30 - 02:30", "2026-10-15T01:30:00+02:00", None),
+            ("America/New_York", "2026-10-14", "19:30 - 20:30", "2026-10-14T19:30:00-04:00", None),
+            ("Asia/Kolkata", "2026-10-15", "05:00 - 06:00", "2026-10-15T05:00:00+05:30", None),
+            // Crosses midnight in the Azores
+            ("Atlantic/Azores", "2026-10-14", "23:30 - 00:30", "2026-10-14T23:30:00+00:00", Some("2026-10-15")),
+        ];
+        for (zone, date, time_range, start, end_date) in cases {
+            let time = parse_event_time(&late, &locale(zone));
+            assert_eq!(
+                (time.date.as_str(), time.time_range.as_str(), time.start.as_str(), time.end_date.as_deref()),
+                (date, time_range, start, end_date),
+                "{}",
+                zone
+            );
+        }
+    }
+
+    #[test]
+    fn events_without_a_start_fall_back_to_today() {
+        let locale = locale("Europe/Oslo");
+        let time = parse_event_time(&entry(serde_json::json!({})), &locale);
+        let today = locale.now().date();
+        assert!(time.is_all_day);
+        assert_eq!(time.date, today.format("%Y-%m-%d").to_string());
+        assert_eq!(time.date_formatted, locale.format_date(today));
+    }
+
+    #[test]
+    fn finds_meeting_urls() {
+        let cases: &[(&str, &[&str])] = &[
+            ("Join: https://meet.google.com/abc-defg-hij.", &["https://meet.google.com/abc-defg-hij"]),
+            ("(https://us02web.zoom.us/j/8512345678?pwd=xyz), thanks!", &["https://us02web.zoom.us/j/8512345678?pwd=xyz"]),
+            (
+                "Teams: https://teams.microsoft.com/l/meetup-join/19%3ameeting_abc%40thread.v2/0;",
+                &["https://teams.microsoft.com/l/meetup-join/19%3ameeting_abc%40thread.v2/0"],
+            ),
+            (
+                r#"<p>Agenda</p><a href="https://zoom.us/j/123">Join Zoom</a><br>Backup: <b>https://meet.google.com/xyz-abcd-efg</b>"#,
+                &["https://zoom.us/j/123", "https://meet.google.com/xyz-abcd-efg"],
+            ),
+            // Hosts that merely contain a meeting host's name are skipped, as are plain links
+            ("https://zoom.us.example.com/j/1 https://notzoom.us/j/1 https://example.com/agenda", &[]),
+            ("http://zoom.us/j/1 is not https", &[]),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(find_meeting_urls(text), *expected, "{}", text);
+        }
+    }
+
+    fn links(
+        conference_data: Option<serde_json::Value>,
+        hangout_link: Option<&str>,
+        location: &str,
+        description: &str,
+    ) -> Vec<(String, String, String)> {
+        let conference_data: Option<ConferenceData> = conference_data.map(|c| serde_json::from_value(c).unwrap());
+        conference_links(conference_data.as_ref(), hangout_link, location, description)
+            .into_iter()
+            .map(|l| (l.kind, l.label, l.uri))
+            .collect()
+    }
+
+    #[test]
+    fn conference_data_comes_first() {
+        let meet = "https://meet.google.com/abc-defg-hij";
+        let conference = serde_json::json!({
+            "entryPoints": [
+                { "entryPointType": "video", "uri": meet, "label": "meet.google.com/abc-defg-hij" },
+                { "entryPointType": "phone", "uri": "tel:+47-21-00-00-00", "label": "+47 21 00 00 00" },
+            ]
+        });
+        let found = links(
+            Some(conference),
+            Some(meet),
+            meet,
+            &format!("<a href=\"{0}\">{0}</a> or https://zoom.us/j/42.", meet),
+        );
+        // The same link in the hangout link, location and description is listed once, as
+        // conferenceData labels it
+        assert_eq!(
+            found,
+            [
+                ("video".to_string(), "meet.google.com/abc-defg-hij".to_string(), meet.to_string()),
+                ("phone".to_string(), "+47 21 00 00 00".to_string(), "tel:+47-21-00-00-00".to_string()),
+                ("video".to_string(), "https://zoom.us/j/42".to_string(), "https://zoom.us/j/42".to_string()),
+            ]
+        );
+
+        // Without conferenceData, the location comes before the description
+        let found = links(None, None, "https://zoom.us/j/1", "https://meet.google.com/x https://zoom.us/j/1");
+        let uris: Vec<&str> = found.iter().map(|(_, _, uri)| uri.as_str()).collect();
+        assert_eq!(uris, ["https://zoom.us/j/1", "https://meet.google.com/x"]);
+    }
+}
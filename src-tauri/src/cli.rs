@@ -0,0 +1,378 @@
+//! Headless subcommands for terminals, status bars and cron. They share the providers, the
+//! cache and the sign-in with the widget, but never open a window.
+
+use std::collections::HashMap;
+use std::fs;
+use std::sync::Mutex;
+
+use chrono::{Datelike, Duration, Local, NaiveDate};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+
+use crate::settings::{self, AgendaFormat};
+use crate::tasks::Task;
+use crate::{export, microsoft, AppState, CachedData};
+
+#[derive(Parser)]
+#[command(name = "calendar-widget", version, about = "Calendar widget; runs headless when given a subcommand")]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Events and tasks due in a date range, today by default
+    Agenda {
+        #[command(flatten)]
+        range: Range,
+        /// Overrides `agenda.format`; the default is plain text
+        #[arg(long, value_enum)]
+        format: Option<Format>,
+        #[command(flatten)]
+        output: Output,
+    },
+    /// Open tasks with their ids
+    Tasks {
+        #[command(flatten)]
+        output: Output,
+    },
+    /// Completes a task right away, without the undo window
+    Complete {
+        /// Task id, as listed by `tasks`
+        id: String,
+        /// Task list id, needed when the id appears in more than one list
+        #[arg(long)]
+        list: Option<String>,
+        #[command(flatten)]
+        output: Output,
+    },
+    /// Signs in to or out of an account
+    Auth {
+        #[command(subcommand)]
+        action: AuthAction,
+    },
+    /// Writes a date range as .ics or an agenda file, the current week by default
+    Export {
+        #[command(flatten)]
+        range: Range,
+        #[arg(long, value_enum, default_value_t = ExportFormat::Ics)]
+        format: ExportFormat,
+        /// File to write; standard output when left out
+        #[arg(short, long)]
+        output: Option<String>,
+        /// Use the cached data instead of fetching
+        #[arg(long)]
+        cached: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum AuthAction {
+    /// Opens the browser to sign in, replacing any stored token
+    Login {
+        #[arg(value_enum, default_value_t = Account::Google)]
+        account: Account,
+    },
+    /// Deletes the stored token
+    Logout {
+        #[arg(value_enum, default_value_t = Account::Google)]
+        account: Account,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Account {
+    Google,
+    Microsoft,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Format {
+    Markdown,
+    Text,
+    Csv,
+    Json,
+}
+
+impl From<Format> for AgendaFormat {
+    fn from(format: Format) -> Self {
+        match format {
+            Format::Markdown => AgendaFormat::Markdown,
+            Format::Text => AgendaFormat::Text,
+            Format::Csv => AgendaFormat::Csv,
+            Format::Json => AgendaFormat::Json,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum ExportFormat {
+    Ics,
+    Markdown,
+    Text,
+    Csv,
+    Json,
+}
+
+/// Inclusive date range, `YYYY-MM-DD`
+#[derive(Args)]
+pub struct Range {
+    #[arg(long)]
+    from: Option<NaiveDate>,
+    #[arg(long, conflicts_with = "days")]
+    to: Option<NaiveDate>,
+    /// Number of days from `--from`
+    #[arg(long)]
+    days: Option<u32>,
+}
+
+impl Range {
+    fn resolve(&self, default_from: NaiveDate, default_days: u32) -> Result<(NaiveDate, NaiveDate), String> {
+        let from = self.from.unwrap_or(default_from);
+        let to = match (self.to, self.days) {
+            (Some(to), _) => to,
+            (None, days) => from + Duration::days(days.unwrap_or(default_days).max(1) as i64 - 1),
+        };
+        if to < from {
+            return Err(format!("End date {} is before start date {}", to, from));
+        }
+        Ok((from, to))
+    }
+}
+
+/// First day of the week `day` is in
+fn week_start(day: NaiveDate, locale: &settings::LocaleSettings) -> NaiveDate {
+    day - Duration::days(locale.days_since_week_start(day.weekday()))
+}
+
+#[derive(Args)]
+pub struct Output {
+    /// Print JSON instead of text
+    #[arg(long)]
+    json: bool,
+    /// Use the cached data instead of fetching, for quick status bar updates
+    #[arg(long)]
+    cached: bool,
+}
+
+/// The subcommand on the command line, if any. Exits on `--help`, `--version` and usage errors.
+pub fn parse() -> Option<Command> {
+    if std::env::args_os().len() < 2 {
+        return None;
+    }
+    attach_console();
+    Cli::parse().command
+}
+
+/// Release builds use the Windows GUI subsystem, which starts without a console
+#[cfg(windows)]
+fn attach_console() {
+    extern "system" {
+        fn AttachConsole(process_id: u32) -> i32;
+    }
+    const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(windows))]
+fn attach_console() {}
+
+/// Runs a subcommand and returns the process exit code
+pub fn run(command: Command) -> i32 {
+    let state = AppState {
+        cache: Mutex::new(crate::load_cache()),
+        pending_completions: Mutex::new(HashMap::new()),
+        clipboard: Mutex::new(None),
+    };
+    match tauri::async_runtime::block_on(execute(command, &state)) {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            1
+        }
+    }
+}
+
+async fn load_data(state: &AppState, cached: bool) -> Result<CachedData, String> {
+    if cached {
+        state
+            .cache
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| "Nothing cached yet; run without --cached first".to_string())
+    } else {
+        crate::refresh_data(state).await
+    }
+}
+
+fn print_json<T: serde::Serialize>(value: &T) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(value).map_err(|e| format!("Failed to serialize output: {}", e))?;
+    println!("{}", json);
+    Ok(())
+}
+
+/// Tasks with their subtasks, and how deeply each one is nested
+fn flatten(tasks: &[Task], depth: usize, out: &mut Vec<(usize, Task)>) {
+    for task in tasks {
+        out.push((depth, Task { subtasks: Vec::new(), ..task.clone() }));
+        flatten(&task.subtasks, depth + 1, out);
+    }
+}
+
+async fn execute(command: Command, state: &AppState) -> Result<(), String> {
+    let settings = settings::load_settings();
+    let today = Local::now().date_naive();
+
+    match command {
+        Command::Agenda { range, format, output } => {
+            let (from, to) = range.resolve(today, 1)?;
+            let data = load_data(state, output.cached).await?;
+            let mut options = settings.agenda.clone();
+            options.format = match (format, output.json) {
+                (Some(format), _) => format.into(),
+                (None, true) => AgendaFormat::Json,
+                (None, false) => AgendaFormat::Text,
+            };
+            let agenda = export::render_agenda(&data.events, &data.tasks, from, to, &options, &settings.locale)?;
+            print!("{}", agenda);
+        }
+        Command::Tasks { output } => {
+            let data = load_data(state, output.cached).await?;
+            if output.json {
+                return print_json(&data.tasks);
+            }
+            let mut tasks = Vec::new();
+            flatten(&data.tasks, 0, &mut tasks);
+            let width = tasks.iter().map(|(_, t)| t.id.chars().count()).max().unwrap_or(0);
+            for (depth, task) in tasks.iter().filter(|(_, t)| !t.completed) {
+                let due = if task.due.is_empty() { String::new() } else { format!("  (due {})", task.due) };
+                println!("{:<width$}  {}{}{}", task.id, "  ".repeat(*depth), task.title, due, width = width);
+            }
+        }
+        Command::Complete { id, list, output } => {
+            let data = load_data(state, output.cached).await?;
+            let mut tasks = Vec::new();
+            flatten(&data.tasks, 0, &mut tasks);
+            let matches: Vec<Task> = tasks
+                .into_iter()
+                .map(|(_, t)| t)
+                .filter(|t| t.id == id && list.as_ref().is_none_or(|l| *l == t.tasklist_id))
+                .collect();
+            let task = match matches.as_slice() {
+                [] => return Err(format!("No open task with id {}", id)),
+                [task] => task.clone(),
+                _ => return Err(format!("Task id {} is in more than one list; pass --list", id)),
+            };
+
+            crate::finish_completion(state, &task.id, &task.tasklist_id).await?;
+            let task = Task { completed: true, ..task };
+            if output.json {
+                return print_json(&task);
+            }
+            println!("Completed: {}", task.title);
+        }
+        Command::Auth { action } => match action {
+            AuthAction::Login { account } => {
+                match account {
+                    Account::Google => crate::auth::sign_in_google().await?,
+                    Account::Microsoft => microsoft::MicrosoftProvider::new(settings.providers.microsoft).sign_in().await?,
+                }
+                println!("Signed in");
+            }
+            AuthAction::Logout { account } => {
+                let removed = match account {
+                    Account::Google => crate::auth::sign_out_google()?,
+                    Account::Microsoft => microsoft::MicrosoftProvider::new(settings.providers.microsoft).sign_out()?,
+                };
+                println!("{}", if removed { "Signed out" } else { "Not signed in" });
+            }
+        },
+        Command::Export { range, format, output, cached } => {
+            let (from, to) = range.resolve(week_start(today, &settings.locale), 7)?;
+            let data = load_data(state, cached).await?;
+            let agenda_format = match format {
+                ExportFormat::Ics => None,
+                ExportFormat::Markdown => Some(AgendaFormat::Markdown),
+                ExportFormat::Text => Some(AgendaFormat::Text),
+                ExportFormat::Csv => Some(AgendaFormat::Csv),
+                ExportFormat::Json => Some(AgendaFormat::Json),
+            };
+            let content = match agenda_format {
+                None => export::to_ics(&export::events_in_range(&data.events, from, to, &settings.locale), &settings.locale),
+                Some(format) => {
+                    let options = settings::AgendaSettings { format, ..settings.agenda.clone() };
+                    export::render_agenda(&data.events, &data.tasks, from, to, &options, &settings.locale)?
+                }
+            };
+            match output {
+                Some(path) => {
+                    fs::write(&path, content).map_err(|e| format!("Failed to write {}: {}", path, e))?;
+                    eprintln!("Saved {}", path);
+                }
+                None => print!("{}", content),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Weekday;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    /// The range of `export` with these arguments, resolved as a week from Monday 12 October
+    fn export_range(args: &[&str]) -> Result<(NaiveDate, NaiveDate), String> {
+        let cli = Cli::try_parse_from(["calendar-widget", "export"].iter().chain(args)).map_err(|e| e.kind().to_string())?;
+        match cli.command {
+            Some(Command::Export { range, .. }) => range.resolve(date("2026-10-12"), 7),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn resolves_ranges() {
+        let cases: &[(&[&str], &str, &str)] = &[
+            (&[], "2026-10-12", "2026-10-18"),
+            (&["--days", "3"], "2026-10-12", "2026-10-14"),
+            (&["--from", "2026-10-20"], "2026-10-20", "2026-10-26"),
+            (&["--from", "2026-10-20", "--days", "1"], "2026-10-20", "2026-10-20"),
+            // Zero days still covers the start date
+            (&["--days", "0"], "2026-10-12", "2026-10-12"),
+            (&["--to", "2026-10-31"], "2026-10-12", "2026-10-31"),
+            (&["--from", "2026-10-14", "--to", "2026-10-14"], "2026-10-14", "2026-10-14"),
+        ];
+        for (args, from, to) in cases {
+            assert_eq!(export_range(args), Ok((date(from), date(to))), "{:?}", args);
+        }
+
+        assert_eq!(
+            export_range(&["--from", "2026-10-14", "--to", "2026-10-13"]),
+            Err("End date 2026-10-13 is before start date 2026-10-14".to_string())
+        );
+        // --to and --days can't be combined
+        assert!(export_range(&["--to", "2026-10-31", "--days", "3"]).is_err());
+    }
+
+    #[test]
+    fn weeks_start_on_the_configured_day() {
+        let locale = |first_day_of_week| settings::LocaleSettings {
+            first_day_of_week,
+            ..settings::LocaleSettings::default()
+        };
+        // Wednesday 14 October
+        let day = date("2026-10-14");
+        assert_eq!(week_start(day, &locale(Weekday::Mon)), date("2026-10-12"));
+        assert_eq!(week_start(day, &locale(Weekday::Sun)), date("2026-10-11"));
+        assert_eq!(week_start(day, &locale(Weekday::Wed)), day);
+        assert_eq!(week_start(day, &locale(Weekday::Thu)), date("2026-10-08"));
+    }
+}
@@ -0,0 +1,196 @@
+//! Overlap detection between timed events across all calendars.
+
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, FixedOffset};
+use std::collections::HashMap;
+
+use crate::calendar::{self, Event};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Conflict {
+    pub date: String,
+    /// RFC 3339 bounds of the overlapping part
+    pub start: String,
+    pub end: String,
+    pub event_ids: [String; 2],
+    /// Calendars of the two events; ids are only unique within a calendar
+    pub calendar_ids: [String; 2],
+    pub titles: [String; 2],
+}
+
+/// Events marked "free", declined ones and all-day events never conflict
+fn blocks_time(event: &Event) -> bool {
+    !event.is_all_day
+        && event.transparency != "transparent"
+        && event.response_status != "declined"
+        && event.status != "cancelled"
+}
+
+/// How `Event::conflicts` refers to another event
+fn conflict_key(event: &Event) -> String {
+    format!("{}/{}", event.calendar_id, event.id)
+}
+
+/// Fills in `Event::conflicts` and returns one entry per overlapping pair. Multi-day events are
+/// checked with their real span, and every per-day copy gets the conflicts.
+pub fn detect(events: &mut [Event]) -> Vec<Conflict> {
+    let whole = calendar::whole_events(events.iter());
+    let mut timed: Vec<(&Event, DateTime<FixedOffset>, DateTime<FixedOffset>)> = whole
+        .iter()
+        .filter(|e| blocks_time(e))
+        .filter_map(|e| {
+            let start = DateTime::parse_from_rfc3339(&e.start).ok()?;
+            let end = DateTime::parse_from_rfc3339(&e.end).ok()?;
+            (end > start).then_some((e, start, end))
+        })
+        .collect();
+    timed.sort_by_key(|(_, start, _)| *start);
+
+    let mut conflicts = Vec::new();
+    let mut keys: HashMap<String, Vec<String>> = HashMap::new();
+    for (n, (a, a_start, a_end)) in timed.iter().enumerate() {
+        for (b, b_start, b_end) in &timed[n + 1..] {
+            // Sorted by start, so nothing later can overlap either
+            if b_start >= a_end {
+                break;
+            }
+            // The same invitation can show up on several calendars of one account, while
+            // other providers may reuse the id for an unrelated event
+            if a.id == b.id && a.provider == b.provider {
+                continue;
+            }
+
+            let overlap_start = (*a_start).max(*b_start);
+            let overlap_end = (*a_end).min(*b_end);
+            conflicts.push(Conflict {
+                date: a.date.clone(),
+                start: overlap_start.to_rfc3339(),
+                end: overlap_end.to_rfc3339(),
+                event_ids: [a.id.clone(), b.id.clone()],
+                calendar_ids: [a.calendar_id.clone(), b.calendar_id.clone()],
+                titles: [a.title.clone(), b.title.clone()],
+            });
+
+            keys.entry(conflict_key(a)).or_default().push(conflict_key(b));
+            keys.entry(conflict_key(b)).or_default().push(conflict_key(a));
+        }
+    }
+
+    for event in events.iter_mut() {
+        event.conflicts = keys.get(&conflict_key(event)).cloned().unwrap_or_default();
+    }
+
+    conflicts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(id: &str, calendar_id: &str, provider: &str, start: &str, end: &str) -> Event {
+        Event {
+            id: id.to_string(),
+            calendar_id: calendar_id.to_string(),
+            provider: provider.to_string(),
+            title: id.to_string(),
+            date: start[..10].to_string(),
+            start: start.to_string(),
+            end: end.to_string(),
+            transparency: "opaque".to_string(),
+            status: "confirmed".to_string(),
+            ..Event::default()
+        }
+    }
+
+    /// 10:00-11:00 on the 14th
+    fn meeting(id: &str) -> Event {
+        event(id, "work", "google", "2026-10-14T10:00:00+02:00", "2026-10-14T11:00:00+02:00")
+    }
+
+    /// 10:30-11:30 on the 14th, on another calendar
+    fn other(id: &str) -> Event {
+        event(id, "home", "google", "2026-10-14T10:30:00+02:00", "2026-10-14T11:30:00+02:00")
+    }
+
+    fn pairs(conflicts: &[Conflict]) -> Vec<[String; 2]> {
+        conflicts.iter().map(|c| c.event_ids.clone()).collect()
+    }
+
+    #[test]
+    fn overlapping_events_conflict() {
+        let later = event("c", "work", "google", "2026-10-14T11:30:00+02:00", "2026-10-14T12:00:00+02:00");
+        let mut events = vec![meeting("a"), other("b"), later];
+        let conflicts = detect(&mut events);
+        assert_eq!(pairs(&conflicts), [["a".to_string(), "b".to_string()]]);
+        assert_eq!(conflicts[0].start, "2026-10-14T10:30:00+02:00");
+        assert_eq!(conflicts[0].end, "2026-10-14T11:00:00+02:00");
+        assert_eq!(events[0].conflicts, ["home/b"]);
+        assert_eq!(events[1].conflicts, ["work/a"]);
+        // Touching ends don't overlap
+        assert!(events[2].conflicts.is_empty());
+    }
+
+    #[test]
+    fn free_declined_and_cancelled_events_never_conflict() {
+        for change in [
+            (|e: &mut Event| e.transparency = "transparent".to_string()) as fn(&mut Event),
+            |e| e.response_status = "declined".to_string(),
+            |e| e.status = "cancelled".to_string(),
+        ] {
+            let mut b = other("b");
+            change(&mut b);
+            let mut events = vec![meeting("a"), b];
+            assert!(detect(&mut events).is_empty());
+            assert!(events.iter().all(|e| e.conflicts.is_empty()));
+        }
+    }
+
+    #[test]
+    fn same_invitation_on_two_calendars() {
+        // One provider, same id: the same event shared to both calendars
+        let mut events = vec![meeting("invite"), other("invite")];
+        assert!(detect(&mut events).is_empty());
+
+        // Another provider reusing the id is a different event
+        let mut caldav = other("invite");
+        caldav.provider = "caldav:Home".to_string();
+        let mut events = vec![meeting("invite"), caldav];
+        assert_eq!(detect(&mut events).len(), 1);
+        assert_eq!(events[0].conflicts, ["home/invite"]);
+        assert_eq!(events[1].conflicts, ["work/invite"]);
+    }
+
+    #[test]
+    fn multi_day_events_conflict_over_their_span() {
+        // 22:00-09:00, cached as an all-day copy per day
+        let shift: Vec<Event> = ["2026-10-13", "2026-10-14"]
+            .iter()
+            .map(|day| Event {
+                date: day.to_string(),
+                start: day.to_string(),
+                end: day.to_string(),
+                is_all_day: true,
+                span_start: "2026-10-13T22:00:00+02:00".to_string(),
+                span_end: "2026-10-14T09:00:00+02:00".to_string(),
+                ..event("shift", "oncall", "google", "2026-10-13T22:00:00+02:00", "2026-10-14T09:00:00+02:00")
+            })
+            .collect();
+        let early = event("early", "work", "google", "2026-10-14T08:00:00+02:00", "2026-10-14T08:30:00+02:00");
+
+        let mut events = shift;
+        events.push(early);
+        let conflicts = detect(&mut events);
+        assert_eq!(pairs(&conflicts), [["shift".to_string(), "early".to_string()]]);
+        assert_eq!(conflicts[0].date, "2026-10-13");
+        // Every copy of the shift is marked
+        assert_eq!(events[0].conflicts, ["work/early"]);
+        assert_eq!(events[1].conflicts, ["work/early"]);
+        assert_eq!(events[2].conflicts, ["oncall/shift"]);
+
+        // All-day events themselves block nothing
+        let mut holiday = event("holiday", "home", "google", "2026-10-14", "2026-10-14");
+        holiday.is_all_day = true;
+        let mut events = vec![holiday, meeting("a")];
+        assert!(detect(&mut events).is_empty());
+    }
+}
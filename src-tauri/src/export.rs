@@ -0,0 +1,772 @@
+//! Getting cached data out of the widget: events in a date range as an RFC 5545 calendar, and
+//! agendas of events and tasks as Markdown, plain text, CSV or JSON.
+
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, Offset, TimeZone, Utc};
+use chrono_tz::{OffsetComponents, OffsetName, Tz};
+use serde::Serialize;
+
+use crate::calendar::{self, Event};
+use crate::ics::{self, Component, IcsTime, Property};
+use crate::settings::{AgendaFormat, AgendaSettings, LocaleSettings};
+use crate::tasks::Task;
+
+const PRODID: &str = "-//Calendar Widget//Export//EN";
+
+/// First and last day an event covers in the configured time zone
+pub fn event_days(event: &Event, locale: &LocaleSettings) -> Option<(NaiveDate, NaiveDate)> {
+    let parse_date = |value: &str| NaiveDate::parse_from_str(value, "%Y-%m-%d").ok();
+
+    if event.is_all_day {
+        let first = parse_date(&event.start)?;
+        return Some((first, parse_date(&event.end).unwrap_or(first)));
+    }
+
+    let start = locale.to_local(&DateTime::parse_from_rfc3339(&event.start).ok()?);
+    let end = DateTime::parse_from_rfc3339(&event.end)
+        .map(|e| locale.to_local(&e))
+        .unwrap_or(start);
+    let mut last = end.date();
+    // An event ending at midnight doesn't reach into that day
+    if end > start && end.time() == NaiveTime::MIN {
+        last = last.pred_opt().unwrap_or(last);
+    }
+    Some((start.date(), last.max(start.date())))
+}
+
+/// Events touching any day from `from` to `to`, inclusive
+pub fn events_in_range<'a>(
+    events: &'a [Event],
+    from: NaiveDate,
+    to: NaiveDate,
+    locale: &LocaleSettings,
+) -> Vec<&'a Event> {
+    events
+        .iter()
+        .filter(|e| event_days(e, locale).is_some_and(|(first, last)| first <= to && last >= from))
+        .collect()
+}
+
+fn property(name: &str, value: String) -> Property {
+    Property {
+        name: name.to_string(),
+        params: Vec::new(),
+        value,
+    }
+}
+
+fn text_property(name: &str, value: &str) -> Property {
+    property(name, ics::escape_text(value))
+}
+
+/// DTSTART/DTEND value: DATE for all-day events, wall-clock time with TZID when the zone is
+/// known, UTC otherwise
+fn event_time(value: &str, all_day: bool, zone: Option<Tz>) -> Option<IcsTime> {
+    if all_day {
+        return NaiveDate::parse_from_str(value, "%Y-%m-%d").ok().map(IcsTime::Date);
+    }
+    let dt = DateTime::parse_from_rfc3339(value).ok()?;
+    Some(match zone {
+        Some(tz) => IcsTime::Local(dt.with_timezone(&tz).naive_local(), Some(tz.name().to_string())),
+        None => IcsTime::Utc(dt.with_timezone(&Utc)),
+    })
+}
+
+/// A VTIMEZONE spelling out every offset change between `from` and `to`, which is all
+/// a reader needs for the events in the file
+fn vtimezone(tz: Tz, from: DateTime<Utc>, to: DateTime<Utc>) -> Component {
+    let observance = |at: DateTime<Utc>, before: &chrono_tz::TzOffset| {
+        let after = tz.offset_from_utc_datetime(&at.naive_utc());
+        let name = if after.dst_offset().is_zero() { "STANDARD" } else { "DAYLIGHT" };
+        let offset = |o: &chrono_tz::TzOffset| {
+            let seconds = o.fix().local_minus_utc();
+            let sign = if seconds < 0 { '-' } else { '+' };
+            format!("{}{:02}{:02}", sign, seconds.abs() / 3600, seconds.abs() % 3600 / 60)
+        };
+
+        let mut properties = vec![
+            // Onsets are written in the local time in effect before the change
+            property(
+                "DTSTART",
+                (at + Duration::seconds(before.fix().local_minus_utc() as i64))
+                    .naive_utc()
+                    .format("%Y%m%dT%H%M%S")
+                    .to_string(),
+            ),
+            property("TZOFFSETFROM", offset(before)),
+            property("TZOFFSETTO", offset(&after)),
+        ];
+        if let Some(abbreviation) = after.abbreviation() {
+            properties.push(text_property("TZNAME", abbreviation));
+        }
+        Component {
+            name: name.to_string(),
+            properties,
+            components: Vec::new(),
+        }
+    };
+
+    let start = (from - Duration::days(1)).date_naive().and_time(NaiveTime::MIN).and_utc();
+    let first = tz.offset_from_utc_datetime(&start.naive_utc());
+    let mut observances = vec![observance(start, &first)];
+
+    // Offsets change at most a few times a year: step by day, then narrow down to the second
+    let mut previous = first;
+    let mut day = start;
+    while day < to + Duration::days(1) {
+        let next_day = day + Duration::days(1);
+        let offset = tz.offset_from_utc_datetime(&next_day.naive_utc());
+        if offset.fix() != previous.fix() {
+            let (mut low, mut high) = (day, next_day);
+            while high - low > Duration::seconds(1) {
+                let mid = low + (high - low) / 2;
+                if tz.offset_from_utc_datetime(&mid.naive_utc()).fix() == previous.fix() {
+                    low = mid;
+                } else {
+                    high = mid;
+                }
+            }
+            observances.push(observance(high, &previous));
+            previous = offset;
+        }
+        day = next_day;
+    }
+
+    Component {
+        name: "VTIMEZONE".to_string(),
+        properties: vec![property("TZID", tz.name().to_string())],
+        components: observances,
+    }
+}
+
+fn participation_status(response_status: &str) -> &'static str {
+    match response_status {
+        "accepted" => "ACCEPTED",
+        "declined" => "DECLINED",
+        "tentative" => "TENTATIVE",
+        _ => "NEEDS-ACTION",
+    }
+}
+
+fn to_vevent(event: &Event, zone: Option<Tz>, stamp: &IcsTime) -> Option<Component> {
+    let start = event_time(&event.start, event.is_all_day, zone)?;
+    let end = if event.is_all_day {
+        // DTEND is exclusive, while the cached end is the last day of the event
+        let last = NaiveDate::parse_from_str(&event.end, "%Y-%m-%d").ok();
+        IcsTime::Date(last.unwrap_or(start.naive().date()) + Duration::days(1))
+    } else {
+        event_time(&event.end, false, zone).unwrap_or_else(|| start.clone())
+    };
+
+    let mut properties = vec![
+        // Ids are only unique within a calendar
+        text_property("UID", &format!("{}/{}@calendar-widget", event.calendar_id, event.id)),
+        stamp.to_property("DTSTAMP"),
+        start.to_property("DTSTART"),
+        end.to_property("DTEND"),
+        text_property("SUMMARY", &event.title),
+    ];
+    if !event.location.is_empty() {
+        properties.push(text_property("LOCATION", &event.location));
+    }
+    if !event.description.is_empty() {
+        properties.push(text_property("DESCRIPTION", &event.description));
+    }
+    if !event.status.is_empty() {
+        properties.push(property("STATUS", event.status.to_ascii_uppercase()));
+    }
+    properties.push(property(
+        "TRANSP",
+        if event.transparency == "transparent" { "TRANSPARENT" } else { "OPAQUE" }.to_string(),
+    ));
+    if !event.calendar.is_empty() {
+        properties.push(text_property("CATEGORIES", &event.calendar));
+    }
+    if !event.join_url.is_empty() {
+        properties.push(property("URL", event.join_url.clone()));
+    }
+    let cn = |name: &str| ("CN".to_string(), format!("\"{}\"", name.replace('"', "'")));
+    if let Some(organizer) = event.attendees.iter().find(|a| a.is_organizer && !a.email.is_empty()) {
+        properties.push(Property {
+            name: "ORGANIZER".to_string(),
+            params: if organizer.name.is_empty() { Vec::new() } else { vec![cn(&organizer.name)] },
+            value: format!("mailto:{}", organizer.email),
+        });
+    }
+    for attendee in event.attendees.iter().filter(|a| !a.email.is_empty()) {
+        let mut params = Vec::new();
+        if !attendee.name.is_empty() {
+            params.push(cn(&attendee.name));
+        }
+        params.push(("PARTSTAT".to_string(), participation_status(&attendee.response_status).to_string()));
+        if attendee.optional {
+            params.push(("ROLE".to_string(), "OPT-PARTICIPANT".to_string()));
+        }
+        properties.push(Property {
+            name: "ATTENDEE".to_string(),
+            params,
+            value: format!("mailto:{}", attendee.email),
+        });
+    }
+
+    Some(Component {
+        name: "VEVENT".to_string(),
+        properties,
+        components: Vec::new(),
+    })
+}
+
+/// The events as one VCALENDAR. Each event is written on its own, so recurring series come
+/// out as their individual instances, while the per-day copies of a multi-day event are
+/// written once with its real start and end. Times are written in the configured time zone.
+pub fn to_ics(events: &[&Event], locale: &LocaleSettings) -> String {
+    let zone = locale.zone();
+    let events = calendar::whole_events(events.iter().copied());
+    let stamp = IcsTime::Utc(Utc::now());
+    let vevents: Vec<Component> = events.iter().filter_map(|e| to_vevent(e, zone, &stamp)).collect();
+
+    let mut components = Vec::new();
+    if let Some(tz) = zone {
+        let times: Vec<DateTime<Utc>> = events
+            .iter()
+            .filter(|e| !e.is_all_day)
+            .flat_map(|e| [&e.start, &e.end])
+            .filter_map(|t| DateTime::parse_from_rfc3339(t).ok())
+            .map(|t| t.with_timezone(&Utc))
+            .collect();
+        if let (Some(from), Some(to)) = (times.iter().min(), times.iter().max()) {
+            components.push(vtimezone(tz, *from, *to));
+        }
+    }
+    components.extend(vevents);
+
+    Component {
+        name: "VCALENDAR".to_string(),
+        properties: vec![
+            property("VERSION", "2.0".to_string()),
+            property("PRODID", PRODID.to_string()),
+            property("CALSCALE", "GREGORIAN".to_string()),
+        ],
+        components,
+    }
+    .to_ics()
+}
+
+/// One line of an agenda
+#[derive(Debug, Clone, Serialize)]
+struct AgendaItem {
+    /// `YYYY-MM-DD`, empty for tasks without a due date
+    date: String,
+    /// "event" or "task"
+    #[serde(rename = "type")]
+    kind: &'static str,
+    /// Time range or the all-day label; empty for tasks
+    time: String,
+    title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    location: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    calendar: Option<String>,
+    /// All-day events first, then by start time, then tasks
+    #[serde(skip)]
+    order: (u8, String),
+}
+
+impl AgendaItem {
+    /// Location and calendar, for the formats that put them after the title
+    fn extras(&self) -> Vec<&str> {
+        [&self.location, &self.calendar]
+            .into_iter()
+            .flatten()
+            .map(|s| s.as_str())
+            .filter(|s| !s.is_empty())
+            .collect()
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct AgendaDay<'a> {
+    date: String,
+    label: String,
+    items: Vec<&'a AgendaItem>,
+}
+
+fn event_items(event: &Event, from: NaiveDate, to: NaiveDate, options: &AgendaSettings, locale: &LocaleSettings) -> Vec<AgendaItem> {
+    let Some((first, last)) = event_days(event, locale) else {
+        return Vec::new();
+    };
+    let times = if event.is_all_day {
+        None
+    } else {
+        let start = DateTime::parse_from_rfc3339(&event.start).ok().map(|t| locale.in_zone(&t));
+        let end = DateTime::parse_from_rfc3339(&event.end).ok().map(|t| locale.in_zone(&t));
+        start.zip(end)
+    };
+
+    let mut items = Vec::new();
+    let mut day = first.max(from);
+    while day <= last.min(to) {
+        // Timed events spanning several days show where they start and end
+        let (time, order) = match times {
+            Some((start, end)) if day == first && day == last => (
+                format!("{} - {}", locale.format_time(&start), locale.format_time(&end)),
+                (1, start.format("%H:%M").to_string()),
+            ),
+            Some((start, _)) if day == first => (
+                format!("{} {}", locale.from_label, locale.format_time(&start)),
+                (1, start.format("%H:%M").to_string()),
+            ),
+            Some((_, end)) if day == last => (
+                format!("{} {}", locale.until_label, locale.format_time(&end)),
+                (1, String::new()),
+            ),
+            _ => (locale.all_day_label.clone(), (0, String::new())),
+        };
+        items.push(AgendaItem {
+            date: day.format("%Y-%m-%d").to_string(),
+            kind: "event",
+            time,
+            title: event.title.clone(),
+            location: Some(event.location.clone()).filter(|l| options.include_location && !l.is_empty()),
+            calendar: Some(event.calendar.clone()).filter(|c| options.include_calendar && !c.is_empty()),
+            order,
+        });
+        day = match day.succ_opt() {
+            Some(next) => next,
+            None => break,
+        };
+    }
+    items
+}
+
+/// Open tasks including subtasks, parents first
+fn open_tasks(tasks: &[Task]) -> Vec<&Task> {
+    let mut out = Vec::new();
+    for task in tasks.iter().filter(|t| !t.completed) {
+        out.push(task);
+        out.extend(open_tasks(&task.subtasks));
+    }
+    out
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Renders the events and tasks from `from` to `to` (inclusive) in the requested format
+pub fn render_agenda(
+    events: &[Event],
+    tasks: &[Task],
+    from: NaiveDate,
+    to: NaiveDate,
+    options: &AgendaSettings,
+    locale: &LocaleSettings,
+) -> Result<String, String> {
+    // Multi-day events are cached as one all-day copy per day; their real times give the
+    // "from"/"until" lines
+    let mut items: Vec<AgendaItem> = calendar::whole_events(events_in_range(events, from, to, locale))
+        .iter()
+        .flat_map(|e| event_items(e, from, to, options, locale))
+        .collect();
+
+    if options.include_tasks {
+        for task in open_tasks(tasks) {
+            let due = NaiveDate::parse_from_str(&task.due, "%Y-%m-%d").ok();
+            if due.is_some_and(|d| d < from || d > to) {
+                continue;
+            }
+            items.push(AgendaItem {
+                date: due.map(|d| d.format("%Y-%m-%d").to_string()).unwrap_or_default(),
+                kind: "task",
+                time: String::new(),
+                title: task.title.clone(),
+                location: None,
+                calendar: None,
+                order: (2, task.title.clone()),
+            });
+        }
+    }
+
+    // Undated tasks sort last
+    items.sort_by(|a, b| {
+        (a.date.is_empty(), &a.date, &a.order).cmp(&(b.date.is_empty(), &b.date, &b.order))
+    });
+
+    let label = |date: &str| match NaiveDate::parse_from_str(date, "%Y-%m-%d") {
+        Ok(date) => locale.format_date(date),
+        Err(_) => locale.no_due_date_label.clone(),
+    };
+    let mut days: Vec<AgendaDay> = Vec::new();
+    for item in &items {
+        match days.last_mut() {
+            Some(day) if day.date == item.date => day.items.push(item),
+            _ => days.push(AgendaDay {
+                date: item.date.clone(),
+                label: label(&item.date),
+                items: vec![item],
+            }),
+        }
+    }
+
+    let mut out = String::new();
+    match options.format {
+        AgendaFormat::Markdown => {
+            for day in &days {
+                if options.group_by_day {
+                    out.push_str(&format!("## {}\n\n", day.label));
+                }
+                for item in &day.items {
+                    let prefix = if options.group_by_day { String::new() } else { format!("{}: ", day.label) };
+                    let line = if item.kind == "task" {
+                        format!("[ ] {}", item.title)
+                    } else {
+                        format!("{} **{}**", item.time, item.title)
+                    };
+                    let extras = item.extras();
+                    let extras = if extras.is_empty() { String::new() } else { format!(" ({})", extras.join(", ")) };
+                    out.push_str(&format!("- {}{}{}\n", prefix, line, extras));
+                }
+                if options.group_by_day {
+                    out.push('\n');
+                }
+            }
+        }
+        AgendaFormat::Text => {
+            let width = items.iter().map(|i| i.time.chars().count()).max().unwrap_or(0);
+            let label_width = days.iter().map(|d| d.label.chars().count()).max().unwrap_or(0);
+            for day in &days {
+                if options.group_by_day {
+                    out.push_str(&format!("{}\n", day.label));
+                }
+                for item in &day.items {
+                    let indent = if options.group_by_day {
+                        "  ".to_string()
+                    } else {
+                        format!("{:<label_width$}  ", day.label)
+                    };
+                    let time = if item.kind == "task" { "[ ]".to_string() } else { item.time.clone() };
+                    let extras = item.extras();
+                    let extras = if extras.is_empty() { String::new() } else { format!(" ({})", extras.join(", ")) };
+                    out.push_str(&format!("{}{:<width$}  {}{}\n", indent, time, item.title, extras, width = width.max(3)));
+                }
+                if options.group_by_day {
+                    out.push('\n');
+                }
+            }
+        }
+        AgendaFormat::Csv => {
+            let mut header = vec!["date", "time", "type", "title"];
+            if options.include_location {
+                header.push("location");
+            }
+            if options.include_calendar {
+                header.push("calendar");
+            }
+            out.push_str(&header.join(","));
+            out.push_str("\r\n");
+            for item in &items {
+                let mut row = vec![item.date.as_str(), item.time.as_str(), item.kind, item.title.as_str()];
+                if options.include_location {
+                    row.push(item.location.as_deref().unwrap_or(""));
+                }
+                if options.include_calendar {
+                    row.push(item.calendar.as_deref().unwrap_or(""));
+                }
+                let row: Vec<String> = row.into_iter().map(csv_field).collect();
+                out.push_str(&row.join(","));
+                out.push_str("\r\n");
+            }
+        }
+        AgendaFormat::Json => {
+            out = if options.group_by_day {
+                serde_json::to_string_pretty(&days)
+            } else {
+                serde_json::to_string_pretty(&items)
+            }
+            .map_err(|e| format!("Failed to serialize agenda: {}", e))?;
+            out.push('\n');
+        }
+    }
+
+    Ok(out.trim_end().to_string() + "\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn locale() -> LocaleSettings {
+        LocaleSettings {
+            date_format: "%a %-d %b".to_string(),
+            timezone: "Europe/Oslo".to_string(),
+            ..LocaleSettings::default()
+        }
+    }
+
+    fn event(id: &str, title: &str, start: &str, end: &str) -> Event {
+        Event {
+            id: id.to_string(),
+            calendar_id: "work".to_string(),
+            calendar: "Work".to_string(),
+            title: title.to_string(),
+            date: start[..10].to_string(),
+            start: start.to_string(),
+            end: end.to_string(),
+            is_all_day: start.len() == 10,
+            ..Event::default()
+        }
+    }
+
+    /// A standup, an all-day offsite and a night shift cached as one all-day copy per day
+    fn events() -> Vec<Event> {
+        let mut standup = event("standup", "Standup", "2026-10-14T09:00:00+02:00", "2026-10-14T09:15:00+02:00");
+        standup.location = "Room 1, \"Fjord\"".to_string();
+        let offsite = event("offsite", "Offsite", "2026-10-14", "2026-10-14");
+        let shift = ["2026-10-14", "2026-10-15"].map(|day| Event {
+            span_start: "2026-10-14T22:00:00+02:00".to_string(),
+            span_end: "2026-10-15T06:00:00+02:00".to_string(),
+            ..event("shift", "Night shift", day, day)
+        });
+        let outside = event("later", "Next week", "2026-10-20T09:00:00+02:00", "2026-10-20T10:00:00+02:00");
+        vec![standup, offsite, shift[0].clone(), shift[1].clone(), outside]
+    }
+
+    fn tasks() -> Vec<Task> {
+        let task = |title: &str, due: &str| Task {
+            title: title.to_string(),
+            due: due.to_string(),
+            ..Task::default()
+        };
+        vec![
+            task("Someday", ""),
+            task("Report", "2026-10-15"),
+            task("Too late", "2026-10-16"),
+            Task {
+                completed: true,
+                ..task("Done", "2026-10-15")
+            },
+        ]
+    }
+
+    fn render(format: AgendaFormat, group_by_day: bool) -> String {
+        let options = AgendaSettings {
+            format,
+            group_by_day,
+            include_location: true,
+            include_calendar: false,
+            include_tasks: true,
+        };
+        let day = |d: &str| NaiveDate::parse_from_str(d, "%Y-%m-%d").unwrap();
+        render_agenda(&events(), &tasks(), day("2026-10-14"), day("2026-10-15"), &options, &locale()).unwrap()
+    }
+
+    #[test]
+    fn agendas() {
+        let cases: &[(AgendaFormat, bool, &str)] = &[
+            (
+                AgendaFormat::Markdown,
+                true,
+                "## Wed 14 Oct\n\n\
+                 - All day **Offsite**\n\
+                 - 09:00 - 09:15 **Standup** (Room 1, \"Fjord\")\n\
+                 - from 22:00 **Night shift**\n\n\
+                 ## Thu 15 Oct\n\n\
+                 - until 06:00 **Night shift**\n\
+                 - [ ] Report\n\n\
+                 ## No due date\n\n\
+                 - [ ] Someday\n",
+            ),
+            (
+                AgendaFormat::Markdown,
+                false,
+                "- Wed 14 Oct: All day **Offsite**\n\
+                 - Wed 14 Oct: 09:00 - 09:15 **Standup** (Room 1, \"Fjord\")\n\
+                 - Wed 14 Oct: from 22:00 **Night shift**\n\
+                 - Thu 15 Oct: until 06:00 **Night shift**\n\
+                 - Thu 15 Oct: [ ] Report\n\
+                 - No due date: [ ] Someday\n",
+            ),
+            (
+                AgendaFormat::Text,
+                true,
+                "Wed 14 Oct\n\
+                 \x20 All day        Offsite\n\
+                 \x20 09:00 - 09:15  Standup (Room 1, \"Fjord\")\n\
+                 \x20 from 22:00     Night shift\n\n\
+                 Thu 15 Oct\n\
+                 \x20 until 06:00    Night shift\n\
+                 \x20 [ ]            Report\n\n\
+                 No due date\n\
+                 \x20 [ ]            Someday\n",
+            ),
+            (
+                AgendaFormat::Text,
+                false,
+                "Wed 14 Oct   All day        Offsite\n\
+                 Wed 14 Oct   09:00 - 09:15  Standup (Room 1, \"Fjord\")\n\
+                 Wed 14 Oct   from 22:00     Night shift\n\
+                 Thu 15 Oct   until 06:00    Night shift\n\
+                 Thu 15 Oct   [ ]            Report\n\
+                 No due date  [ ]            Someday\n",
+            ),
+        ];
+        for (format, group_by_day, expected) in cases {
+            assert_eq!(render(*format, *group_by_day), *expected, "{:?}, grouped: {}", format, group_by_day);
+        }
+    }
+
+    #[test]
+    fn csv_agenda() {
+        let expected = "date,time,type,title,location\r\n\
+                        2026-10-14,All day,event,Offsite,\r\n\
+                        2026-10-14,09:00 - 09:15,event,Standup,\"Room 1, \"\"Fjord\"\"\"\r\n\
+                        2026-10-14,from 22:00,event,Night shift,\r\n\
+                        2026-10-15,until 06:00,event,Night shift,\r\n\
+                        2026-10-15,,task,Report,\r\n\
+                        ,,task,Someday,\n";
+        // Always one row per item
+        for group_by_day in [true, false] {
+            assert_eq!(render(AgendaFormat::Csv, group_by_day), expected);
+        }
+    }
+
+    #[test]
+    fn json_agenda() {
+        let titles = |items: &serde_json::Value| -> Vec<String> {
+            items.as_array().unwrap().iter().map(|i| format!("{} {}", i["time"], i["title"])).collect()
+        };
+
+        let days: serde_json::Value = serde_json::from_str(&render(AgendaFormat::Json, true)).unwrap();
+        let labels: Vec<&str> = days.as_array().unwrap().iter().map(|d| d["label"].as_str().unwrap()).collect();
+        assert_eq!(labels, ["Wed 14 Oct", "Thu 15 Oct", "No due date"]);
+        assert_eq!(titles(&days[1]["items"]), ["\"until 06:00\" \"Night shift\"", "\"\" \"Report\""]);
+
+        let items: serde_json::Value = serde_json::from_str(&render(AgendaFormat::Json, false)).unwrap();
+        assert_eq!(items.as_array().unwrap().len(), 6);
+        assert_eq!(items[1]["location"], "Room 1, \"Fjord\"");
+        assert!(items[0].get("location").is_none());
+        assert_eq!(items[5]["date"], "");
+        assert_eq!(items[5]["type"], "task");
+    }
+
+    #[test]
+    fn agenda_follows_the_locale() {
+        let locale = LocaleSettings {
+            timezone: "Europe/London".to_string(),
+            from_label: "fra".to_string(),
+            until_label: "til".to_string(),
+            no_due_date_label: "Uten frist".to_string(),
+            ..locale()
+        };
+        let options = AgendaSettings {
+            format: AgendaFormat::Text,
+            group_by_day: false,
+            include_location: false,
+            include_calendar: true,
+            include_tasks: true,
+        };
+        let day = |d: &str| NaiveDate::parse_from_str(d, "%Y-%m-%d").unwrap();
+        let agenda = render_agenda(&events(), &tasks(), day("2026-10-14"), day("2026-10-15"), &options, &locale).unwrap();
+        // Times are shown an hour earlier in London
+        assert_eq!(
+            agenda,
+            "Wed 14 Oct  All day        Offsite (Work)\n\
+             Wed 14 Oct  08:00 - 08:15  Standup (Work)\n\
+             Wed 14 Oct  fra 21:00      Night shift (Work)\n\
+             Thu 15 Oct  til 05:00      Night shift (Work)\n\
+             Thu 15 Oct  [ ]            Report\n\
+             Uten frist  [ ]            Someday\n"
+        );
+    }
+
+    /// The VEVENTs and VTIMEZONEs of an export
+    fn export(events: &[Event]) -> Vec<Component> {
+        let refs: Vec<&Event> = events.iter().collect();
+        let mut vcalendar = ics::parse(&to_ics(&refs, &locale())).unwrap().remove(0);
+        assert_eq!(vcalendar.name, "VCALENDAR");
+        std::mem::take(&mut vcalendar.components)
+    }
+
+    fn value(component: &Component, name: &str) -> String {
+        let property = component.property(name).unwrap();
+        let params: String = property.params.iter().map(|(k, v)| format!(";{}={}", k, v)).collect();
+        format!("{}{}:{}", name, params, property.value)
+    }
+
+    #[test]
+    fn exports_across_a_dst_change() {
+        // Oslo leaves summer time on 25 October
+        let events = [
+            event("a", "Before", "2026-10-21T10:00:00+02:00", "2026-10-21T11:00:00+02:00"),
+            event("b", "After", "2026-10-28T10:00:00+01:00", "2026-10-28T11:00:00+01:00"),
+        ];
+        let components = export(&events);
+
+        let vtimezone = &components[0];
+        assert_eq!(value(vtimezone, "TZID"), "TZID:Europe/Oslo");
+        let onsets: Vec<(String, String, String, String)> = vtimezone
+            .components
+            .iter()
+            .map(|o| (o.name.clone(), value(o, "DTSTART"), value(o, "TZOFFSETFROM"), value(o, "TZOFFSETTO")))
+            .collect();
+        assert_eq!(
+            onsets,
+            [
+                ("DAYLIGHT".into(), "DTSTART:20261020T020000".into(), "TZOFFSETFROM:+0200".into(), "TZOFFSETTO:+0200".into()),
+                ("STANDARD".into(), "DTSTART:20261025T030000".into(), "TZOFFSETFROM:+0200".into(), "TZOFFSETTO:+0100".into()),
+            ]
+        );
+        assert_eq!(value(&components[1], "DTSTART"), "DTSTART;TZID=Europe/Oslo:20261021T100000");
+        assert_eq!(value(&components[2], "DTSTART"), "DTSTART;TZID=Europe/Oslo:20261028T100000");
+
+        // Read back by a widget in another zone, the events keep their instants
+        let refs: Vec<&Event> = events.iter().collect();
+        let calendar = calendar::Calendar {
+            id: "import".to_string(),
+            name: "Import".to_string(),
+            color: String::new(),
+            foreground_color: String::new(),
+            primary: false,
+            writable: false,
+        };
+        let mut settings = crate::settings::Settings::default();
+        settings.locale.timezone = "America/New_York".to_string();
+        let window = |s: &str| DateTime::parse_from_rfc3339(s).unwrap();
+        let imported = ics::events_from_ics(
+            &to_ics(&refs, &locale()),
+            &calendar,
+            (window("2026-10-19T00:00:00Z"), window("2026-11-01T00:00:00Z")),
+            "",
+            &settings,
+        )
+        .unwrap();
+        let instant = |s: &str| DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc);
+        let times: Vec<_> = imported.iter().map(|e| (e.title.as_str(), instant(&e.start), instant(&e.end))).collect();
+        let expected: Vec<_> = events.iter().map(|e| (e.title.as_str(), instant(&e.start), instant(&e.end))).collect();
+        assert_eq!(times, expected);
+    }
+
+    #[test]
+    fn all_day_end_is_exclusive() {
+        let components = export(&[event("trip", "Trip", "2026-10-14", "2026-10-16")]);
+        // No timed events, so no VTIMEZONE
+        assert_eq!(components.len(), 1);
+        assert_eq!(value(&components[0], "DTSTART"), "DTSTART;VALUE=DATE:20261014");
+        assert_eq!(value(&components[0], "DTEND"), "DTEND;VALUE=DATE:20261017");
+    }
+
+    #[test]
+    fn multi_day_events_are_written_once() {
+        let components = export(&events()[1..4]);
+        let vevents: Vec<&Component> = components.iter().filter(|c| c.name == "VEVENT").collect();
+        assert_eq!(vevents.len(), 2);
+        let shift = vevents[1];
+        assert_eq!(value(shift, "UID"), "UID:work/shift@calendar-widget");
+        assert_eq!(value(shift, "DTSTART"), "DTSTART;TZID=Europe/Oslo:20261014T220000");
+        assert_eq!(value(shift, "DTEND"), "DTEND;TZID=Europe/Oslo:20261015T060000");
+        assert_eq!(value(vevents[0], "UID"), "UID:work/offsite@calendar-widget");
+    }
+}
@@ -0,0 +1,230 @@
+//! Read-only iCalendar subscriptions (.ics URLs such as holiday calendars or team rotas),
+//! downloaded with conditional GET so unchanged feeds aren't transferred again.
+
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
+
+use crate::calendar::{self, Calendar, Event};
+use crate::ics;
+use crate::provider::CalendarProvider;
+use crate::settings::{IcsFeed, Settings};
+
+/// Last response per feed URL, revalidated with If-None-Match / If-Modified-Since
+#[derive(Debug, Clone)]
+struct CachedFeed {
+    etag: Option<String>,
+    last_modified: Option<String>,
+    body: String,
+}
+
+static FEED_CACHE: LazyLock<Mutex<HashMap<String, CachedFeed>>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// webcal:// is just a hint for calendar apps; the feed itself is served over HTTPS
+fn feed_url(url: &str) -> String {
+    match url.strip_prefix("webcal://") {
+        Some(rest) => format!("https://{}", rest),
+        None => url.to_string(),
+    }
+}
+
+/// GET with the validators of the cached response, if any
+fn request(url: &str, cached: Option<&CachedFeed>) -> reqwest::RequestBuilder {
+    let mut request = reqwest::Client::new().get(url);
+    if let Some(cached) = cached {
+        if let Some(etag) = &cached.etag {
+            request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &cached.last_modified {
+            request = request.header(reqwest::header::IF_MODIFIED_SINCE, last_modified);
+        }
+    }
+    request
+}
+
+async fn send(url: &str, request: reqwest::RequestBuilder) -> Result<reqwest::Response, String> {
+    request
+        .send()
+        .await
+        .map_err(|e| format!("Failed to download {}: {}", url, e))
+}
+
+async fn download(url: &str) -> Result<String, String> {
+    let cached = FEED_CACHE.lock().unwrap().get(url).cloned();
+
+    let mut response = send(url, request(url, cached.as_ref())).await?;
+    if response.status() == reqwest::StatusCode::NOT_MODIFIED {
+        match cached {
+            Some(cached) => return Ok(cached.body),
+            // Nothing to reuse, so a cache on the way answered for us: ask again, past any caches
+            None => {
+                let request = request(url, None).header(reqwest::header::CACHE_CONTROL, "no-cache");
+                response = send(url, request).await?;
+            }
+        }
+    }
+    if !response.status().is_success() {
+        return Err(format!("Feed {} returned {}", url, response.status()));
+    }
+
+    let header = |name: reqwest::header::HeaderName| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.to_string())
+    };
+    let etag = header(reqwest::header::ETAG);
+    let last_modified = header(reqwest::header::LAST_MODIFIED);
+
+    let body = response
+        .text()
+        .await
+        .map_err(|e| format!("Failed to read {}: {}", url, e))?;
+
+    FEED_CACHE.lock().unwrap().insert(
+        url.to_string(),
+        CachedFeed {
+            etag,
+            last_modified,
+            body: body.clone(),
+        },
+    );
+    Ok(body)
+}
+
+/// Calendar metadata for a feed: configured values first, then X-WR-CALNAME / X-APPLE-CALENDAR-COLOR
+pub fn feed_calendar(id: &str, name: &str, color: &str, data: &str) -> Calendar {
+    let vcalendar = ics::parse(data).ok().and_then(|c| c.into_iter().next());
+    let property = |key: &str| vcalendar.as_ref().and_then(|c| c.text(key)).filter(|v| !v.is_empty());
+
+    let name = if name.is_empty() {
+        property("X-WR-CALNAME").unwrap_or_else(|| id.to_string())
+    } else {
+        name.to_string()
+    };
+    let color = if color.is_empty() {
+        property("X-APPLE-CALENDAR-COLOR")
+            .map(|c| c.chars().take(7).collect())
+            .unwrap_or_else(|| "#64748b".to_string())
+    } else {
+        color.to_string()
+    };
+
+    Calendar {
+        id: id.to_string(),
+        name,
+        color,
+        foreground_color: "#ffffff".to_string(),
+        primary: false,
+        writable: false,
+    }
+}
+
+pub struct IcsFeedProvider {
+    feed: IcsFeed,
+}
+
+impl IcsFeedProvider {
+    pub fn new(feed: IcsFeed) -> Self {
+        IcsFeedProvider { feed }
+    }
+}
+
+#[async_trait]
+impl CalendarProvider for IcsFeedProvider {
+    fn id(&self) -> String {
+        format!("ics:{}", self.feed.url)
+    }
+
+    async fn get_events(&self, days: i32, settings: &Settings) -> Result<Vec<Event>, String> {
+        let url = feed_url(&self.feed.url);
+        let data = download(&url).await?;
+
+        let calendar = feed_calendar(&url, &self.feed.name, &self.feed.color, &data);
+        let window = calendar::event_window(days, &settings.locale);
+        let mut events = ics::events_from_ics(&data, &calendar, window, "", settings)?;
+
+        calendar::sort_events(&mut events);
+        Ok(events)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn webcal_is_fetched_over_https() {
+        assert_eq!(feed_url("webcal://example.com/holidays.ics"), "https://example.com/holidays.ics");
+        assert_eq!(feed_url("https://example.com/rota.ics"), "https://example.com/rota.ics");
+        assert_eq!(feed_url("http://intranet/rota.ics"), "http://intranet/rota.ics");
+    }
+
+    #[test]
+    fn calendar_metadata() {
+        let feed = |properties: &str| format!("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n{}END:VCALENDAR\r\n", properties);
+        let named = feed("X-WR-CALNAME:Norwegian holidays\r\nX-APPLE-CALENDAR-COLOR:#E6C800FF\r\n");
+        let cases = [
+            // Configured values win
+            ("Holidays", "#ef4444", named.as_str(), "Holidays", "#ef4444"),
+            // Then the feed's own, with the alpha of #RRGGBBAA dropped
+            ("", "", named.as_str(), "Norwegian holidays", "#E6C800"),
+            ("", "", &feed("X-WR-CALNAME:\r\nX-APPLE-CALENDAR-COLOR:#1BADF8\r\n"), "https://example.com/feed.ics", "#1BADF8"),
+            ("", "", &feed(""), "https://example.com/feed.ics", "#64748b"),
+            ("", "", "not a calendar", "https://example.com/feed.ics", "#64748b"),
+        ];
+        for (name, color, data, expected_name, expected_color) in cases {
+            let calendar = feed_calendar("https://example.com/feed.ics", name, color, data);
+            assert_eq!((calendar.name.as_str(), calendar.color.as_str()), (expected_name, expected_color), "{}", data);
+            assert!(!calendar.writable);
+        }
+    }
+
+    /// A feed server answering each request with the next of `statuses`, and recording the
+    /// conditional headers it was sent
+    fn serve(statuses: &'static [u16]) -> (String, std::sync::mpsc::Receiver<Vec<String>>) {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/feed.ics", server.server_addr().to_ip().unwrap());
+        let (sender, received) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            for (request, status) in server.incoming_requests().zip(statuses) {
+                let headers = request
+                    .headers()
+                    .iter()
+                    .filter(|h| ["If-None-Match", "Cache-Control"].iter().any(|name| h.field.equiv(name)))
+                    .map(|h| format!("{}: {}", h.field, h.value))
+                    .collect();
+                let _ = sender.send(headers);
+                let response = tiny_http::Response::from_string(format!("BODY {}", status))
+                    .with_status_code(*status)
+                    .with_header("ETag: \"v1\"".parse::<tiny_http::Header>().unwrap());
+                let _ = request.respond(response);
+            }
+        });
+        (url, received)
+    }
+
+    #[tokio::test]
+    async fn revalidates_with_the_etag() {
+        let (url, received) = serve(&[200, 304]);
+        assert_eq!(download(&url).await.unwrap(), "BODY 200");
+        assert!(received.recv().unwrap().is_empty());
+
+        // Unchanged: the cached body is used
+        assert_eq!(download(&url).await.unwrap(), "BODY 200");
+        assert_eq!(received.recv().unwrap(), ["if-none-match: \"v1\""]);
+    }
+
+    #[tokio::test]
+    async fn not_modified_without_a_cached_copy_is_fetched_again() {
+        let (url, received) = serve(&[304, 200]);
+        assert_eq!(download(&url).await.unwrap(), "BODY 200");
+        assert!(received.recv().unwrap().is_empty());
+        assert_eq!(received.recv().unwrap(), ["cache-control: no-cache"]);
+
+        // Only once
+        let (url, _) = serve(&[304, 304, 200]);
+        assert!(download(&url).await.unwrap_err().contains("304"));
+    }
+}
@@ -0,0 +1,973 @@
+//! Minimal RFC 5545 reader and writer: line folding, properties with parameters and nested
+//! components, VTIMEZONE offsets, recurrence expansion, and the mapping from VEVENT to the event
+//! resource shape `calendar.rs` understands.
+
+use chrono::{DateTime, Duration, FixedOffset, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use std::collections::HashMap;
+
+use crate::calendar::{self, Calendar, Event};
+use crate::recurrence::RRule;
+use crate::settings::{LocaleSettings, Settings};
+
+#[derive(Debug, Clone)]
+pub struct Property {
+    pub name: String,
+    pub params: Vec<(String, String)>,
+    pub value: String,
+}
+
+impl Property {
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Value with TEXT escapes resolved
+    pub fn text(&self) -> String {
+        let mut text = String::with_capacity(self.value.len());
+        let mut chars = self.value.chars();
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                text.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some('n') | Some('N') => text.push('\n'),
+                Some(other) => text.push(other),
+                None => {}
+            }
+        }
+        text
+    }
+
+    /// The property as a content line without folding, e.g. "EXDATE;TZID=Europe/Oslo:20240102T090000"
+    pub fn to_line(&self) -> String {
+        let mut line = self.name.clone();
+        for (key, value) in &self.params {
+            line.push_str(&format!(";{}={}", key, value));
+        }
+        line.push(':');
+        line.push_str(&self.value);
+        line
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Component {
+    pub name: String,
+    pub properties: Vec<Property>,
+    pub components: Vec<Component>,
+}
+
+impl Component {
+    pub fn property(&self, name: &str) -> Option<&Property> {
+        self.properties.iter().find(|p| p.name == name)
+    }
+
+    pub fn properties<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Property> {
+        self.properties.iter().filter(move |p| p.name == name)
+    }
+
+    pub fn text(&self, name: &str) -> Option<String> {
+        self.property(name).map(|p| p.text())
+    }
+
+    pub fn children<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Component> {
+        self.components.iter().filter(move |c| c.name == name)
+    }
+
+    /// Replaces every property called `property.name` with `property`
+    pub fn set_property(&mut self, property: Property) {
+        self.properties.retain(|p| p.name != property.name);
+        self.properties.push(property);
+    }
+
+    /// The component and its children as folded content lines
+    pub fn to_ics(&self) -> String {
+        let mut text = format!("BEGIN:{}\r\n", self.name);
+        for property in &self.properties {
+            text.push_str(&fold(&property.to_line()));
+        }
+        for component in &self.components {
+            text.push_str(&component.to_ics());
+        }
+        text.push_str(&format!("END:{}\r\n", self.name));
+        text
+    }
+}
+
+/// Splits a content line into lines of at most 75 octets, continuations starting with a space,
+/// without breaking UTF-8 sequences
+pub fn fold(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len() + line.len() / 74 * 3 + 2);
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            width = 1;
+        }
+        folded.push(c);
+        width += c.len_utf8();
+    }
+    folded.push_str("\r\n");
+    folded
+}
+
+/// Escapes a TEXT value: backslashes, semicolons, commas and line breaks
+pub fn escape_text(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+/// Parses an iCalendar stream into its top-level components (normally one VCALENDAR)
+pub fn parse(text: &str) -> Result<Vec<Component>, String> {
+    let mut stack: Vec<Component> = Vec::new();
+    let mut top_level = Vec::new();
+
+    for line in unfold(text) {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let property = parse_line(&line)?;
+        match property.name.as_str() {
+            "BEGIN" => stack.push(Component {
+                name: property.value.trim().to_ascii_uppercase(),
+                properties: Vec::new(),
+                components: Vec::new(),
+            }),
+            "END" => {
+                let component = stack
+                    .pop()
+                    .ok_or_else(|| format!("Unexpected END:{}", property.value))?;
+                if !component.name.eq_ignore_ascii_case(property.value.trim()) {
+                    return Err(format!("END:{} does not close BEGIN:{}", property.value, component.name));
+                }
+                match stack.last_mut() {
+                    Some(parent) => parent.components.push(component),
+                    None => top_level.push(component),
+                }
+            }
+            // Stray properties outside any component are ignored
+            _ => {
+                if let Some(component) = stack.last_mut() {
+                    component.properties.push(property);
+                }
+            }
+        }
+    }
+
+    if let Some(open) = stack.last() {
+        return Err(format!("Missing END:{}", open.name));
+    }
+    Ok(top_level)
+}
+
+/// Joins folded continuation lines (those starting with a space or tab)
+fn unfold(text: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for raw in text.split('\n') {
+        let raw = raw.strip_suffix('\r').unwrap_or(raw);
+        if let Some(rest) = raw.strip_prefix(' ').or_else(|| raw.strip_prefix('\t')) {
+            if let Some(last) = lines.last_mut() {
+                last.push_str(rest);
+                continue;
+            }
+        }
+        lines.push(raw.to_string());
+    }
+    lines
+}
+
+fn parse_line(line: &str) -> Result<Property, String> {
+    // Find the ':' that ends the name and parameters, skipping quoted parameter values
+    let mut in_quotes = false;
+    let mut split = None;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            ':' if !in_quotes => {
+                split = Some(i);
+                break;
+            }
+            _ => {}
+        }
+    }
+    let split = split.ok_or_else(|| format!("Invalid content line '{}'", line))?;
+    let (head, value) = (&line[..split], &line[split + 1..]);
+
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    in_quotes = false;
+    for c in head.chars() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            ';' if !in_quotes => parts.push(std::mem::take(&mut current)),
+            _ => current.push(c),
+        }
+    }
+    parts.push(current);
+
+    let name = parts.remove(0).trim().to_ascii_uppercase();
+    if name.is_empty() {
+        return Err(format!("Invalid content line '{}'", line));
+    }
+    let params = parts
+        .into_iter()
+        .filter_map(|part| {
+            part.split_once('=')
+                .map(|(key, value)| (key.trim().to_ascii_uppercase(), value.to_string()))
+        })
+        .collect();
+
+    Ok(Property {
+        name,
+        params,
+        value: value.to_string(),
+    })
+}
+
+/// A DATE or DATE-TIME value
+#[derive(Debug, Clone, PartialEq)]
+pub enum IcsTime {
+    Date(NaiveDate),
+    Utc(DateTime<Utc>),
+    /// Wall-clock time in the given TZID, or floating when there is none
+    Local(NaiveDateTime, Option<String>),
+}
+
+impl IcsTime {
+    pub fn parse(property: &Property) -> Result<IcsTime, String> {
+        let tzid = property.param("TZID").map(|t| t.to_string());
+        Self::parse_value(property.value.trim(), property.param("VALUE"), tzid)
+    }
+
+    pub fn parse_value(value: &str, value_type: Option<&str>, tzid: Option<String>) -> Result<IcsTime, String> {
+        if value_type.is_some_and(|t| t.eq_ignore_ascii_case("DATE")) || value.len() == 8 {
+            return NaiveDate::parse_from_str(value, "%Y%m%d")
+                .map(IcsTime::Date)
+                .map_err(|e| format!("Invalid date '{}': {}", value, e));
+        }
+        if let Some(utc) = value.strip_suffix('Z') {
+            return NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S")
+                .map(|dt| IcsTime::Utc(Utc.from_utc_datetime(&dt)))
+                .map_err(|e| format!("Invalid date-time '{}': {}", value, e));
+        }
+        NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S")
+            .map(|dt| IcsTime::Local(dt, tzid))
+            .map_err(|e| format!("Invalid date-time '{}': {}", value, e))
+    }
+
+    pub fn is_date(&self) -> bool {
+        matches!(self, IcsTime::Date(_))
+    }
+
+    /// Wall-clock value, with date-only values at midnight
+    pub fn naive(&self) -> NaiveDateTime {
+        match self {
+            IcsTime::Date(date) => date.and_time(chrono::NaiveTime::MIN),
+            IcsTime::Utc(dt) => dt.naive_utc(),
+            IcsTime::Local(naive, _) => *naive,
+        }
+    }
+
+    /// Same kind and zone as `self`, at another wall-clock time
+    pub fn with_naive(&self, naive: NaiveDateTime) -> IcsTime {
+        match self {
+            IcsTime::Date(_) => IcsTime::Date(naive.date()),
+            IcsTime::Utc(_) => IcsTime::Utc(Utc.from_utc_datetime(&naive)),
+            IcsTime::Local(_, tzid) => IcsTime::Local(naive, tzid.clone()),
+        }
+    }
+
+    pub fn to_property(&self, name: &str) -> Property {
+        let (params, value) = match self {
+            IcsTime::Date(date) => (
+                vec![("VALUE".to_string(), "DATE".to_string())],
+                date.format("%Y%m%d").to_string(),
+            ),
+            IcsTime::Utc(dt) => (Vec::new(), dt.format("%Y%m%dT%H%M%SZ").to_string()),
+            IcsTime::Local(naive, tzid) => (
+                tzid.iter().map(|t| ("TZID".to_string(), t.clone())).collect(),
+                naive.format("%Y%m%dT%H%M%S").to_string(),
+            ),
+        };
+        Property {
+            name: name.to_string(),
+            params,
+            value,
+        }
+    }
+
+    /// Absolute time. Floating times and zones chrono-tz doesn't know use the configured zone.
+    pub fn resolve(&self, locale: &LocaleSettings) -> Option<DateTime<FixedOffset>> {
+        match self {
+            IcsTime::Date(date) => locale.localize(date.and_hms_opt(0, 0, 0)?),
+            IcsTime::Utc(dt) => Some(dt.fixed_offset()),
+            IcsTime::Local(naive, tzid) => match tzid.as_deref().and_then(parse_tzid) {
+                Some(tz) => tz.from_local_datetime(naive).earliest().map(|dt| dt.fixed_offset()),
+                None => locale.localize(*naive),
+            },
+        }
+    }
+}
+
+/// One STANDARD or DAYLIGHT block of a VTIMEZONE
+#[derive(Debug, Clone)]
+struct Observance {
+    /// First onset, in local time before the change
+    start: NaiveDateTime,
+    offset_from: FixedOffset,
+    offset_to: FixedOffset,
+    rule: Option<RRule>,
+    rdates: Vec<NaiveDateTime>,
+}
+
+impl Observance {
+    /// Latest onset at or before `local`
+    fn last_onset(&self, local: NaiveDateTime) -> Option<NaiveDateTime> {
+        let mut onsets = match &self.rule {
+            // Rules repeat at least yearly; one that ended earlier is walked from the start
+            Some(rule) => match rule.occurrences(self.start, local - Duration::days(366), local) {
+                recent if recent.is_empty() => rule.occurrences(self.start, self.start, local),
+                recent => recent,
+            },
+            None => vec![self.start],
+        };
+        onsets.extend(self.rdates.iter().copied());
+        onsets.into_iter().filter(|onset| *onset <= local).max()
+    }
+}
+
+/// The VTIMEZONE definitions of a VCALENDAR, for TZIDs that aren't IANA names
+/// (Outlook's "W. Europe Standard Time" and the like)
+#[derive(Debug, Clone, Default)]
+pub struct Timezones {
+    zones: HashMap<String, Vec<Observance>>,
+}
+
+impl Timezones {
+    pub fn from_calendar(vcalendar: &Component) -> Timezones {
+        let mut zones = HashMap::new();
+        for vtimezone in vcalendar.children("VTIMEZONE") {
+            let Some(tzid) = vtimezone.text("TZID") else {
+                continue;
+            };
+            let observances: Vec<Observance> = vtimezone
+                .components
+                .iter()
+                .filter(|c| c.name == "STANDARD" || c.name == "DAYLIGHT")
+                .filter_map(|c| {
+                    Some(Observance {
+                        start: IcsTime::parse(c.property("DTSTART")?).ok()?.naive(),
+                        offset_from: parse_utc_offset(&c.property("TZOFFSETFROM")?.value)?,
+                        offset_to: parse_utc_offset(&c.property("TZOFFSETTO")?.value)?,
+                        rule: c.property("RRULE").and_then(|p| RRule::parse(&p.value).ok()),
+                        rdates: c
+                            .properties("RDATE")
+                            .flat_map(|p| p.value.split(',').map(|v| v.to_string()).collect::<Vec<_>>())
+                            .filter_map(|v| IcsTime::parse_value(&v, None, None).ok())
+                            .map(|t| t.naive())
+                            .collect(),
+                    })
+                })
+                .collect();
+            if !observances.is_empty() {
+                zones.insert(tzid, observances);
+            }
+        }
+        Timezones { zones }
+    }
+
+    fn offset_at(&self, tzid: &str, local: NaiveDateTime) -> Option<FixedOffset> {
+        let observances = self.zones.get(tzid)?;
+        let current = observances
+            .iter()
+            .filter_map(|o| o.last_onset(local).map(|onset| (onset, o.offset_to)))
+            .max_by_key(|(onset, _)| *onset);
+        match current {
+            Some((_, offset)) => Some(offset),
+            // Before the first onset the zone is at its "from" offset
+            None => observances.iter().min_by_key(|o| o.start).map(|o| o.offset_from),
+        }
+    }
+
+    /// Absolute time for a value. IANA TZIDs go through chrono-tz, others through the
+    /// calendar's VTIMEZONE; floating times and unknown zones use the configured zone.
+    pub fn resolve(&self, time: &IcsTime, locale: &LocaleSettings) -> Option<DateTime<FixedOffset>> {
+        if let IcsTime::Local(naive, Some(tzid)) = time {
+            if parse_tzid(tzid).is_none() {
+                if let Some(offset) = self.offset_at(tzid, *naive) {
+                    return offset.from_local_datetime(naive).earliest();
+                }
+            }
+        }
+        time.resolve(locale)
+    }
+
+    /// Wall-clock time in `reference`'s zone for an absolute UTC time
+    fn to_wall_clock(&self, utc: NaiveDateTime, reference: &IcsTime, locale: &LocaleSettings) -> NaiveDateTime {
+        let offset = match reference {
+            IcsTime::Utc(_) => return utc,
+            IcsTime::Date(_) => None,
+            IcsTime::Local(_, Some(tzid)) => match parse_tzid(tzid) {
+                Some(tz) => return tz.from_utc_datetime(&utc).naive_local(),
+                None => self.offset_at(tzid, utc),
+            },
+            IcsTime::Local(_, None) => None,
+        };
+        let offset = offset
+            .or_else(|| locale.localize(utc).map(|dt| *dt.offset()))
+            .unwrap_or_else(|| FixedOffset::east_opt(0).unwrap());
+        utc + Duration::seconds(offset.local_minus_utc() as i64)
+    }
+}
+
+/// TZOFFSETFROM/TZOFFSETTO values such as "+0200" or "-053000"
+fn parse_utc_offset(value: &str) -> Option<FixedOffset> {
+    let value = value.trim();
+    let (sign, digits) = match value.split_at_checked(1)? {
+        ("+", rest) => (1, rest),
+        ("-", rest) => (-1, rest),
+        _ => return None,
+    };
+    let hours: i32 = digits.get(0..2)?.parse().ok()?;
+    let minutes: i32 = digits.get(2..4)?.parse().ok()?;
+    let seconds: i32 = digits.get(4..6).and_then(|s| s.parse().ok()).unwrap_or(0);
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60 + seconds))
+}
+
+/// IANA zone for a TZID, also accepting vendor prefixes such as "/mozilla.org/20050126_1/Europe/Oslo"
+pub fn parse_tzid(tzid: &str) -> Option<Tz> {
+    let tzid = tzid.trim_matches('"');
+    let segments: Vec<&str> = tzid.split('/').collect();
+    (0..segments.len()).find_map(|start| segments[start..].join("/").parse::<Tz>().ok())
+}
+
+/// RFC 5545 DURATION such as "PT1H30M", "P1D" or "-P1W"
+pub fn parse_duration(value: &str) -> Result<Duration, String> {
+    let invalid = || format!("Invalid duration '{}'", value);
+    let (negative, rest) = match value.trim().strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, value.trim().strip_prefix('+').unwrap_or(value.trim())),
+    };
+    let rest = rest.strip_prefix('P').ok_or_else(invalid)?;
+
+    let mut total = Duration::zero();
+    let mut number = String::new();
+    for c in rest.chars() {
+        match c {
+            '0'..='9' => number.push(c),
+            'T' => {}
+            'W' | 'D' | 'H' | 'M' | 'S' => {
+                let n: i64 = number.parse().map_err(|_| invalid())?;
+                number.clear();
+                total += match c {
+                    'W' => Duration::weeks(n),
+                    'D' => Duration::days(n),
+                    'H' => Duration::hours(n),
+                    'M' => Duration::minutes(n),
+                    _ => Duration::seconds(n),
+                };
+            }
+            _ => return Err(invalid()),
+        }
+    }
+    if !number.is_empty() {
+        return Err(invalid());
+    }
+    Ok(if negative { -total } else { total })
+}
+
+/// Start and end of a VEVENT. A missing DTEND falls back to DURATION, then to one day for
+/// all-day events and zero length for timed ones.
+pub fn event_times(vevent: &Component) -> Result<(IcsTime, IcsTime), String> {
+    let start = IcsTime::parse(vevent.property("DTSTART").ok_or("VEVENT without DTSTART")?)?;
+    let end = match (vevent.property("DTEND"), vevent.property("DURATION")) {
+        (Some(end), _) => IcsTime::parse(end)?,
+        (None, Some(duration)) => shift(&start, parse_duration(&duration.value)?),
+        (None, None) if start.is_date() => shift(&start, Duration::days(1)),
+        (None, None) => start.clone(),
+    };
+    Ok((start, end))
+}
+
+/// Moves a time by `offset`, keeping its kind and zone
+pub fn shift(time: &IcsTime, offset: Duration) -> IcsTime {
+    match time {
+        IcsTime::Date(date) => IcsTime::Date(*date + offset),
+        IcsTime::Utc(dt) => IcsTime::Utc(*dt + offset),
+        IcsTime::Local(naive, tzid) => IcsTime::Local(*naive + offset, tzid.clone()),
+    }
+}
+
+fn time_resource(time: &IcsTime, timezones: &Timezones, locale: &LocaleSettings) -> serde_json::Value {
+    match time {
+        IcsTime::Date(date) => serde_json::json!({ "date": date.format("%Y-%m-%d").to_string() }),
+        other => serde_json::json!({
+            "dateTime": timezones.resolve(other, locale).map(|dt| dt.to_rfc3339()),
+        }),
+    }
+}
+
+/// Comparable key for an instance start: the date for all-day values, otherwise the UTC timestamp
+fn instance_key(time: &IcsTime, timezones: &Timezones, locale: &LocaleSettings) -> Option<String> {
+    match time {
+        IcsTime::Date(date) => Some(date.format("%Y%m%d").to_string()),
+        other => timezones.resolve(other, locale).map(|dt| dt.timestamp().to_string()),
+    }
+}
+
+/// Values of every EXDATE or RDATE property, which may each hold a comma-separated list
+fn date_list(vevent: &Component, name: &str) -> Vec<IcsTime> {
+    vevent
+        .properties(name)
+        .flat_map(|p| {
+            let tzid = p.param("TZID").map(|t| t.to_string());
+            let value_type = p.param("VALUE").map(|v| v.to_string());
+            p.value
+                .split(',')
+                // PERIOD values ("start/end" or "start/duration") only need their start here
+                .map(|v| v.split('/').next().unwrap_or(v).trim().to_string())
+                .filter_map(|v| IcsTime::parse_value(&v, value_type.as_deref(), tzid.clone()).ok())
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Concrete VEVENTs overlapping the window: single events as they are, recurring ones expanded
+/// from RRULE and RDATE minus EXDATE, with modified instances (RECURRENCE-ID) taking the place
+/// of the occurrence they replace
+pub fn expand_events(
+    vcalendar: &Component,
+    timezones: &Timezones,
+    window: (DateTime<FixedOffset>, DateTime<FixedOffset>),
+    locale: &LocaleSettings,
+) -> Vec<Component> {
+    let vevents: Vec<&Component> = vcalendar.children("VEVENT").collect();
+
+    let mut overrides: HashMap<(String, String), &Component> = HashMap::new();
+    for vevent in vevents.iter().filter(|v| v.property("RECURRENCE-ID").is_some()) {
+        let recurrence_id = vevent.property("RECURRENCE-ID").and_then(|p| IcsTime::parse(p).ok());
+        if let Some(key) = recurrence_id.and_then(|t| instance_key(&t, timezones, locale)) {
+            overrides.insert((vevent.text("UID").unwrap_or_default(), key), vevent);
+        }
+    }
+
+    let overlaps = |vevent: &Component| -> bool {
+        let Ok((start, end)) = event_times(vevent) else {
+            return false;
+        };
+        match (timezones.resolve(&start, locale), timezones.resolve(&end, locale)) {
+            (Some(start), Some(end)) => start <= window.1 && (end > window.0 || (end == start && start >= window.0)),
+            _ => false,
+        }
+    };
+
+    let mut result = Vec::new();
+    for vevent in &vevents {
+        let is_master = vevent.property("RECURRENCE-ID").is_none()
+            && (vevent.property("RRULE").is_some() || vevent.property("RDATE").is_some());
+
+        if vevent.property("RECURRENCE-ID").is_some() {
+            // Modified instances whose master is missing still show up on their own
+            let uid = vevent.text("UID").unwrap_or_default();
+            let has_master = vevents
+                .iter()
+                .any(|v| v.property("RECURRENCE-ID").is_none() && v.text("UID").unwrap_or_default() == uid);
+            if !has_master && overlaps(vevent) {
+                result.push((*vevent).clone());
+            }
+            continue;
+        }
+        if !is_master {
+            if overlaps(vevent) {
+                result.push((*vevent).clone());
+            }
+            continue;
+        }
+
+        let Ok((start, end)) = event_times(vevent) else {
+            continue;
+        };
+        let length = end.naive() - start.naive();
+        let uid = vevent.text("UID").unwrap_or_default();
+
+        // Generate in the event's own wall-clock time so DST changes keep the local hour, from
+        // the first start that could still overlap the window
+        let from = window.0.naive_utc() - length.max(Duration::zero()) - Duration::days(2);
+        let limit = window.1.naive_utc() + Duration::days(2);
+        let mut starts: Vec<NaiveDateTime> = match vevent.property("RRULE").map(|p| RRule::parse(&p.value)) {
+            Some(Ok(mut rule)) => {
+                if rule.until_utc {
+                    rule.until = rule.until.map(|until| timezones.to_wall_clock(until, &start, locale));
+                }
+                rule.occurrences(start.naive(), from, limit)
+            }
+            Some(Err(e)) => {
+                eprintln!("Ignoring RRULE of {}: {}", uid, e);
+                vec![start.naive()]
+            }
+            None => vec![start.naive()],
+        };
+        starts.extend(date_list(vevent, "RDATE").iter().map(|t| {
+            match t {
+                IcsTime::Utc(dt) => timezones.to_wall_clock(dt.naive_utc(), &start, locale),
+                other => other.naive(),
+            }
+        }));
+        starts.sort();
+        starts.dedup();
+
+        let excluded: Vec<String> = date_list(vevent, "EXDATE")
+            .iter()
+            .filter_map(|t| instance_key(t, timezones, locale))
+            .collect();
+
+        for naive in starts {
+            let instance_start = start.with_naive(naive);
+            let Some(key) = instance_key(&instance_start, timezones, locale) else {
+                continue;
+            };
+            if excluded.contains(&key) {
+                continue;
+            }
+            if let Some(modified) = overrides.get(&(uid.clone(), key)) {
+                if overlaps(modified) {
+                    result.push((*modified).clone());
+                }
+                continue;
+            }
+
+            let mut instance = (*vevent).clone();
+            instance
+                .properties
+                .retain(|p| !matches!(p.name.as_str(), "DTSTART" | "DTEND" | "DURATION" | "RDATE" | "EXDATE"));
+            instance.properties.push(instance_start.to_property("DTSTART"));
+            instance.properties.push(instance_start.with_naive(naive + length).to_property("DTEND"));
+            instance.properties.push(instance_start.to_property("RECURRENCE-ID"));
+            if overlaps(&instance) {
+                result.push(instance);
+            }
+        }
+    }
+    result
+}
+
+fn mail_address(value: &str) -> String {
+    let value = value.trim();
+    value
+        .strip_prefix("mailto:")
+        .or_else(|| value.strip_prefix("MAILTO:"))
+        .unwrap_or(value)
+        .to_string()
+}
+
+fn response_status(partstat: Option<&str>) -> &'static str {
+    match partstat.map(|p| p.to_ascii_uppercase()).as_deref() {
+        Some("ACCEPTED") => "accepted",
+        Some("DECLINED") => "declined",
+        Some("TENTATIVE") => "tentative",
+        _ => "needsAction",
+    }
+}
+
+/// Maps a VEVENT onto the event resource shape `calendar::events_from_resource` expects.
+/// `self_email` marks the matching attendee as the user, for response status and dimming.
+pub fn to_event_resource(
+    vevent: &Component,
+    timezones: &Timezones,
+    self_email: &str,
+    locale: &LocaleSettings,
+) -> Result<serde_json::Value, String> {
+    let uid = vevent.text("UID").unwrap_or_default();
+    let (start, end) = event_times(vevent)?;
+
+    // Instances of a series share the UID and are told apart by RECURRENCE-ID
+    let (id, recurring_event_id) = match vevent.property("RECURRENCE-ID") {
+        Some(recurrence_id) => (format!("{}_{}", uid, recurrence_id.value), Some(uid.clone())),
+        None => (uid.clone(), None),
+    };
+
+    let status = vevent
+        .text("STATUS")
+        .map(|s| s.to_ascii_lowercase())
+        .unwrap_or_else(|| "confirmed".to_string());
+    let transparency = match vevent.text("TRANSP").as_deref() {
+        Some("TRANSPARENT") => "transparent",
+        _ => "opaque",
+    };
+
+    let organizer_email = vevent
+        .property("ORGANIZER")
+        .map(|p| mail_address(&p.value))
+        .unwrap_or_default();
+    let organizer = vevent.property("ORGANIZER").map(|p| {
+        serde_json::json!({
+            "email": organizer_email,
+            "displayName": p.param("CN").map(|cn| cn.trim_matches('"').to_string()),
+        })
+    });
+
+    let attendees: Vec<serde_json::Value> = vevent
+        .properties("ATTENDEE")
+        .map(|p| {
+            let email = mail_address(&p.value);
+            serde_json::json!({
+                "email": email,
+                "displayName": p.param("CN").map(|cn| cn.trim_matches('"').to_string()),
+                "responseStatus": response_status(p.param("PARTSTAT")),
+                "self": !self_email.is_empty() && email.eq_ignore_ascii_case(self_email),
+                "organizer": !organizer_email.is_empty() && email.eq_ignore_ascii_case(&organizer_email),
+                "optional": p.param("ROLE").is_some_and(|r| r.eq_ignore_ascii_case("OPT-PARTICIPANT")),
+            })
+        })
+        .collect();
+
+    let recurrence: Vec<String> = vevent
+        .properties
+        .iter()
+        .filter(|p| matches!(p.name.as_str(), "RRULE" | "EXDATE" | "RDATE"))
+        .map(|p| p.to_line())
+        .collect();
+
+    Ok(serde_json::json!({
+        "id": id,
+        "status": status,
+        "transparency": transparency,
+        "summary": vevent.text("SUMMARY"),
+        "location": vevent.text("LOCATION"),
+        "description": vevent.text("DESCRIPTION"),
+        "start": time_resource(&start, timezones, locale),
+        "end": time_resource(&end, timezones, locale),
+        "organizer": organizer,
+        "attendees": attendees,
+        "recurringEventId": recurring_event_id,
+        "recurrence": if recurrence.is_empty() { None } else { Some(recurrence) },
+    }))
+}
+
+/// Events in the window from an iCalendar document, shaped like the Google ones.
+/// Unreadable VEVENTs are skipped with a log line rather than failing the whole calendar.
+pub fn events_from_ics(
+    data: &str,
+    calendar: &Calendar,
+    window: (DateTime<FixedOffset>, DateTime<FixedOffset>),
+    self_email: &str,
+    settings: &Settings,
+) -> Result<Vec<Event>, String> {
+    let mut events = Vec::new();
+    for vcalendar in parse(data)? {
+        let timezones = Timezones::from_calendar(&vcalendar);
+        for vevent in expand_events(&vcalendar, &timezones, window, &settings.locale) {
+            let result = to_event_resource(&vevent, &timezones, self_email, &settings.locale)
+                .and_then(|resource| calendar::events_from_resource(resource, calendar, settings));
+            match result {
+                Ok(expanded) => events.extend(expanded),
+                Err(e) => eprintln!("Skipping event in {}: {}", calendar.name, e),
+            }
+        }
+    }
+    Ok(events)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OUTLOOK_ZONE: &str = "BEGIN:VTIMEZONE
+TZID:W. Europe Standard Time
+BEGIN:STANDARD
+DTSTART:16010101T030000
+TZOFFSETFROM:+0200
+TZOFFSETTO:+0100
+RRULE:FREQ=YEARLY;BYDAY=-1SU;BYMONTH=10
+END:STANDARD
+BEGIN:DAYLIGHT
+DTSTART:16010101T020000
+TZOFFSETFROM:+0100
+TZOFFSETTO:+0200
+RRULE:FREQ=YEARLY;BYDAY=-1SU;BYMONTH=3
+END:DAYLIGHT
+END:VTIMEZONE
+";
+
+    fn locale() -> LocaleSettings {
+        LocaleSettings {
+            timezone: "Europe/Oslo".to_string(),
+            ..LocaleSettings::default()
+        }
+    }
+
+    /// Start (in UTC) and summary of each instance in the window, in order
+    fn expand(body: &str, from: &str, to: &str) -> Vec<(String, String)> {
+        let text = format!("BEGIN:VCALENDAR\nVERSION:2.0\n{}END:VCALENDAR\n", body);
+        let vcalendar = parse(&text).unwrap().remove(0);
+        let timezones = Timezones::from_calendar(&vcalendar);
+        let window = |s: &str| DateTime::parse_from_rfc3339(s).unwrap();
+        let window = (window(from), window(to));
+        let mut instances: Vec<(String, String)> = expand_events(&vcalendar, &timezones, window, &locale())
+            .iter()
+            .map(|vevent| {
+                let (start, _) = event_times(vevent).unwrap();
+                let start = timezones.resolve(&start, &locale()).unwrap().with_timezone(&Utc);
+                (start.format("%Y-%m-%d %H:%M").to_string(), vevent.text("SUMMARY").unwrap_or_default())
+            })
+            .collect();
+        instances.sort();
+        instances
+    }
+
+    fn starts(instances: &[(String, String)]) -> Vec<&str> {
+        instances.iter().map(|(start, _)| start.as_str()).collect()
+    }
+
+    #[test]
+    fn folds_without_splitting_characters() {
+        for (line, first) in [
+            // 8 + 33 * 2 octets, and the next "ø" would make it 76
+            (format!("SUMMARY:{}", "ø".repeat(40)), 74),
+            (format!("SUMMARY:{}", "a".repeat(80)), 75),
+            (format!("SUMMARY:{}{}", "a".repeat(65), "🎉".repeat(3)), 73),
+        ] {
+            let folded = fold(&line);
+            let lines: Vec<&str> = folded.strip_suffix("\r\n").unwrap().split("\r\n").collect();
+            assert!(lines.len() > 1, "{}", line);
+            assert_eq!(lines[0].len(), first, "{}", line);
+            assert!(lines.iter().all(|l| l.len() <= 75));
+            assert!(lines[1..].iter().all(|l| l.starts_with(' ')));
+            assert_eq!(unfold(&folded).concat(), line);
+        }
+        assert_eq!(fold("SUMMARY:Short"), "SUMMARY:Short\r\n");
+    }
+
+    #[test]
+    fn exdate_rdate_and_modified_instances() {
+        let instances = expand(
+            "BEGIN:VEVENT
+UID:standup
+DTSTART;TZID=Europe/Oslo:20261012T090000
+DTEND;TZID=Europe/Oslo:20261012T091500
+RRULE:FREQ=DAILY;BYDAY=MO,TU,WE,TH,FR
+EXDATE;TZID=Europe/Oslo:20261014T090000
+RDATE;TZID=Europe/Oslo:20261017T100000
+SUMMARY:Standup
+END:VEVENT
+BEGIN:VEVENT
+UID:standup
+RECURRENCE-ID;TZID=Europe/Oslo:20261016T090000
+DTSTART;TZID=Europe/Oslo:20261016T130000
+DTEND;TZID=Europe/Oslo:20261016T131500
+SUMMARY:Standup (moved)
+END:VEVENT
+",
+            "2026-10-12T00:00:00+02:00",
+            "2026-10-19T00:00:00+02:00",
+        );
+        assert_eq!(
+            instances,
+            [
+                ("2026-10-12 07:00", "Standup"),
+                ("2026-10-13 07:00", "Standup"),
+                ("2026-10-15 07:00", "Standup"),
+                ("2026-10-16 11:00", "Standup (moved)"),
+                ("2026-10-17 08:00", "Standup"),
+            ]
+            .map(|(start, summary)| (start.to_string(), summary.to_string()))
+        );
+    }
+
+    #[test]
+    fn keeps_the_local_hour_across_dst() {
+        let weekly = "BEGIN:VEVENT
+UID:review
+DTSTART;TZID=Europe/Oslo:20261019T090000
+DTEND;TZID=Europe/Oslo:20261019T100000
+RRULE:FREQ=WEEKLY
+SUMMARY:Review
+END:VEVENT
+";
+        let instances = expand(weekly, "2026-10-19T00:00:00+02:00", "2026-11-02T00:00:00+01:00");
+        assert_eq!(starts(&instances), ["2026-10-19 07:00", "2026-10-26 08:00"]);
+
+        // The same through the calendar's own VTIMEZONE, as Outlook sends it
+        let outlook = format!("{}{}", OUTLOOK_ZONE, weekly.replace("Europe/Oslo", "W. Europe Standard Time"));
+        let instances = expand(&outlook, "2026-10-19T00:00:00+02:00", "2026-11-02T00:00:00+01:00");
+        assert_eq!(starts(&instances), ["2026-10-19 07:00", "2026-10-26 08:00"]);
+    }
+
+    #[test]
+    fn old_series_start_in_the_window() {
+        let body = format!(
+            "{}BEGIN:VEVENT
+UID:medication
+DTSTART;TZID=W. Europe Standard Time:19900101T080000
+DURATION:PT5M
+RRULE:FREQ=DAILY
+EXDATE;TZID=W. Europe Standard Time:20261015T080000
+SUMMARY:Medication
+END:VEVENT
+",
+            OUTLOOK_ZONE
+        );
+        let instances = expand(&body, "2026-10-22T00:00:00+02:00", "2026-10-27T00:00:00+01:00");
+        assert_eq!(
+            starts(&instances),
+            ["2026-10-22 06:00", "2026-10-23 06:00", "2026-10-24 06:00", "2026-10-25 07:00", "2026-10-26 07:00"]
+        );
+        let instances = expand(&body, "2026-10-14T00:00:00+02:00", "2026-10-17T00:00:00+02:00");
+        assert_eq!(starts(&instances), ["2026-10-14 06:00", "2026-10-16 06:00"]);
+    }
+
+    #[test]
+    fn utc_until_is_read_in_the_event_zone() {
+        // 21:00 UTC is 23:00 in Oslo, so the 14th is the last instance
+        let instances = expand(
+            "BEGIN:VEVENT
+UID:late
+DTSTART;TZID=Europe/Oslo:20261012T230000
+DTEND;TZID=Europe/Oslo:20261012T233000
+RRULE:FREQ=DAILY;UNTIL=20261014T210000Z
+SUMMARY:Late call
+END:VEVENT
+",
+            "2026-10-12T00:00:00+02:00",
+            "2026-10-19T00:00:00+02:00",
+        );
+        assert_eq!(starts(&instances), ["2026-10-12 21:00", "2026-10-13 21:00", "2026-10-14 21:00"]);
+    }
+
+    #[test]
+    fn all_day_series() {
+        let text = "BEGIN:VCALENDAR
+BEGIN:VEVENT
+UID:birthday
+DTSTART;VALUE=DATE:19900315
+RRULE:FREQ=YEARLY
+EXDATE;VALUE=DATE:20250315
+SUMMARY:Birthday
+END:VEVENT
+END:VCALENDAR
+";
+        let vcalendar = parse(text).unwrap().remove(0);
+        let timezones = Timezones::from_calendar(&vcalendar);
+        let window = |from: &str, to: &str| {
+            (DateTime::parse_from_rfc3339(from).unwrap(), DateTime::parse_from_rfc3339(to).unwrap())
+        };
+
+        let march = window("2026-03-01T00:00:00+01:00", "2026-04-01T00:00:00+02:00");
+        let instances = expand_events(&vcalendar, &timezones, march, &locale());
+        let values: Vec<&str> = instances.iter().map(|i| i.property("DTSTART").unwrap().value.as_str()).collect();
+        assert_eq!(values, ["20260315"]);
+        assert_eq!(instances[0].property("DTEND").unwrap().value, "20260316");
+
+        let march = window("2025-03-01T00:00:00+01:00", "2025-04-01T00:00:00+02:00");
+        assert!(expand_events(&vcalendar, &timezones, march, &locale()).is_empty());
+    }
+}
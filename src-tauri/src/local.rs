@@ -0,0 +1,290 @@
+//! Calendars from local .ics files or vdir directories (as synced by vdirsyncer), with a file
+//! watcher so edits on disk show up without waiting for the next refresh.
+
+use async_trait::async_trait;
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::Duration;
+
+use crate::calendar::{self, Calendar, Event};
+use crate::feeds::feed_calendar;
+use crate::ics;
+use crate::provider::CalendarProvider;
+use crate::settings::{LocalCalendar, Settings};
+
+// Editors and sync tools touch several files at once; wait for them to settle
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// One calendar on disk: a single .ics file or a directory of them
+struct Collection {
+    path: PathBuf,
+    files: Vec<PathBuf>,
+}
+
+/// The configured path, with a leading `~` taken as the home directory
+fn source_path(path: &str) -> PathBuf {
+    match path.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with(['/', '\\']) => match dirs::home_dir() {
+            Some(home) => home.join(rest.trim_start_matches(['/', '\\'])),
+            None => PathBuf::from(path),
+        },
+        _ => PathBuf::from(path),
+    }
+}
+
+fn is_ics(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("ics"))
+}
+
+fn ics_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|e| e.path())
+                .filter(|p| p.is_file() && is_ics(p))
+                .collect()
+        })
+        .unwrap_or_default();
+    files.sort();
+    files
+}
+
+/// A file is one calendar. A directory holding .ics files is one vdir collection; a directory
+/// of such directories (vdirsyncer's storage root) gives one calendar per subdirectory.
+fn collections(path: &Path) -> Result<Vec<Collection>, String> {
+    if path.is_file() {
+        return Ok(vec![Collection {
+            path: path.to_path_buf(),
+            files: vec![path.to_path_buf()],
+        }]);
+    }
+    if !path.is_dir() {
+        return Err(format!("Calendar path {} does not exist", path.display()));
+    }
+
+    let files = ics_files(path);
+    if !files.is_empty() {
+        return Ok(vec![Collection {
+            path: path.to_path_buf(),
+            files,
+        }]);
+    }
+
+    let mut subdirs: Vec<PathBuf> = fs::read_dir(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.is_dir())
+        .collect();
+    subdirs.sort();
+    Ok(subdirs
+        .into_iter()
+        .map(|dir| Collection {
+            files: ics_files(&dir),
+            path: dir,
+        })
+        .filter(|c| !c.files.is_empty())
+        .collect())
+}
+
+/// vdir metadata file such as `displayname` or `color`, if present
+fn vdir_meta(dir: &Path, name: &str) -> String {
+    fs::read_to_string(dir.join(name))
+        .map(|s| s.trim().to_string())
+        .unwrap_or_default()
+}
+
+pub struct LocalCalendarProvider {
+    source: LocalCalendar,
+}
+
+impl LocalCalendarProvider {
+    pub fn new(source: LocalCalendar) -> Self {
+        LocalCalendarProvider { source }
+    }
+
+    fn to_calendar(&self, collection: &Collection, first_file: &str, single: bool) -> Calendar {
+        let id = collection.path.to_string_lossy().to_string();
+        let name = if single && !self.source.name.is_empty() {
+            self.source.name.clone()
+        } else {
+            vdir_meta(&collection.path, "displayname")
+        };
+        let color = if self.source.color.is_empty() {
+            vdir_meta(&collection.path, "color")
+        } else {
+            self.source.color.clone()
+        };
+
+        // X-WR-CALNAME and the default color fill in whatever is still empty
+        let mut calendar = feed_calendar(&id, &name, &color, first_file);
+        if calendar.name == id {
+            if let Some(stem) = collection.path.file_stem() {
+                calendar.name = stem.to_string_lossy().to_string();
+            }
+        }
+        calendar
+    }
+}
+
+#[async_trait]
+impl CalendarProvider for LocalCalendarProvider {
+    fn id(&self) -> String {
+        format!("local:{}", self.source.path)
+    }
+
+    async fn get_events(&self, days: i32, settings: &Settings) -> Result<Vec<Event>, String> {
+        let collections = collections(&source_path(&self.source.path))?;
+        let single = collections.len() == 1;
+        let window = calendar::event_window(days, &settings.locale);
+
+        let mut all_events = Vec::new();
+        for collection in &collections {
+            let contents: Vec<(PathBuf, String)> = collection
+                .files
+                .iter()
+                .filter_map(|file| match fs::read_to_string(file) {
+                    Ok(content) => Some((file.clone(), content)),
+                    Err(e) => {
+                        eprintln!("Failed to read {}: {}", file.display(), e);
+                        None
+                    }
+                })
+                .collect();
+
+            let first = contents.first().map(|(_, c)| c.as_str()).unwrap_or("");
+            let calendar = self.to_calendar(collection, first, single);
+
+            for (file, content) in &contents {
+                match ics::events_from_ics(content, &calendar, window, "", settings) {
+                    Ok(events) => all_events.extend(events),
+                    Err(e) => eprintln!("Skipping {}: {}", file.display(), e),
+                }
+            }
+        }
+
+        calendar::sort_events(&mut all_events);
+        Ok(all_events)
+    }
+}
+
+/// Watches the configured files and directories and calls `on_change` once things settle after
+/// a change. Paths that don't exist yet can't be watched and are skipped. The watcher stops
+/// when the returned value is dropped.
+pub fn watch(
+    sources: &[LocalCalendar],
+    on_change: impl Fn() + Send + 'static,
+) -> Result<RecommendedWatcher, String> {
+    let (sender, receiver) = mpsc::channel::<()>();
+    let watched: Vec<PathBuf> = sources.iter().map(|s| source_path(&s.path)).collect();
+
+    let relevant = watched.clone();
+    let mut watcher = notify::recommended_watcher(move |result: notify::Result<notify::Event>| {
+        let Ok(event) = result else {
+            return;
+        };
+        if event.kind.is_access() {
+            return;
+        }
+        if event.paths.iter().any(|p| relevant.iter().any(|w| p.starts_with(w))) {
+            let _ = sender.send(());
+        }
+    })
+    .map_err(|e| format!("Failed to start file watcher: {}", e))?;
+
+    for path in &watched {
+        // Editors often replace files by renaming, which ends a watch on the file itself,
+        // so single files are watched through their directory
+        let (target, mode) = if path.is_file() {
+            (path.parent().unwrap_or(path).to_path_buf(), RecursiveMode::NonRecursive)
+        } else {
+            (path.clone(), RecursiveMode::Recursive)
+        };
+        if let Err(e) = watcher.watch(&target, mode) {
+            eprintln!("Failed to watch {}: {}", target.display(), e);
+        }
+    }
+
+    std::thread::spawn(move || {
+        while receiver.recv().is_ok() {
+            while receiver.recv_timeout(DEBOUNCE).is_ok() {}
+            on_change();
+        }
+    });
+
+    Ok(watcher)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An empty directory for one test
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("calendar-widget-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write(path: &Path, content: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    fn calendar(name: &str) -> String {
+        format!("BEGIN:VCALENDAR\r\nVERSION:2.0\r\nX-WR-CALNAME:{}\r\nEND:VCALENDAR\r\n", name)
+    }
+
+    #[test]
+    fn expands_the_home_directory() {
+        let home = dirs::home_dir().unwrap();
+        assert_eq!(source_path("~"), home);
+        assert_eq!(source_path("~/calendars/work"), home.join("calendars/work"));
+        // Someone else's home and paths without a tilde are left alone
+        assert_eq!(source_path("~kari/calendars"), PathBuf::from("~kari/calendars"));
+        assert_eq!(source_path("/srv/calendars/~"), PathBuf::from("/srv/calendars/~"));
+        assert_eq!(source_path("calendars"), PathBuf::from("calendars"));
+    }
+
+    #[test]
+    fn finds_files_collections_and_storage_roots() {
+        let dir = temp_dir("collections");
+        write(&dir.join("holidays.ics"), &calendar("Holidays"));
+        write(&dir.join("work/b.ics"), &calendar("Work"));
+        write(&dir.join("work/a.ICS"), &calendar("Work"));
+        write(&dir.join("work/displayname"), "Work\n");
+        write(&dir.join("work/color"), "#ef4444\n");
+        write(&dir.join("storage/home/event.ics"), &calendar("Home"));
+        write(&dir.join("storage/empty/notes.txt"), "");
+        write(&dir.join("storage/team/event.ics"), &calendar("Team"));
+
+        let found = |path: &Path| -> Vec<(PathBuf, usize)> {
+            collections(path).unwrap().into_iter().map(|c| (c.path, c.files.len())).collect()
+        };
+        // A file is its own calendar
+        assert_eq!(found(&dir.join("holidays.ics")), [(dir.join("holidays.ics"), 1)]);
+        // A directory with .ics files is one vdir, however it spells the extension
+        let work = collections(&dir.join("work")).unwrap();
+        assert_eq!(work.len(), 1);
+        assert_eq!(work[0].files, [dir.join("work/a.ICS"), dir.join("work/b.ics")]);
+        // A directory of vdirs gives one calendar each, skipping those without events
+        assert_eq!(found(&dir.join("storage")), [(dir.join("storage/home"), 1), (dir.join("storage/team"), 1)]);
+
+        let missing = collections(&dir.join("missing")).err().unwrap();
+        assert!(missing.contains("does not exist"), "{}", missing);
+
+        // vdir metadata comes before the calendar's own name
+        let provider = LocalCalendarProvider::new(LocalCalendar {
+            path: dir.join("work").to_string_lossy().to_string(),
+            ..LocalCalendar::default()
+        });
+        let calendar = provider.to_calendar(&work[0], &self::calendar("Ignored"), true);
+        assert_eq!((calendar.name.as_str(), calendar.color.as_str()), ("Work", "#ef4444"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
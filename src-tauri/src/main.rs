@@ -1,23 +1,67 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 mod auth;
+mod availability;
+mod caldav;
+mod caldav_tasks;
 mod calendar;
+mod cli;
+mod conflicts;
+mod export;
+mod feeds;
+mod ics;
+mod local;
+mod microsoft;
+mod provider;
+mod quickadd;
+mod recurrence;
+mod settings;
 mod tasks;
+mod todoist;
 
 use serde::{Deserialize, Serialize};
 use std::fs;
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::Mutex;
-use tauri::{AppHandle, Manager};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct CachedData {
     events: Vec<calendar::Event>,
     tasks: Vec<tasks::Task>,
+    #[serde(default)]
+    conflicts: Vec<conflicts::Conflict>,
+    /// Task lists that were cut off at the configured task limit
+    #[serde(default)]
+    truncated_lists: Vec<String>,
+    /// Recently completed tasks, see `tasks.show_completed`
+    #[serde(default)]
+    completed_tasks: Vec<tasks::Task>,
 }
 
 struct AppState {
     cache: Mutex<Option<CachedData>>,
+    /// Task completions waiting out the undo window, keyed by `pending_key`
+    pending_completions: Mutex<HashMap<String, PendingCompletion>>,
+    /// Opened on first copy and kept, since on X11 and Wayland copied text is only
+    /// available while its owner is alive
+    clipboard: Mutex<Option<arboard::Clipboard>>,
+}
+
+/// A completion waiting out the undo window
+struct PendingCompletion {
+    task_id: String,
+    tasklist_id: String,
+    handle: tauri::async_runtime::JoinHandle<()>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct TaskCompletionFailed {
+    task_id: String,
+    tasklist_id: String,
+    error: String,
 }
 
 fn get_base_dir() -> PathBuf {
@@ -33,7 +77,17 @@ fn load_cache() -> Option<CachedData> {
     let path = get_cache_path();
     if path.exists() {
         if let Ok(content) = fs::read_to_string(&path) {
-            return serde_json::from_str(&content).ok();
+            let mut data: CachedData = serde_json::from_str(&content).ok()?;
+            // Caches written before providers existed only hold Google data
+            for event in data.events.iter_mut().filter(|e| e.provider.is_empty()) {
+                event.provider = provider::GOOGLE.to_string();
+            }
+            for task in data.tasks.iter_mut().chain(data.completed_tasks.iter_mut()) {
+                if task.provider.is_empty() {
+                    task.provider = provider::GOOGLE.to_string();
+                }
+            }
+            return Some(data);
         }
     }
     None
@@ -46,31 +100,184 @@ fn save_cache(data: &CachedData) {
     }
 }
 
+/// Applies an optimistic change to the cache
+fn update_cache(state: &AppState, f: impl FnOnce(&mut CachedData)) {
+    let mut cache = state.cache.lock().unwrap();
+    if let Some(data) = cache.as_mut() {
+        f(data);
+        data.conflicts = conflicts::detect(&mut data.events);
+        save_cache(data);
+    }
+}
+
+/// The cached copies of one event (one per day for multi-day events), kept for rollback
+fn cached_event(state: &AppState, calendar_id: &str, event_id: &str) -> Vec<calendar::Event> {
+    state
+        .cache
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|data| data.events.iter().filter(|e| is_event(e, calendar_id, event_id)).cloned().collect())
+        .unwrap_or_default()
+}
+
+/// Undoes a failed write by putting back one event, keeping changes that landed meanwhile
+fn restore_event(state: &AppState, calendar_id: &str, event_id: &str, previous: Vec<calendar::Event>) {
+    update_cache(state, |data| {
+        data.events.retain(|e| !is_event(e, calendar_id, event_id));
+        data.events.extend(previous);
+        calendar::sort_events(&mut data.events);
+    });
+}
+
+/// The cached task, kept for rollback
+fn cached_task(state: &AppState, tasklist_id: &str, task_id: &str) -> Option<tasks::Task> {
+    state
+        .cache
+        .lock()
+        .unwrap()
+        .as_ref()
+        .and_then(|data| tasks::find_task(&data.tasks, tasklist_id, task_id).cloned())
+}
+
+/// Undoes a failed write by putting back one task, keeping changes that landed meanwhile
+fn restore_task(state: &AppState, tasklist_id: &str, task_id: &str, previous: Option<tasks::Task>) {
+    update_cache(state, |data| match (tasks::find_task_mut(&mut data.tasks, tasklist_id, task_id), previous) {
+        (Some(task), Some(previous)) => *task = previous,
+        (Some(_), None) => tasks::retain_task(&mut data.tasks, tasklist_id, task_id),
+        (None, Some(previous)) => tasks::insert_under_parent(&mut data.tasks, previous),
+        (None, None) => {}
+    });
+}
+
+fn require_event_editing(settings: &settings::Settings) -> Result<(), String> {
+    if settings.events.allow_editing {
+        Ok(())
+    } else {
+        Err("Event editing is disabled in settings.json (events.allow_editing)".to_string())
+    }
+}
+
+/// Refuses calendars you can only read, such as subscribed or shared ones, before anything is
+/// shown as pending
+fn require_writable(calendar: &calendar::Calendar) -> Result<(), String> {
+    if calendar.writable {
+        Ok(())
+    } else {
+        Err(format!("The calendar \"{}\" is read-only; pick another one", calendar.name))
+    }
+}
+
+fn is_event(event: &calendar::Event, calendar_id: &str, event_id: &str) -> bool {
+    event.id == event_id && event.calendar_id == calendar_id
+}
+
+/// Event writes go to Google Calendar; events from the other providers are read-only
+fn require_google_event(state: &AppState, calendar_id: &str, event_id: &str) -> Result<(), String> {
+    let provider_id = state
+        .cache
+        .lock()
+        .unwrap()
+        .as_ref()
+        .and_then(|data| data.events.iter().find(|e| is_event(e, calendar_id, event_id)))
+        .map(|e| e.provider.clone())
+        .ok_or_else(|| format!("Event {} isn't cached; refresh and try again", event_id))?;
+    if provider_id == provider::GOOGLE {
+        Ok(())
+    } else {
+        Err(format!("Events from {} are read-only", provider_id))
+    }
+}
+
 #[tauri::command]
 async fn get_data(state: tauri::State<'_, AppState>) -> Result<CachedData, String> {
-    // Try to fetch fresh data
-    let events_result = calendar::get_events(60).await;
-    let tasks_result = tasks::get_tasks().await;
-
-    match (events_result, tasks_result) {
-        (Ok(events), Ok(tasks)) => {
-            let data = CachedData { events, tasks };
-
-            // Update cache
-            save_cache(&data);
-            *state.cache.lock().unwrap() = Some(data.clone());
-
-            Ok(data)
-        }
-        (Err(e), _) | (_, Err(e)) => {
-            // Return cached data on error
-            if let Some(cached) = state.cache.lock().unwrap().clone() {
-                Ok(cached)
-            } else {
-                Err(e)
+    refresh_data(&state).await
+}
+
+/// Fetches from every provider, falling back to the cache for those that fail, and saves the result
+async fn refresh_data(state: &AppState) -> Result<CachedData, String> {
+    let settings = settings::load_settings();
+    let cached = state.cache.lock().unwrap().clone();
+
+    let mut data = CachedData {
+        events: vec![],
+        tasks: vec![],
+        conflicts: vec![],
+        truncated_lists: vec![],
+        completed_tasks: vec![],
+    };
+    let mut errors = Vec::new();
+    let mut any_success = false;
+
+    // Try to fetch fresh data from every configured provider
+    for provider in provider::calendar_providers(&settings) {
+        let id = provider.id();
+        match provider.get_events(60, &settings).await {
+            Ok(events) => {
+                any_success = true;
+                data.events.extend(events.into_iter().map(|e| calendar::Event { provider: id.clone(), ..e }));
+            }
+            Err(e) => {
+                // Keep showing what we had from this provider
+                eprintln!("Failed to fetch events from {}: {}", id, e);
+                if let Some(cached) = &cached {
+                    data.events.extend(cached.events.iter().filter(|e| e.provider == id).cloned());
+                }
+                errors.push(e);
             }
         }
     }
+
+    for provider in provider::task_providers(&settings) {
+        let id = provider.id();
+        match provider.get_tasks(&settings).await {
+            Ok(fetch) => {
+                any_success = true;
+                data.tasks.extend(fetch.tasks.into_iter().map(|t| tasks::Task { provider: id.clone(), ..t }));
+                data.completed_tasks.extend(
+                    fetch.completed.into_iter().map(|t| tasks::Task { provider: id.clone(), ..t }),
+                );
+                data.truncated_lists.extend(fetch.truncated_lists);
+            }
+            Err(e) => {
+                eprintln!("Failed to fetch tasks from {}: {}", id, e);
+                if let Some(cached) = &cached {
+                    data.tasks.extend(cached.tasks.iter().filter(|t| t.provider == id).cloned());
+                    data.completed_tasks
+                        .extend(cached.completed_tasks.iter().filter(|t| t.provider == id).cloned());
+                }
+                errors.push(e);
+            }
+        }
+    }
+
+    if !any_success {
+        // Return cached data when nothing could be fetched
+        return match (cached, errors.into_iter().next()) {
+            (Some(cached), _) => Ok(cached),
+            (None, Some(e)) => Err(e),
+            (None, None) => Ok(data),
+        };
+    }
+
+    calendar::sort_events(&mut data.events);
+    data.completed_tasks.sort_by(|a, b| b.completed_at.cmp(&a.completed_at));
+    data.conflicts = conflicts::detect(&mut data.events);
+
+    // Completions inside the undo window haven't reached the server yet
+    let pending = state.pending_completions.lock().unwrap();
+    tasks::for_each_task_mut(&mut data.tasks, &mut |task| {
+        if pending.contains_key(&pending_key(&task.tasklist_id, &task.id)) {
+            task.completed = true;
+        }
+    });
+    drop(pending);
+
+    // Update cache
+    save_cache(&data);
+    *state.cache.lock().unwrap() = Some(data.clone());
+
+    Ok(data)
 }
 
 #[tauri::command]
@@ -81,15 +288,561 @@ async fn get_cached_data(state: tauri::State<'_, AppState>) -> Result<CachedData
         Ok(CachedData {
             events: vec![],
             tasks: vec![],
+            conflicts: vec![],
+            truncated_lists: vec![],
+            completed_tasks: vec![],
         })
     }
 }
 
+/// Writes the cached events from `start` to `end` (inclusive, `YYYY-MM-DD`) as an .ics file.
+/// Without a path the file goes to the Downloads folder. Returns the path written.
+#[tauri::command]
+async fn export_ics(
+    state: tauri::State<'_, AppState>,
+    start: String,
+    end: String,
+    path: Option<String>,
+) -> Result<String, String> {
+    let (from, to) = parse_range(&start, &end)?;
+    let events = state
+        .cache
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|c| c.events.clone())
+        .unwrap_or_default();
+
+    let locale = settings::load_settings().locale;
+    let ics = export::to_ics(&export::events_in_range(&events, from, to, &locale), &locale);
+
+    let path = match path {
+        Some(path) => PathBuf::from(path),
+        None => dirs::download_dir()
+            .or_else(dirs::home_dir)
+            .ok_or_else(|| "No Downloads folder to save to".to_string())?
+            .join(format!("calendar-{}-{}.ics", start, end)),
+    };
+    fs::write(&path, ics).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(path.display().to_string())
+}
+
+/// Renders the cached events and tasks from `start` to `end` as an agenda. Uses the `agenda`
+/// settings unless options are passed. Writes to `path` and returns it, or copies to the clipboard.
+#[tauri::command]
+async fn export_agenda(
+    state: tauri::State<'_, AppState>,
+    start: String,
+    end: String,
+    options: Option<settings::AgendaSettings>,
+    path: Option<String>,
+) -> Result<String, String> {
+    let (from, to) = parse_range(&start, &end)?;
+    let (events, tasks) = state
+        .cache
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|c| (c.events.clone(), c.tasks.clone()))
+        .unwrap_or_default();
+
+    let settings = settings::load_settings();
+    let options = options.unwrap_or(settings.agenda);
+    let agenda = export::render_agenda(&events, &tasks, from, to, &options, &settings.locale)?;
+
+    match path {
+        Some(path) => {
+            fs::write(&path, agenda).map_err(|e| format!("Failed to write {}: {}", path, e))?;
+            Ok(path)
+        }
+        None => {
+            let mut clipboard = state.clipboard.lock().unwrap();
+            if clipboard.is_none() {
+                *clipboard = Some(
+                    arboard::Clipboard::new().map_err(|e| format!("Failed to open the clipboard: {}", e))?,
+                );
+            }
+            clipboard
+                .as_mut()
+                .unwrap()
+                .set_text(agenda)
+                .map_err(|e| format!("Failed to copy to the clipboard: {}", e))?;
+            Ok(String::new())
+        }
+    }
+}
+
+fn parse_range(start: &str, end: &str) -> Result<(chrono::NaiveDate, chrono::NaiveDate), String> {
+    let parse = |value: &str| {
+        chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d").map_err(|e| format!("Invalid date '{}': {}", value, e))
+    };
+    let (from, to) = (parse(start)?, parse(end)?);
+    if to < from {
+        return Err(format!("End date {} is before start date {}", end, start));
+    }
+    Ok((from, to))
+}
+
+#[tauri::command]
+async fn get_calendars() -> Result<Vec<calendar::Calendar>, String> {
+    calendar::get_calendars().await
+}
+
+#[tauri::command]
+async fn create_event(
+    calendar_id: String,
+    draft: calendar::EventDraft,
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<calendar::Event>, String> {
+    let settings = settings::load_settings();
+    require_event_editing(&settings)?;
+    let calendar = calendar::get_calendar(&calendar_id).await?;
+    require_writable(&calendar)?;
+
+    let pending_id = format!("pending-{}", chrono::Utc::now().timestamp_millis());
+    let pending = calendar::draft_events(&draft, &pending_id, &calendar, &settings)?;
+    update_cache(&state, |data| {
+        data.events.extend(pending);
+        calendar::sort_events(&mut data.events);
+    });
+
+    match calendar::insert_event(&calendar, &draft, &settings).await {
+        Ok(created) => {
+            update_cache(&state, |data| {
+                data.events.retain(|e| !is_event(e, &calendar_id, &pending_id));
+                data.events.extend(created.clone());
+                calendar::sort_events(&mut data.events);
+            });
+            Ok(created)
+        }
+        Err(e) => {
+            restore_event(&state, &calendar_id, &pending_id, Vec::new());
+            Err(e)
+        }
+    }
+}
+
+/// Parses quick-add text into a draft for the user to confirm before `create_event`
+#[tauri::command]
+async fn quick_add(text: String) -> Result<calendar::EventDraft, String> {
+    let settings = settings::load_settings();
+    quickadd::parse(&text, settings.locale.now(), &settings.locale)
+}
+
+#[tauri::command]
+async fn update_event(
+    calendar_id: String,
+    event_id: String,
+    draft: calendar::EventDraft,
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<calendar::Event>, String> {
+    let settings = settings::load_settings();
+    require_event_editing(&settings)?;
+    require_google_event(&state, &calendar_id, &event_id)?;
+    let calendar = calendar::get_calendar(&calendar_id).await?;
+
+    let edited = calendar::draft_events(&draft, &event_id, &calendar, &settings)?;
+    let previous = cached_event(&state, &calendar_id, &event_id);
+    update_cache(&state, |data| {
+        data.events.retain(|e| !is_event(e, &calendar_id, &event_id));
+        data.events.extend(edited);
+        calendar::sort_events(&mut data.events);
+    });
+
+    match calendar::patch_event(&calendar, &event_id, &draft, &settings).await {
+        Ok(updated) => {
+            update_cache(&state, |data| {
+                data.events.retain(|e| !is_event(e, &calendar_id, &event_id));
+                data.events.extend(updated.clone());
+                calendar::sort_events(&mut data.events);
+            });
+            Ok(updated)
+        }
+        Err(e) => {
+            restore_event(&state, &calendar_id, &event_id, previous);
+            Err(e)
+        }
+    }
+}
+
+#[tauri::command]
+async fn delete_event(
+    calendar_id: String,
+    event_id: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), String> {
+    let settings = settings::load_settings();
+    require_event_editing(&settings)?;
+    require_google_event(&state, &calendar_id, &event_id)?;
+
+    let previous = cached_event(&state, &calendar_id, &event_id);
+    update_cache(&state, |data| {
+        data.events.retain(|e| !is_event(e, &calendar_id, &event_id));
+    });
+
+    if let Err(e) = calendar::delete_event(&calendar_id, &event_id).await {
+        restore_event(&state, &calendar_id, &event_id, previous);
+        return Err(e);
+    }
+
+    Ok(())
+}
+
 #[tauri::command]
-async fn complete_task(task_id: String, tasklist_id: String) -> Result<bool, String> {
-    tasks::complete_task(&task_id, &tasklist_id).await
+async fn find_free_slots(
+    query: availability::AvailabilityQuery,
+    state: tauri::State<'_, AppState>,
+) -> Result<availability::Availability, String> {
+    let settings = settings::load_settings();
+    let events = state
+        .cache
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|data| data.events.clone())
+        .unwrap_or_default();
+    availability::find_free_slots(&events, &query, &settings.locale).await
 }
 
+#[tauri::command]
+async fn get_settings() -> Result<settings::Settings, String> {
+    Ok(settings::load_settings())
+}
+
+fn pending_key(tasklist_id: &str, task_id: &str) -> String {
+    format!("{}/{}", tasklist_id, task_id)
+}
+
+fn set_task_completed(state: &AppState, tasklist_id: &str, task_id: &str, completed: bool) {
+    update_cache(state, |data| {
+        if let Some(task) = tasks::find_task_mut(&mut data.tasks, tasklist_id, task_id) {
+            task.completed = completed;
+        }
+    });
+}
+
+/// The configured provider a cached task came from
+fn task_provider_for(
+    state: &AppState,
+    settings: &settings::Settings,
+    tasklist_id: &str,
+    task_id: &str,
+) -> Result<Box<dyn provider::TaskProvider>, String> {
+    let provider_id = state
+        .cache
+        .lock()
+        .unwrap()
+        .as_ref()
+        .and_then(|data| {
+            tasks::find_task(&data.tasks, tasklist_id, task_id)
+                .or_else(|| data.completed_tasks.iter().find(|t| tasks::is_task(t, tasklist_id, task_id)))
+        })
+        .map(|t| t.provider.clone())
+        .ok_or_else(|| format!("Task {} isn't cached; refresh and try again", task_id))?;
+    provider::task_provider(settings, &provider_id)
+}
+
+/// Sends the completion through the task's provider and updates the cache with the result
+async fn finish_completion(state: &AppState, task_id: &str, tasklist_id: &str) -> Result<(), String> {
+    let settings = settings::load_settings();
+    let result = match task_provider_for(state, &settings, tasklist_id, task_id) {
+        Ok(provider) => provider
+            .complete_task(task_id, tasklist_id, &settings)
+            .await
+            .map(|task| tasks::Task { provider: provider.id(), ..task }),
+        Err(e) => Err(e),
+    };
+
+    match result {
+        Ok(completed) => {
+            let keep_completed = settings.tasks.show_completed != settings::CompletedWindow::Off;
+            update_cache(state, |data| {
+                tasks::retain_task(&mut data.tasks, tasklist_id, task_id);
+                if keep_completed {
+                    data.completed_tasks.insert(0, completed);
+                }
+            });
+            Ok(())
+        }
+        Err(e) => {
+            set_task_completed(state, tasklist_id, task_id, false);
+            Err(e)
+        }
+    }
+}
+
+/// Marks a task completed right away and sends the change after the undo window.
+/// A failed deferred request is reported through the `task-completion-failed` event.
+#[tauri::command]
+async fn complete_task(
+    task_id: String,
+    tasklist_id: String,
+    app: AppHandle,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), String> {
+    let settings = settings::load_settings();
+    set_task_completed(&state, &tasklist_id, &task_id, true);
+
+    if settings.tasks.undo_seconds == 0 {
+        return finish_completion(&state, &task_id, &tasklist_id).await;
+    }
+
+    let key = pending_key(&tasklist_id, &task_id);
+    let mut pending = state.pending_completions.lock().unwrap();
+    // Completing twice just restarts the window
+    if let Some(previous) = pending.remove(&key) {
+        previous.handle.abort();
+    }
+
+    let delay = Duration::from_secs(settings.tasks.undo_seconds);
+    let handle = {
+        let (task_id, tasklist_id) = (task_id.clone(), tasklist_id.clone());
+        tauri::async_runtime::spawn(async move {
+            tokio::time::sleep(delay).await;
+
+            let state = app.state::<AppState>();
+            // Past this point undo is no longer possible
+            if state
+                .pending_completions
+                .lock()
+                .unwrap()
+                .remove(&pending_key(&tasklist_id, &task_id))
+                .is_none()
+            {
+                return;
+            }
+
+            if let Err(error) = finish_completion(&state, &task_id, &tasklist_id).await {
+                eprintln!("Failed to complete task {}: {}", task_id, error);
+                let _ = app.emit(
+                    "task-completion-failed",
+                    TaskCompletionFailed { task_id, tasklist_id, error },
+                );
+            }
+        })
+    };
+    pending.insert(key, PendingCompletion { task_id, tasklist_id, handle });
+
+    Ok(())
+}
+
+/// Cancels a completion that is still inside the undo window
+#[tauri::command]
+async fn undo_complete_task(
+    task_id: String,
+    tasklist_id: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), String> {
+    let pending = state
+        .pending_completions
+        .lock()
+        .unwrap()
+        .remove(&pending_key(&tasklist_id, &task_id))
+        .ok_or_else(|| "Too late to undo; the task has already been completed".to_string())?;
+    pending.handle.abort();
+
+    set_task_completed(&state, &tasklist_id, &task_id, false);
+    Ok(())
+}
+
+#[tauri::command]
+async fn get_series_master(
+    calendar_id: String,
+    recurring_event_id: String,
+) -> Result<Vec<calendar::Event>, String> {
+    let settings = settings::load_settings();
+    let calendar = calendar::get_calendar(&calendar_id).await?;
+    calendar::get_series_master(&calendar, &recurring_event_id, &settings).await
+}
+
+#[tauri::command]
+async fn respond_to_event(
+    calendar_id: String,
+    event_id: String,
+    response: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<calendar::Event>, String> {
+    let settings = settings::load_settings();
+    require_event_editing(&settings)?;
+    require_google_event(&state, &calendar_id, &event_id)?;
+    let calendar = calendar::get_calendar(&calendar_id).await?;
+
+    let previous = cached_event(&state, &calendar_id, &event_id);
+    update_cache(&state, |data| {
+        for event in data.events.iter_mut().filter(|e| is_event(e, &calendar_id, &event_id)) {
+            event.response_status = response.clone();
+            for attendee in event.attendees.iter_mut().filter(|a| a.is_self) {
+                attendee.response_status = response.clone();
+            }
+        }
+    });
+
+    match calendar::respond_to_event(&calendar, &event_id, &response, &settings).await {
+        Ok(confirmed) => {
+            update_cache(&state, |data| {
+                data.events.retain(|e| !is_event(e, &calendar_id, &event_id));
+                data.events.extend(confirmed.clone());
+                calendar::sort_events(&mut data.events);
+            });
+            Ok(confirmed)
+        }
+        Err(e) => {
+            restore_event(&state, &calendar_id, &event_id, previous);
+            Err(e)
+        }
+    }
+}
+
+#[tauri::command]
+async fn open_url(url: String) -> Result<(), String> {
+    if !(url.starts_with("https://") || url.starts_with("http://")) {
+        return Err(format!("Refusing to open non-web URL: {}", url));
+    }
+    open::that(&url).map_err(|e| format!("Failed to open {}: {}", url, e))
+}
+
+/// Lists new tasks can be added to; only Google Tasks supports adding
+#[tauri::command]
+async fn get_tasklists() -> Result<Vec<tasks::TaskList>, String> {
+    let settings = settings::load_settings();
+    provider::task_provider(&settings, provider::GOOGLE)?.get_tasklists().await
+}
+
+#[tauri::command]
+async fn create_task(
+    tasklist_id: String,
+    draft: tasks::TaskDraft,
+    state: tauri::State<'_, AppState>,
+) -> Result<tasks::Task, String> {
+    let settings = settings::load_settings();
+    let provider = provider::task_provider(&settings, provider::GOOGLE)?;
+    let pending_id = format!("pending-{}", chrono::Utc::now().timestamp_millis());
+    let pending = tasks::draft_task(&draft, &pending_id, &tasklist_id);
+    update_cache(&state, |data| data.tasks.insert(0, pending));
+
+    match provider.create_task(&tasklist_id, &draft).await {
+        Ok(created) => {
+            update_cache(&state, |data| {
+                if let Some(task) = tasks::find_task_mut(&mut data.tasks, &tasklist_id, &pending_id) {
+                    *task = created.clone();
+                }
+            });
+            Ok(created)
+        }
+        Err(e) => {
+            restore_task(&state, &tasklist_id, &pending_id, None);
+            Err(e)
+        }
+    }
+}
+
+#[tauri::command]
+async fn update_task(
+    task_id: String,
+    tasklist_id: String,
+    draft: tasks::TaskDraft,
+    state: tauri::State<'_, AppState>,
+) -> Result<tasks::Task, String> {
+    let settings = settings::load_settings();
+    let provider = task_provider_for(&state, &settings, &tasklist_id, &task_id)?;
+    let previous = cached_task(&state, &tasklist_id, &task_id);
+    update_cache(&state, |data| {
+        if let Some(task) = tasks::find_task_mut(&mut data.tasks, &tasklist_id, &task_id) {
+            task.title = draft.title.clone();
+            task.notes = draft.notes.clone();
+            task.due = draft.due.clone();
+        }
+    });
+
+    match provider.update_task(&task_id, &tasklist_id, &draft).await {
+        Ok(updated) => {
+            update_cache(&state, |data| {
+                if let Some(task) = tasks::find_task_mut(&mut data.tasks, &tasklist_id, &task_id) {
+                    // The response doesn't include subtasks
+                    let subtasks = std::mem::take(&mut task.subtasks);
+                    *task = tasks::Task { subtasks, ..updated.clone() };
+                }
+            });
+            Ok(updated)
+        }
+        Err(e) => {
+            restore_task(&state, &tasklist_id, &task_id, previous);
+            Err(e)
+        }
+    }
+}
+
+#[tauri::command]
+async fn delete_task(
+    task_id: String,
+    tasklist_id: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), String> {
+    let settings = settings::load_settings();
+    let provider = task_provider_for(&state, &settings, &tasklist_id, &task_id)?;
+    let previous = cached_task(&state, &tasklist_id, &task_id);
+    update_cache(&state, |data| {
+        tasks::retain_task(&mut data.tasks, &tasklist_id, &task_id);
+    });
+
+    if let Err(e) = provider.delete_task(&task_id, &tasklist_id).await {
+        restore_task(&state, &tasklist_id, &task_id, previous);
+        return Err(e);
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn uncomplete_task(
+    task_id: String,
+    tasklist_id: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<tasks::Task, String> {
+    let settings = settings::load_settings();
+    let provider = task_provider_for(&state, &settings, &tasklist_id, &task_id)?;
+    let previous = cached_task(&state, &tasklist_id, &task_id);
+    update_cache(&state, |data| {
+        if let Some(task) = tasks::find_task_mut(&mut data.tasks, &tasklist_id, &task_id) {
+            task.completed = false;
+        }
+    });
+
+    match provider.uncomplete_task(&task_id, &tasklist_id).await {
+        Ok(reopened) => {
+            update_cache(&state, |data| {
+                tasks::retain_task(&mut data.tasks, &tasklist_id, &task_id);
+                data.completed_tasks.retain(|t| !tasks::is_task(t, &tasklist_id, &task_id));
+                tasks::insert_under_parent(&mut data.tasks, reopened.clone());
+            });
+            Ok(reopened)
+        }
+        Err(e) => {
+            restore_task(&state, &tasklist_id, &task_id, previous);
+            Err(e)
+        }
+    }
+}
+
+/// Sends the completions still inside the undo window right away; the cache already shows them
+/// as done, so dropping them on exit would lose them silently
+async fn flush_pending_completions(state: &AppState) {
+    let pending: Vec<PendingCompletion> = state
+        .pending_completions
+        .lock()
+        .unwrap()
+        .drain()
+        .map(|(_, pending)| pending)
+        .collect();
+    for pending in pending {
+        pending.handle.abort();
+        if let Err(e) = finish_completion(state, &pending.task_id, &pending.tasklist_id).await {
+            eprintln!("Failed to complete task {}: {}", pending.task_id, e);
+        }
+    }
+}
+
+/// Exits through `ExitRequested`, which flushes pending completions first
 #[tauri::command]
 async fn close_widget(app: AppHandle) {
     app.exit(0);
@@ -101,6 +854,11 @@ async fn start_drag(window: tauri::Window) -> Result<(), String> {
 }
 
 fn main() {
+    // Subcommands run without the window and exit
+    if let Some(command) = cli::parse() {
+        std::process::exit(cli::run(command));
+    }
+
     // Load cached data at startup
     let cached = load_cache();
 
@@ -108,14 +866,62 @@ fn main() {
         .plugin(tauri_plugin_shell::init())
         .manage(AppState {
             cache: Mutex::new(cached),
+            pending_completions: Mutex::new(HashMap::new()),
+            clipboard: Mutex::new(None),
+        })
+        .setup(|app| {
+            // Local calendars are re-read as soon as they change; new paths are picked up on restart
+            let sources = settings::load_settings().providers.local;
+            if !sources.is_empty() {
+                let handle = app.handle().clone();
+                match local::watch(&sources, move || {
+                    let _ = handle.emit("calendar-files-changed", ());
+                }) {
+                    Ok(watcher) => {
+                        app.manage(Mutex::new(watcher));
+                    }
+                    Err(e) => eprintln!("{}", e),
+                }
+            }
+            Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             get_data,
             get_cached_data,
+            get_settings,
+            get_calendars,
+            create_event,
+            quick_add,
+            update_event,
+            delete_event,
             complete_task,
+            undo_complete_task,
+            get_tasklists,
+            create_task,
+            update_task,
+            delete_task,
+            uncomplete_task,
+            respond_to_event,
+            get_series_master,
+            find_free_slots,
+            export_ics,
+            export_agenda,
+            open_url,
             close_widget,
             start_drag
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            if let tauri::RunEvent::ExitRequested { api, .. } = event {
+                if !app.state::<AppState>().pending_completions.lock().unwrap().is_empty() {
+                    api.prevent_exit();
+                    let app = app.clone();
+                    tauri::async_runtime::spawn(async move {
+                        flush_pending_completions(&app.state::<AppState>()).await;
+                        app.exit(0);
+                    });
+                }
+            }
+        });
 }
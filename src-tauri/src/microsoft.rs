@@ -0,0 +1,739 @@
+//! Microsoft 365 / Outlook.com calendars and Microsoft To Do through Microsoft Graph, signed in
+//! with the same loopback PKCE flow as Google. Graph events are mapped onto the Calendar API's
+//! event resource so they go through the same expansion as every other provider.
+
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+use crate::auth::{self, OAuthClient};
+use crate::calendar::{self, Calendar, Event};
+use crate::ics;
+use crate::provider::{CalendarProvider, TaskProvider, MICROSOFT};
+use crate::settings::{MicrosoftAccount, Settings};
+use crate::tasks::{self, Task, TaskFetch};
+
+const SCOPES: &[&str] = &["offline_access", "User.Read", "Calendars.Read", "Tasks.ReadWrite"];
+
+const PAGE_SIZE: usize = 100;
+
+#[derive(Debug, Deserialize)]
+struct Page<T> {
+    value: Option<Vec<T>>,
+    #[serde(rename = "@odata.nextLink")]
+    next_link: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GraphUser {
+    mail: Option<String>,
+    user_principal_name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GraphCalendar {
+    id: String,
+    name: Option<String>,
+    color: Option<String>,
+    hex_color: Option<String>,
+    is_default_calendar: Option<bool>,
+    can_edit: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GraphDateTime {
+    date_time: String,
+    time_zone: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GraphEvent {
+    id: String,
+    subject: Option<String>,
+    body_preview: Option<String>,
+    start: Option<GraphDateTime>,
+    end: Option<GraphDateTime>,
+    is_all_day: Option<bool>,
+    is_cancelled: Option<bool>,
+    show_as: Option<String>,
+    location: Option<GraphLocation>,
+    organizer: Option<GraphRecipient>,
+    attendees: Option<Vec<GraphAttendee>>,
+    online_meeting: Option<GraphOnlineMeeting>,
+    series_master_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GraphLocation {
+    display_name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GraphRecipient {
+    email_address: Option<GraphEmailAddress>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphEmailAddress {
+    name: Option<String>,
+    address: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GraphAttendee {
+    #[serde(rename = "type")]
+    attendee_type: Option<String>,
+    status: Option<GraphResponseStatus>,
+    email_address: Option<GraphEmailAddress>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphResponseStatus {
+    response: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GraphOnlineMeeting {
+    join_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GraphTaskList {
+    id: String,
+    display_name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GraphTask {
+    id: String,
+    title: Option<String>,
+    status: Option<String>,
+    importance: Option<String>,
+    body: Option<GraphItemBody>,
+    due_date_time: Option<GraphDateTime>,
+    completed_date_time: Option<GraphDateTime>,
+    last_modified_date_time: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GraphItemBody {
+    content: Option<String>,
+    content_type: Option<String>,
+}
+
+/// Absolute time of a Graph dateTimeTimeZone. Every request asks for UTC (see `get`); an IANA
+/// zone that still comes back is honoured, while anything else, including Windows names such
+/// as "Pacific Standard Time", is read as UTC.
+fn graph_utc(value: &GraphDateTime) -> Option<DateTime<Utc>> {
+    let naive = NaiveDateTime::parse_from_str(&value.date_time, "%Y-%m-%dT%H:%M:%S%.f").ok()?;
+    match value.time_zone.as_deref().and_then(ics::parse_tzid) {
+        Some(tz) => tz.from_local_datetime(&naive).earliest().map(|dt| dt.with_timezone(&Utc)),
+        None => Some(Utc.from_utc_datetime(&naive)),
+    }
+}
+
+/// Calendar API response status for a Graph response type
+fn response_status(response: Option<&str>) -> &'static str {
+    match response {
+        Some("accepted") | Some("organizer") => "accepted",
+        Some("declined") => "declined",
+        Some("tentativelyAccepted") => "tentative",
+        _ => "needsAction",
+    }
+}
+
+/// Outlook's named calendar colors, used when a calendar has no hex color
+fn calendar_color(name: &str) -> &'static str {
+    match name {
+        "lightBlue" => "#3b82f6",
+        "lightGreen" => "#22c55e",
+        "lightOrange" => "#f97316",
+        "lightGray" => "#64748b",
+        "lightYellow" => "#eab308",
+        "lightTeal" => "#14b8a6",
+        "lightPink" => "#ec4899",
+        "lightBrown" => "#a16207",
+        "lightRed" => "#ef4444",
+        _ => "#0078d4",
+    }
+}
+
+pub struct MicrosoftProvider {
+    account: MicrosoftAccount,
+}
+
+impl MicrosoftProvider {
+    pub fn new(account: MicrosoftAccount) -> Self {
+        MicrosoftProvider { account }
+    }
+
+    fn oauth_client(&self) -> OAuthClient {
+        let base = format!("{}/{}/oauth2/v2.0", self.account.login_url.trim_end_matches('/'), self.account.tenant);
+        OAuthClient {
+            client_id: self.account.client_id.clone(),
+            client_secret: String::new(),
+            auth_uri: format!("{}/authorize", base),
+            token_uri: format!("{}/token", base),
+            auth_params: vec![("prompt", "select_account")],
+            token_file: "token_microsoft.json",
+        }
+    }
+
+    async fn access_token(&self) -> Result<String, String> {
+        if self.account.client_id.is_empty() {
+            return Err("Set providers.microsoft.client_id to sign in to Microsoft".to_string());
+        }
+        auth::access_token(&self.oauth_client(), SCOPES).await
+    }
+
+    pub async fn sign_in(&self) -> Result<(), String> {
+        if self.account.client_id.is_empty() {
+            return Err("Set providers.microsoft.client_id to sign in to Microsoft".to_string());
+        }
+        auth::sign_in(&self.oauth_client(), SCOPES).await
+    }
+
+    pub fn sign_out(&self) -> Result<bool, String> {
+        auth::sign_out(self.oauth_client().token_file)
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.account.graph_url.trim_end_matches('/'), path)
+    }
+
+    async fn get<T: DeserializeOwned>(&self, client: &reqwest::Client, token: &str, url: &str) -> Result<T, String> {
+        let response = client
+            .get(url)
+            .bearer_auth(token)
+            // Event times come back in UTC instead of each calendar's own zone
+            .header("Prefer", "outlook.timezone=\"UTC\"")
+            .send()
+            .await
+            .map_err(|e| format!("Failed to reach Microsoft Graph: {}", e))?;
+
+        if !response.status().is_success() {
+            let error = response.text().await.unwrap_or_default();
+            return Err(format!("Microsoft Graph error: {}", error));
+        }
+
+        response
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse Microsoft Graph response: {}", e))
+    }
+
+    /// Items of a collection, following `@odata.nextLink` up to `limit` entries.
+    /// The flag is set when there were more.
+    async fn get_all<T: DeserializeOwned>(
+        &self,
+        client: &reqwest::Client,
+        token: &str,
+        url: String,
+        limit: usize,
+    ) -> Result<(Vec<T>, bool), String> {
+        let mut items = Vec::new();
+        let mut next = Some(url);
+
+        while let Some(url) = next {
+            let page: Page<T> = self.get(client, token, &url).await?;
+            items.extend(page.value.unwrap_or_default());
+
+            if items.len() >= limit {
+                let truncated = items.len() > limit || page.next_link.is_some();
+                items.truncate(limit);
+                return Ok((items, truncated));
+            }
+            next = page.next_link;
+        }
+
+        Ok((items, false))
+    }
+
+    fn to_calendar(&self, entry: GraphCalendar) -> Calendar {
+        let color = entry
+            .hex_color
+            .filter(|c| !c.is_empty())
+            .unwrap_or_else(|| calendar_color(entry.color.as_deref().unwrap_or("auto")).to_string());
+        Calendar {
+            id: entry.id,
+            name: entry.name.unwrap_or_else(|| "Calendar".to_string()),
+            color,
+            foreground_color: "#ffffff".to_string(),
+            primary: entry.is_default_calendar.unwrap_or(false),
+            writable: entry.can_edit.unwrap_or(false),
+        }
+    }
+
+    fn to_task(&self, entry: GraphTask, tasklist_id: &str) -> Task {
+        let completed_at = entry
+            .completed_date_time
+            .as_ref()
+            .and_then(graph_utc)
+            .map(|dt| dt.to_rfc3339())
+            .unwrap_or_default();
+        let notes = entry
+            .body
+            .filter(|b| b.content_type.as_deref() != Some("html"))
+            .and_then(|b| b.content)
+            .unwrap_or_default();
+
+        Task {
+            id: entry.id,
+            title: entry.title.unwrap_or_default(),
+            completed: entry.status.as_deref() == Some("completed"),
+            tasklist_id: tasklist_id.to_string(),
+            provider: MICROSOFT.to_string(),
+            notes: notes.trim().to_string(),
+            // To Do stores due dates as midnight in the user's zone; only the date matters
+            due: entry
+                .due_date_time
+                .map(|d| d.date_time.chars().take(10).collect())
+                .unwrap_or_default(),
+            updated: entry.last_modified_date_time.unwrap_or_default(),
+            completed_at,
+            priority: match entry.importance.as_deref() {
+                Some("high") => 1,
+                Some("low") => 9,
+                _ => 0,
+            },
+            ..Task::default()
+        }
+    }
+}
+
+/// The event in the Calendar API's shape, for `calendar::events_from_resource`
+fn to_event_resource(event: GraphEvent, self_email: &str) -> serde_json::Value {
+    let all_day = event.is_all_day.unwrap_or(false);
+    let time = |value: Option<&GraphDateTime>| match value {
+        // All-day events start and end at midnight; the end date is exclusive as in the Calendar API
+        Some(value) if all_day => serde_json::json!({ "date": value.date_time.chars().take(10).collect::<String>() }),
+        Some(value) => serde_json::json!({ "dateTime": graph_utc(value).map(|dt| dt.to_rfc3339()) }),
+        None => serde_json::Value::Null,
+    };
+
+    let address = |recipient: &Option<GraphEmailAddress>| {
+        recipient
+            .as_ref()
+            .and_then(|e| e.address.clone())
+            .unwrap_or_default()
+    };
+    let organizer_email = event
+        .organizer
+        .as_ref()
+        .map(|o| address(&o.email_address))
+        .unwrap_or_default();
+    let organizer = event.organizer.as_ref().map(|o| {
+        serde_json::json!({
+            "email": organizer_email,
+            "displayName": o.email_address.as_ref().and_then(|e| e.name.clone()),
+        })
+    });
+
+    let attendees: Vec<serde_json::Value> = event
+        .attendees
+        .unwrap_or_default()
+        .into_iter()
+        .map(|a| {
+            let email = address(&a.email_address);
+            serde_json::json!({
+                "email": email,
+                "displayName": a.email_address.as_ref().and_then(|e| e.name.clone()),
+                "responseStatus": response_status(a.status.as_ref().and_then(|s| s.response.as_deref())),
+                "self": !self_email.is_empty() && email.eq_ignore_ascii_case(self_email),
+                "organizer": !organizer_email.is_empty() && email.eq_ignore_ascii_case(&organizer_email),
+                "optional": a.attendee_type.as_deref() == Some("optional"),
+            })
+        })
+        .collect();
+
+    let conference = event
+        .online_meeting
+        .and_then(|m| m.join_url)
+        .map(|url| {
+            serde_json::json!({
+                "entryPoints": [{ "entryPointType": "video", "uri": url, "label": "Microsoft Teams" }]
+            })
+        });
+
+    serde_json::json!({
+        "id": event.id,
+        "status": if event.is_cancelled.unwrap_or(false) { "cancelled" } else { "confirmed" },
+        "transparency": if event.show_as.as_deref() == Some("free") { "transparent" } else { "opaque" },
+        "summary": event.subject,
+        "location": event.location.and_then(|l| l.display_name).filter(|l| !l.is_empty()),
+        "description": event.body_preview,
+        "start": time(event.start.as_ref()),
+        "end": time(event.end.as_ref()),
+        "organizer": organizer,
+        "attendees": attendees,
+        "conferenceData": conference,
+        "recurringEventId": event.series_master_id,
+    })
+}
+
+#[async_trait]
+impl CalendarProvider for MicrosoftProvider {
+    fn id(&self) -> String {
+        MICROSOFT.to_string()
+    }
+
+    async fn get_events(&self, days: i32, settings: &Settings) -> Result<Vec<Event>, String> {
+        let token = self.access_token().await?;
+        let client = reqwest::Client::new();
+
+        // Only used to recognise your own attendee entry
+        let self_email = match self.get::<GraphUser>(&client, &token, &self.url("/me")).await {
+            Ok(user) => user.mail.or(user.user_principal_name).unwrap_or_default(),
+            Err(e) => {
+                eprintln!("{}", e);
+                String::new()
+            }
+        };
+
+        let (calendars, _) = self
+            .get_all::<GraphCalendar>(&client, &token, self.url("/me/calendars"), usize::MAX)
+            .await?;
+
+        let (time_min, time_max) = calendar::event_window(days, &settings.locale);
+        let mut all_events = Vec::new();
+
+        for entry in calendars {
+            let calendar = self.to_calendar(entry);
+            if !self.account.calendars.is_empty() && !self.account.calendars.contains(&calendar.name) {
+                continue;
+            }
+
+            let url = self.url(&format!(
+                "/me/calendars/{}/calendarView?startDateTime={}&endDateTime={}&$top={}",
+                urlencoding::encode(&calendar.id),
+                urlencoding::encode(&time_min.to_rfc3339()),
+                urlencoding::encode(&time_max.to_rfc3339()),
+                PAGE_SIZE
+            ));
+            let events = match self.get_all::<GraphEvent>(&client, &token, url, usize::MAX).await {
+                Ok((events, _)) => events,
+                Err(e) => {
+                    eprintln!("Failed to fetch events from {}: {}", calendar.name, e);
+                    continue;
+                }
+            };
+
+            for event in events {
+                match calendar::events_from_resource(to_event_resource(event, &self_email), &calendar, settings) {
+                    Ok(events) => all_events.extend(events),
+                    Err(e) => eprintln!("Skipping event in {}: {}", calendar.name, e),
+                }
+            }
+        }
+
+        calendar::sort_events(&mut all_events);
+        Ok(all_events)
+    }
+}
+
+#[async_trait]
+impl TaskProvider for MicrosoftProvider {
+    fn id(&self) -> String {
+        MICROSOFT.to_string()
+    }
+
+    async fn get_tasks(&self, settings: &Settings) -> Result<TaskFetch, String> {
+        let token = self.access_token().await?;
+        let client = reqwest::Client::new();
+        let limit = settings.tasks.max_tasks_per_list;
+        let completed_min = tasks::completed_min(settings).and_then(|m| DateTime::parse_from_rfc3339(&m).ok());
+
+        let (lists, _) = self
+            .get_all::<GraphTaskList>(&client, &token, self.url("/me/todo/lists"), usize::MAX)
+            .await?;
+
+        let mut fetch = TaskFetch::default();
+        for list in lists {
+            let list_title = list.display_name.unwrap_or_default();
+            if !self.account.task_lists.is_empty() && !self.account.task_lists.contains(&list_title) {
+                continue;
+            }
+
+            let tasks_url = |filter: &str| {
+                self.url(&format!(
+                    "/me/todo/lists/{}/tasks?$filter={}&$top={}",
+                    urlencoding::encode(&list.id),
+                    urlencoding::encode(filter),
+                    PAGE_SIZE
+                ))
+            };
+
+            let (items, truncated) = match self
+                .get_all::<GraphTask>(&client, &token, tasks_url("status ne 'completed'"), limit)
+                .await
+            {
+                Ok(result) => result,
+                Err(e) => {
+                    eprintln!("Failed to fetch tasks from {}: {}", list_title, e);
+                    continue;
+                }
+            };
+
+            if truncated {
+                eprintln!(
+                    "Task list {} has more than {} tasks; the rest were skipped",
+                    list_title, limit
+                );
+                fetch.truncated_lists.push(list_title.clone());
+            }
+
+            if let Some(min) = completed_min {
+                // Completion times are stored in UTC; newest first, so the limit drops the oldest
+                let filter = format!(
+                    "status eq 'completed' and completedDateTime/dateTime ge '{}'",
+                    min.with_timezone(&Utc).format("%Y-%m-%dT%H:%M:%S")
+                );
+                let url = format!(
+                    "{}&$orderby={}",
+                    tasks_url(&filter),
+                    urlencoding::encode("completedDateTime/dateTime desc")
+                );
+                match self.get_all::<GraphTask>(&client, &token, url, limit).await {
+                    Ok((items, _)) => fetch.completed.extend(
+                        items
+                            .into_iter()
+                            .map(|item| self.to_task(item, &list.id))
+                            .filter(|t| {
+                                !t.title.is_empty()
+                                    && DateTime::parse_from_rfc3339(&t.completed_at).is_ok_and(|at| at >= min)
+                            }),
+                    ),
+                    Err(e) => eprintln!("Failed to fetch completed tasks from {}: {}", list_title, e),
+                }
+            }
+
+            fetch.tasks.extend(
+                items
+                    .into_iter()
+                    .map(|item| self.to_task(item, &list.id))
+                    .filter(|t| !t.title.is_empty()),
+            );
+        }
+
+        fetch.completed.sort_by(|a, b| b.completed_at.cmp(&a.completed_at));
+        Ok(fetch)
+    }
+
+    async fn complete_task(&self, task_id: &str, tasklist_id: &str, _settings: &Settings) -> Result<Task, String> {
+        let token = self.access_token().await?;
+        let url = self.url(&format!(
+            "/me/todo/lists/{}/tasks/{}",
+            urlencoding::encode(tasklist_id),
+            urlencoding::encode(task_id)
+        ));
+
+        let response = reqwest::Client::new()
+            .patch(&url)
+            .bearer_auth(&token)
+            .json(&serde_json::json!({ "status": "completed" }))
+            .send()
+            .await
+            .map_err(|e| format!("Failed to reach Microsoft Graph: {}", e))?;
+
+        if !response.status().is_success() {
+            let error = response.text().await.unwrap_or_default();
+            return Err(format!("Microsoft Graph error: {}", error));
+        }
+
+        let entry: GraphTask = response
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse task: {}", e))?;
+        Ok(self.to_task(entry, tasklist_id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn provider(graph_url: &str) -> MicrosoftProvider {
+        MicrosoftProvider::new(MicrosoftAccount {
+            graph_url: graph_url.to_string(),
+            ..MicrosoftAccount::default()
+        })
+    }
+
+    fn graph_time(date_time: &str, time_zone: Option<&str>) -> GraphDateTime {
+        GraphDateTime {
+            date_time: date_time.to_string(),
+            time_zone: time_zone.map(|z| z.to_string()),
+        }
+    }
+
+    #[test]
+    fn graph_times() {
+        let cases = [
+            (Some("UTC"), "2026-10-14T08:00:00+00:00"),
+            (None, "2026-10-14T08:00:00+00:00"),
+            (Some("Europe/Oslo"), "2026-10-14T06:00:00+00:00"),
+            // Windows zone names aren't known, and requests ask for UTC anyway
+            (Some("Pacific Standard Time"), "2026-10-14T08:00:00+00:00"),
+        ];
+        for (time_zone, expected) in cases {
+            let utc = graph_utc(&graph_time("2026-10-14T08:00:00.0000000", time_zone)).unwrap();
+            assert_eq!(utc.to_rfc3339(), expected, "{:?}", time_zone);
+        }
+        assert!(graph_utc(&graph_time("next week", None)).is_none());
+    }
+
+    fn event(json: serde_json::Value) -> serde_json::Value {
+        let mut graph = serde_json::json!({
+            "id": "AAMk1",
+            "subject": "Planning",
+            "start": { "dateTime": "2026-10-14T08:00:00.0000000", "timeZone": "UTC" },
+            "end": { "dateTime": "2026-10-14T09:00:00.0000000", "timeZone": "UTC" },
+        });
+        graph.as_object_mut().unwrap().extend(json.as_object().unwrap().clone());
+        to_event_resource(serde_json::from_value(graph).unwrap(), "me@example.com")
+    }
+
+    #[test]
+    fn maps_events_to_calendar_api_resources() {
+        let timed = event(serde_json::json!({}));
+        assert_eq!(timed["start"]["dateTime"], "2026-10-14T08:00:00+00:00");
+        assert_eq!(timed["end"]["dateTime"], "2026-10-14T09:00:00+00:00");
+        assert_eq!(timed["transparency"], "opaque");
+        assert_eq!(timed["status"], "confirmed");
+        assert!(timed["conferenceData"].is_null());
+
+        let all_day = event(serde_json::json!({
+            "isAllDay": true,
+            "start": { "dateTime": "2026-10-14T00:00:00.0000000", "timeZone": "UTC" },
+            "end": { "dateTime": "2026-10-16T00:00:00.0000000", "timeZone": "UTC" },
+        }));
+        assert_eq!(all_day["start"], serde_json::json!({ "date": "2026-10-14" }));
+        assert_eq!(all_day["end"], serde_json::json!({ "date": "2026-10-16" }));
+
+        let free = event(serde_json::json!({ "showAs": "free", "isCancelled": true }));
+        assert_eq!(free["transparency"], "transparent");
+        assert_eq!(free["status"], "cancelled");
+
+        let teams = event(serde_json::json!({
+            "onlineMeeting": { "joinUrl": "https://teams.microsoft.com/l/meetup-join/19%3ameeting" }
+        }));
+        assert_eq!(
+            teams["conferenceData"]["entryPoints"][0]["uri"],
+            "https://teams.microsoft.com/l/meetup-join/19%3ameeting"
+        );
+    }
+
+    #[test]
+    fn flags_yourself_and_the_organizer() {
+        let meeting = event(serde_json::json!({
+            "organizer": { "emailAddress": { "name": "Kari", "address": "kari@example.com" } },
+            "attendees": [
+                {
+                    "type": "required",
+                    "status": { "response": "organizer" },
+                    "emailAddress": { "name": "Kari", "address": "Kari@Example.com" }
+                },
+                {
+                    "type": "optional",
+                    "status": { "response": "tentativelyAccepted" },
+                    "emailAddress": { "name": "Me", "address": "ME@example.com" }
+                },
+                { "type": "required", "status": { "response": "none" }, "emailAddress": { "address": "ola@example.com" } }
+            ]
+        }));
+        let flags: Vec<(bool, bool, bool, &str)> = meeting["attendees"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|a| {
+                (
+                    a["self"].as_bool().unwrap(),
+                    a["organizer"].as_bool().unwrap(),
+                    a["optional"].as_bool().unwrap(),
+                    a["responseStatus"].as_str().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            flags,
+            [
+                (false, true, false, "accepted"),
+                (true, false, true, "tentative"),
+                (false, false, false, "needsAction"),
+            ]
+        );
+        assert_eq!(meeting["organizer"]["email"], "kari@example.com");
+    }
+
+    #[test]
+    fn maps_to_do_tasks() {
+        let task = |json: serde_json::Value| {
+            let mut graph = serde_json::json!({ "id": "t1", "title": "Report" });
+            graph.as_object_mut().unwrap().extend(json.as_object().unwrap().clone());
+            provider("").to_task(serde_json::from_value(graph).unwrap(), "list")
+        };
+
+        for (importance, priority) in [("high", 1), ("normal", 0), ("low", 9)] {
+            assert_eq!(task(serde_json::json!({ "importance": importance })).priority, priority, "{}", importance);
+        }
+
+        let done = task(serde_json::json!({
+            "status": "completed",
+            "dueDateTime": { "dateTime": "2026-10-14T00:00:00.0000000", "timeZone": "W. Europe Standard Time" },
+            "completedDateTime": { "dateTime": "2026-10-13T15:30:00.0000000", "timeZone": "UTC" },
+            "body": { "content": "<p>Notes</p>", "contentType": "html" },
+        }));
+        assert!(done.completed);
+        assert_eq!(done.due, "2026-10-14");
+        assert_eq!(done.completed_at, "2026-10-13T15:30:00+00:00");
+        assert_eq!(done.notes, "");
+        assert_eq!((done.tasklist_id.as_str(), done.provider.as_str()), ("list", MICROSOFT));
+    }
+
+    /// A Graph stand-in serving `pages` of task lists, each linking to the next
+    fn serve(pages: &'static [usize]) -> String {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", server.server_addr().to_ip().unwrap());
+        let link = base.clone();
+        std::thread::spawn(move || {
+            for request in server.incoming_requests() {
+                let page: usize = request.url().trim_start_matches("/page/").parse().unwrap();
+                let value: Vec<serde_json::Value> = (0..pages[page])
+                    .map(|n| serde_json::json!({ "id": format!("{}-{}", page, n) }))
+                    .collect();
+                let mut body = serde_json::json!({ "value": value });
+                if page + 1 < pages.len() {
+                    body["@odata.nextLink"] = format!("{}/page/{}", link, page + 1).into();
+                }
+                let _ = request.respond(tiny_http::Response::from_string(body.to_string()));
+            }
+        });
+        base
+    }
+
+    #[tokio::test]
+    async fn get_all_flags_truncation() {
+        let base = serve(&[2, 2, 1]);
+        let provider = provider(&base);
+        let client = reqwest::Client::new();
+        for (limit, count, truncated) in [(10, 5, false), (5, 5, false), (4, 4, true), (3, 3, true)] {
+            let (items, flag) = provider
+                .get_all::<GraphTaskList>(&client, "token", format!("{}/page/0", base), limit)
+                .await
+                .unwrap();
+            assert_eq!((items.len(), flag), (count, truncated), "limit {}", limit);
+        }
+    }
+}
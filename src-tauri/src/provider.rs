@@ -0,0 +1,177 @@
+//! Calendar and task backends behind a common interface, so `get_data` can merge several sources.
+
+use async_trait::async_trait;
+
+use crate::caldav::CalDavProvider;
+use crate::caldav_tasks::CalDavTaskProvider;
+use crate::calendar::{self, Event};
+use crate::feeds::IcsFeedProvider;
+use crate::local::LocalCalendarProvider;
+use crate::microsoft::MicrosoftProvider;
+use crate::settings::Settings;
+use crate::tasks::{self, Task, TaskDraft, TaskFetch, TaskList};
+use crate::todoist::TodoistProvider;
+
+pub const GOOGLE: &str = "google";
+pub const MICROSOFT: &str = "microsoft";
+pub const TODOIST: &str = "todoist";
+
+#[async_trait]
+pub trait CalendarProvider: Send + Sync {
+    /// Stable id stored on every event, used to route later calls back to this provider
+    fn id(&self) -> String;
+
+    async fn get_events(&self, days: i32, settings: &Settings) -> Result<Vec<Event>, String>;
+}
+
+#[async_trait]
+pub trait TaskProvider: Send + Sync {
+    fn id(&self) -> String;
+
+    async fn get_tasks(&self, settings: &Settings) -> Result<TaskFetch, String>;
+
+    async fn complete_task(&self, task_id: &str, tasklist_id: &str, settings: &Settings) -> Result<Task, String>;
+
+    // Everything past reading and completing is optional; providers without it refuse
+
+    /// Lists new tasks can be added to
+    async fn get_tasklists(&self) -> Result<Vec<TaskList>, String> {
+        Err(unsupported(&self.id(), "Adding tasks"))
+    }
+
+    async fn create_task(&self, _tasklist_id: &str, _draft: &TaskDraft) -> Result<Task, String> {
+        Err(unsupported(&self.id(), "Adding tasks"))
+    }
+
+    async fn update_task(&self, _task_id: &str, _tasklist_id: &str, _draft: &TaskDraft) -> Result<Task, String> {
+        Err(unsupported(&self.id(), "Editing tasks"))
+    }
+
+    async fn uncomplete_task(&self, _task_id: &str, _tasklist_id: &str) -> Result<Task, String> {
+        Err(unsupported(&self.id(), "Reopening tasks"))
+    }
+
+    async fn delete_task(&self, _task_id: &str, _tasklist_id: &str) -> Result<(), String> {
+        Err(unsupported(&self.id(), "Deleting tasks"))
+    }
+}
+
+fn unsupported(provider: &str, action: &str) -> String {
+    format!("{} isn't supported for {} tasks", action, provider)
+}
+
+/// Google Calendar and Google Tasks, signed in through `auth.rs`
+pub struct GoogleProvider;
+
+#[async_trait]
+impl CalendarProvider for GoogleProvider {
+    fn id(&self) -> String {
+        GOOGLE.to_string()
+    }
+
+    async fn get_events(&self, days: i32, settings: &Settings) -> Result<Vec<Event>, String> {
+        calendar::get_events(days, settings).await
+    }
+}
+
+#[async_trait]
+impl TaskProvider for GoogleProvider {
+    fn id(&self) -> String {
+        GOOGLE.to_string()
+    }
+
+    async fn get_tasks(&self, settings: &Settings) -> Result<TaskFetch, String> {
+        tasks::get_tasks(settings).await
+    }
+
+    async fn complete_task(&self, task_id: &str, tasklist_id: &str, _settings: &Settings) -> Result<Task, String> {
+        tasks::complete_task(task_id, tasklist_id).await
+    }
+
+    async fn get_tasklists(&self) -> Result<Vec<TaskList>, String> {
+        tasks::get_tasklists().await
+    }
+
+    async fn create_task(&self, tasklist_id: &str, draft: &TaskDraft) -> Result<Task, String> {
+        tasks::insert_task(tasklist_id, draft).await
+    }
+
+    async fn update_task(&self, task_id: &str, tasklist_id: &str, draft: &TaskDraft) -> Result<Task, String> {
+        tasks::update_task(task_id, tasklist_id, draft).await
+    }
+
+    async fn uncomplete_task(&self, task_id: &str, tasklist_id: &str) -> Result<Task, String> {
+        tasks::uncomplete_task(task_id, tasklist_id).await
+    }
+
+    async fn delete_task(&self, task_id: &str, tasklist_id: &str) -> Result<(), String> {
+        tasks::delete_task(task_id, tasklist_id).await
+    }
+}
+
+pub fn calendar_providers(settings: &Settings) -> Vec<Box<dyn CalendarProvider>> {
+    let mut providers: Vec<Box<dyn CalendarProvider>> = Vec::new();
+    if settings.providers.google {
+        providers.push(Box::new(GoogleProvider));
+    }
+    if settings.providers.microsoft.enabled {
+        providers.push(Box::new(MicrosoftProvider::new(settings.providers.microsoft.clone())));
+    }
+    for account in &settings.providers.caldav {
+        providers.push(Box::new(CalDavProvider::new(account.clone())));
+    }
+    for feed in &settings.providers.ics {
+        providers.push(Box::new(IcsFeedProvider::new(feed.clone())));
+    }
+    for source in &settings.providers.local {
+        providers.push(Box::new(LocalCalendarProvider::new(source.clone())));
+    }
+    providers
+}
+
+pub fn task_providers(settings: &Settings) -> Vec<Box<dyn TaskProvider>> {
+    let mut providers: Vec<Box<dyn TaskProvider>> = Vec::new();
+    if settings.providers.google {
+        providers.push(Box::new(GoogleProvider));
+    }
+    if settings.providers.microsoft.enabled {
+        providers.push(Box::new(MicrosoftProvider::new(settings.providers.microsoft.clone())));
+    }
+    for account in &settings.providers.caldav {
+        providers.push(Box::new(CalDavTaskProvider::new(account.clone())));
+    }
+    if !settings.providers.todoist.token.is_empty() {
+        providers.push(Box::new(TodoistProvider::new(settings.providers.todoist.clone())));
+    }
+    providers
+}
+
+pub fn task_provider(settings: &Settings, id: &str) -> Result<Box<dyn TaskProvider>, String> {
+    task_providers(settings)
+        .into_iter()
+        .find(|p| p.id() == id)
+        .ok_or_else(|| format!("Task provider '{}' is not configured", id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::CalDavAccount;
+
+    #[test]
+    fn routes_tasks_to_their_provider() {
+        let mut settings = Settings::default();
+        settings.providers.google = false;
+        settings.providers.caldav.push(CalDavAccount {
+            name: "Work".to_string(),
+            ..CalDavAccount::default()
+        });
+        settings.providers.todoist.token = "token".to_string();
+
+        assert_eq!(task_provider(&settings, "caldav:Work").unwrap().id(), "caldav:Work");
+        assert_eq!(task_provider(&settings, TODOIST).unwrap().id(), TODOIST);
+        // No silent fallback to Google
+        assert!(task_provider(&settings, GOOGLE).is_err());
+        assert!(task_provider(&settings, "caldav:Home").is_err());
+    }
+}
@@ -0,0 +1,751 @@
+//! Parses free text such as "Lunch with Sam tomorrow 12-13 @Cafe" into an event draft.
+//! Everything here is pure: the current time and locale are passed in.
+
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Weekday};
+
+use crate::calendar::EventDraft;
+use crate::settings::LocaleSettings;
+
+const DEFAULT_DURATION_MINUTES: i64 = 60;
+
+const WEEKDAYS: [Weekday; 7] = [
+    Weekday::Mon,
+    Weekday::Tue,
+    Weekday::Wed,
+    Weekday::Thu,
+    Weekday::Fri,
+    Weekday::Sat,
+    Weekday::Sun,
+];
+
+/// Words that introduce a date or time and are dropped from the title along with it
+const DATE_PREFIXES: &[&str] = &["on"];
+const TIME_PREFIXES: &[&str] = &["at", "@", "kl", "from"];
+const RANGE_SEPARATORS: &[&str] = &["-", "–", "to", "until", "till"];
+
+#[derive(Debug, Default)]
+struct Parsed {
+    date: Option<NaiveDate>,
+    start: Option<NaiveTime>,
+    end: Option<NaiveTime>,
+    duration: Option<Duration>,
+    all_day: bool,
+}
+
+/// Day and month names accepted by the parser: English plus the configured language
+struct Names {
+    weekdays: Vec<(String, Weekday)>,
+    /// Short day names double as ordinary words ("sun", German "do"), so they only count
+    /// as dates when marked, see `match_date`
+    weekday_abbreviations: Vec<(String, Weekday)>,
+    months: Vec<(String, u32)>,
+}
+
+impl Names {
+    fn new(locale: &LocaleSettings) -> Self {
+        let mut weekdays = Vec::new();
+        let mut weekday_abbreviations = Vec::new();
+        let mut months = Vec::new();
+        let locales = [chrono::Locale::en_US, locale.chrono_locale()];
+
+        for loc in locales {
+            // 2024-01-01 was a Monday
+            for (i, day) in WEEKDAYS.iter().enumerate() {
+                let date = NaiveDate::from_ymd_opt(2024, 1, 1 + i as u32).unwrap();
+                weekdays.push((normalize(&date.format_localized("%A", loc).to_string()), *day));
+                weekday_abbreviations.push((normalize(&date.format_localized("%a", loc).to_string()), *day));
+            }
+            for month in 1..=12 {
+                let date = NaiveDate::from_ymd_opt(2024, month, 1).unwrap();
+                for fmt in ["%B", "%b"] {
+                    months.push((normalize(&date.format_localized(fmt, loc).to_string()), month));
+                }
+            }
+        }
+
+        Names {
+            weekdays,
+            weekday_abbreviations,
+            months,
+        }
+    }
+
+    fn weekday(&self, word: &str) -> Option<Weekday> {
+        let word = normalize(word);
+        self.weekdays.iter().find(|(n, _)| *n == word).map(|(_, d)| *d)
+    }
+
+    fn weekday_abbreviation(&self, word: &str) -> Option<Weekday> {
+        let word = normalize(word);
+        self.weekday_abbreviations.iter().find(|(n, _)| *n == word).map(|(_, d)| *d)
+    }
+
+    /// Numbers and month names, which may follow a day name as part of the same date
+    fn is_date_part(&self, word: &str) -> bool {
+        word.starts_with(|c: char| c.is_ascii_digit()) || self.month(word).is_some()
+    }
+
+    /// Words that mark a neighbouring day abbreviation as a date
+    fn is_date_context(&self, word: &str) -> bool {
+        normalize(word) == "next" || self.is_date_part(word)
+    }
+
+    fn month(&self, word: &str) -> Option<u32> {
+        let word = normalize(word);
+        // "Sept" is common enough to accept alongside the locale's "Sep"
+        if word == "sept" {
+            return Some(9);
+        }
+        self.months.iter().find(|(n, _)| *n == word).map(|(_, m)| *m)
+    }
+}
+
+fn normalize(word: &str) -> String {
+    word.trim_end_matches(['.', ',']).to_lowercase()
+}
+
+/// Parses `text` relative to `now` (wall-clock time in the configured zone).
+/// Timed drafts carry RFC 3339 start/end in the configured zone; all-day drafts carry dates.
+pub fn parse(text: &str, now: NaiveDateTime, locale: &LocaleSettings) -> Result<EventDraft, String> {
+    let (text, location) = split_location(text);
+    let names = Names::new(locale);
+    let today = now.date();
+
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let mut used = vec![false; words.len()];
+    let mut parsed = Parsed::default();
+
+    let mut i = 0;
+    while i < words.len() {
+        let consumed = match_at(&words, i, today, locale, &names, &mut parsed);
+        if consumed > 0 {
+            for flag in used.iter_mut().skip(i).take(consumed) {
+                *flag = true;
+            }
+            // Drop a connector word ("on", "at", ...) right before what we matched
+            if i > 0 && !used[i - 1] && is_prefix(words[i - 1]) {
+                used[i - 1] = true;
+            }
+            i += consumed;
+        } else {
+            i += 1;
+        }
+    }
+
+    let title = words
+        .iter()
+        .zip(&used)
+        .filter(|(_, used)| !**used)
+        .map(|(w, _)| *w)
+        .collect::<Vec<_>>()
+        .join(" ");
+    let title = title.trim_matches(|c: char| c == ',' || c.is_whitespace()).to_string();
+    if title.is_empty() {
+        return Err("Quick add needs a title".to_string());
+    }
+
+    let date = parsed.date.unwrap_or(today);
+
+    let (start, end, is_all_day) = match parsed.start {
+        Some(start_time) if !parsed.all_day => {
+            let start = date.and_time(start_time);
+            let end = match (parsed.end, parsed.duration) {
+                (Some(end_time), _) => {
+                    let end = date.and_time(end_time);
+                    // "23-1" ends after midnight
+                    if end <= start {
+                        end + Duration::days(1)
+                    } else {
+                        end
+                    }
+                }
+                (None, Some(duration)) => start + duration,
+                (None, None) => start + Duration::minutes(DEFAULT_DURATION_MINUTES),
+            };
+            let start = locale
+                .localize(start)
+                .ok_or_else(|| format!("{} does not exist in the configured time zone", start))?;
+            let end = locale
+                .localize(end)
+                .ok_or_else(|| format!("{} does not exist in the configured time zone", end))?;
+            (start.to_rfc3339(), end.to_rfc3339(), false)
+        }
+        _ => {
+            let day = date.format("%Y-%m-%d").to_string();
+            (day.clone(), day, true)
+        }
+    };
+
+    Ok(EventDraft {
+        title,
+        start,
+        end,
+        is_all_day,
+        location,
+        description: String::new(),
+    })
+}
+
+/// "@Cafe" (or "@ Cafe Central") marks the rest of the text as the location,
+/// unless it is followed by a time ("@3pm")
+fn split_location(text: &str) -> (String, String) {
+    let mut search_from = 0;
+    while let Some(pos) = text[search_from..].find('@').map(|p| p + search_from) {
+        let at_word_start = pos == 0 || text[..pos].ends_with(char::is_whitespace);
+        let rest = text[pos + 1..].trim();
+        let first_word = rest.split_whitespace().next().unwrap_or("");
+        if at_word_start && !rest.is_empty() && parse_clock(first_word, true).is_none() {
+            return (text[..pos].trim().to_string(), rest.to_string());
+        }
+        search_from = pos + 1;
+    }
+    (text.trim().to_string(), String::new())
+}
+
+fn is_prefix(word: &str) -> bool {
+    let word = normalize(word);
+    DATE_PREFIXES.contains(&word.as_str()) || TIME_PREFIXES.contains(&word.as_str())
+}
+
+/// Tries every pattern at `words[i]`, returning how many words were consumed
+fn match_at(
+    words: &[&str],
+    i: usize,
+    today: NaiveDate,
+    locale: &LocaleSettings,
+    names: &Names,
+    parsed: &mut Parsed,
+) -> usize {
+    // "@3pm" is a time, not a location
+    let word = normalize(words[i].trim_start_matches('@'));
+    let next = words.get(i + 1).map(|w| normalize(w));
+    let next2 = words.get(i + 2).map(|w| normalize(w));
+    let after_prefix = i > 0 && TIME_PREFIXES.contains(&normalize(words[i - 1]).as_str());
+
+    // "at 12.10" is a time even though it also reads as a dotted date
+    let looks_like_time = after_prefix && parse_clock(&word, true).is_some();
+    if parsed.date.is_none() && !looks_like_time {
+        if let Some((date, n)) = match_date(words, i, today, locale, names) {
+            parsed.date = Some(date);
+            return n;
+        }
+    }
+
+    if !parsed.all_day {
+        if word == "all-day" || word == "allday" {
+            parsed.all_day = true;
+            return 1;
+        }
+        if word == "all" && next.as_deref() == Some("day") {
+            parsed.all_day = true;
+            return 2;
+        }
+    }
+
+    if parsed.start.is_none() {
+        // Single-word range: "12-13", "9:30-10:15", "9-10am"
+        if let Some((start, end)) = parse_clock_range(&word, locale) {
+            parsed.start = Some(start);
+            parsed.end = Some(end);
+            return 1;
+        }
+
+        // Spaced range: "12 - 13", "9am to 10am", "from 9 to 11"
+        if let (Some(sep), Some(end_word)) = (&next, &next2) {
+            if RANGE_SEPARATORS.contains(&sep.as_str()) {
+                let explicit = after_prefix || has_marker(&word) || has_marker(end_word) || word.contains(':');
+                if let Some((start, end)) = parse_clock_pair(&word, end_word, explicit, locale) {
+                    parsed.start = Some(start);
+                    parsed.end = Some(end);
+                    return 3;
+                }
+            }
+        }
+
+        // Single time: "at 15", "15:00", "3pm", "noon". Bare numbers need a prefix.
+        let explicit = after_prefix || has_marker(&word) || word.contains(':');
+        if let Some(time) = parse_clock(&word, explicit) {
+            parsed.start = Some(apply_12h_guess(time, &word, locale));
+            return 1;
+        }
+    }
+
+    if parsed.duration.is_none() && word == "for" {
+        if let Some((duration, n)) = match_duration(&words[i + 1..]) {
+            parsed.duration = Some(duration);
+            return 1 + n;
+        }
+    }
+
+    0
+}
+
+fn match_date(
+    words: &[&str],
+    i: usize,
+    today: NaiveDate,
+    locale: &LocaleSettings,
+    names: &Names,
+) -> Option<(NaiveDate, usize)> {
+    let word = normalize(words[i]);
+    let next = words.get(i + 1).map(|w| normalize(w));
+    let next2 = words.get(i + 2).map(|w| normalize(w));
+
+    match word.as_str() {
+        "today" | "tonight" => return Some((today, 1)),
+        "tomorrow" | "tmrw" | "tmr" => return Some((today + Duration::days(1), 1)),
+        "yesterday" => return Some((today - Duration::days(1), 1)),
+        _ => {}
+    }
+
+    // "day after tomorrow"
+    if word == "day" && next.as_deref() == Some("after") && next2.as_deref() == Some("tomorrow") {
+        return Some((today + Duration::days(2), 3));
+    }
+
+    // "in 3 days", "in 2 weeks"
+    if word == "in" {
+        if let (Some(n), Some(unit)) = (next.as_deref().and_then(|n| n.parse::<i64>().ok()), next2.as_deref()) {
+            let days = match unit {
+                "day" | "days" | "d" => Some(n),
+                "week" | "weeks" | "w" => Some(n * 7),
+                _ => None,
+            };
+            if let Some(days) = days {
+                return Some((today + Duration::days(days), 3));
+            }
+        }
+    }
+
+    // "next friday" is the first Friday after today; plain "friday" may be today
+    if word == "next" {
+        let day = next
+            .as_deref()
+            .and_then(|w| names.weekday(w).or_else(|| names.weekday_abbreviation(w)));
+        if let Some(day) = day {
+            return Some((next_weekday(today, day, 1), 2));
+        }
+        if next.as_deref() == Some("week") {
+            let days = 7 - locale.days_since_week_start(today.weekday());
+            return Some((today + Duration::days(days), 2));
+        }
+    }
+    // "Fri." or "Fri 20 Oct", but not the "sun" in "Picnic in the sun"
+    let abbreviation_marked = words[i].ends_with('.')
+        || (i > 0 && names.is_date_context(words[i - 1]))
+        || words.get(i + 1).is_some_and(|w| names.is_date_context(w));
+    let weekday = names
+        .weekday(&word)
+        .or_else(|| names.weekday_abbreviation(&word).filter(|_| abbreviation_marked));
+    if let Some(day) = weekday {
+        // "Friday 20 Oct" names the date twice; the explicit date wins
+        let explicit = words
+            .get(i + 1)
+            .filter(|w| names.is_date_part(w))
+            .and_then(|_| match_date(words, i + 1, today, locale, names));
+        if let Some((date, n)) = explicit {
+            return Some((date, n + 1));
+        }
+        return Some((next_weekday(today, day, 0), 1));
+    }
+
+    if let Some(date) = parse_numeric_date(&word, today, locale) {
+        return Some((date, 1));
+    }
+
+    // "oct 20", "october 20th 2027"
+    if let Some(month) = names.month(&word) {
+        if let Some(day) = next.as_deref().and_then(parse_day_number) {
+            let year = next2.as_deref().and_then(parse_year);
+            if let Some(date) = resolve_date(today, year, month, day) {
+                return Some((date, if year.is_some() { 3 } else { 2 }));
+            }
+        }
+    }
+
+    // "20 oct", "20th of october 2027"
+    if let Some(day) = parse_day_number(&word) {
+        let (month_word, offset) = if next.as_deref() == Some("of") {
+            (next2.clone(), 3)
+        } else {
+            (next.clone(), 2)
+        };
+        if let Some(month) = month_word.as_deref().and_then(|w| names.month(w)) {
+            let year = words.get(i + offset).map(|w| normalize(w)).and_then(|w| parse_year(&w));
+            if let Some(date) = resolve_date(today, year, month, day) {
+                return Some((date, if year.is_some() { offset + 1 } else { offset }));
+            }
+        }
+    }
+
+    None
+}
+
+/// The first `day` at least `min_days` after `from`
+fn next_weekday(from: NaiveDate, day: Weekday, min_days: i64) -> NaiveDate {
+    let ahead = (day.num_days_from_monday() as i64 - from.weekday().num_days_from_monday() as i64).rem_euclid(7);
+    let ahead = if ahead < min_days { ahead + 7 } else { ahead };
+    from + Duration::days(ahead)
+}
+
+/// ISO "2026-10-20", dotted "20.10." / "20.10.2026", or slashed "10/20" (month first for en_US)
+fn parse_numeric_date(word: &str, today: NaiveDate, locale: &LocaleSettings) -> Option<NaiveDate> {
+    if let Ok(date) = NaiveDate::parse_from_str(word, "%Y-%m-%d") {
+        return Some(date);
+    }
+
+    let (parts, month_first): (Vec<&str>, bool) = if word.contains('.') {
+        (word.split('.').filter(|p| !p.is_empty()).collect(), false)
+    } else if word.contains('/') {
+        (word.split('/').collect(), locale.language == "en_US")
+    } else {
+        return None;
+    };
+
+    if parts.len() < 2 || parts.len() > 3 || !parts.iter().all(|p| p.chars().all(|c| c.is_ascii_digit())) {
+        return None;
+    }
+
+    let a: u32 = parts[0].parse().ok()?;
+    let b: u32 = parts[1].parse().ok()?;
+    let (day, month) = if month_first { (b, a) } else { (a, b) };
+    let year = parts.get(2).and_then(|y| parse_year(y));
+    if parts.len() == 3 && year.is_none() {
+        return None;
+    }
+
+    resolve_date(today, year, month, day)
+}
+
+/// Dates without a year refer to the next occurrence, so "Jan 5" in December means next year
+fn resolve_date(today: NaiveDate, year: Option<i32>, month: u32, day: u32) -> Option<NaiveDate> {
+    match year {
+        Some(year) => NaiveDate::from_ymd_opt(year, month, day),
+        None => {
+            let date = NaiveDate::from_ymd_opt(today.year(), month, day)?;
+            if date < today {
+                NaiveDate::from_ymd_opt(today.year() + 1, month, day)
+            } else {
+                Some(date)
+            }
+        }
+    }
+}
+
+fn parse_day_number(word: &str) -> Option<u32> {
+    let digits = word
+        .trim_end_matches("st")
+        .trim_end_matches("nd")
+        .trim_end_matches("rd")
+        .trim_end_matches("th");
+    let day: u32 = digits.parse().ok()?;
+    (1..=31).contains(&day).then_some(day)
+}
+
+fn parse_year(word: &str) -> Option<i32> {
+    if word.len() == 4 && word.chars().all(|c| c.is_ascii_digit()) {
+        return word.parse().ok();
+    }
+    if word.len() == 2 && word.chars().all(|c| c.is_ascii_digit()) {
+        return word.parse::<i32>().ok().map(|y| 2000 + y);
+    }
+    None
+}
+
+/// "am" or "pm" if the word carries one
+fn meridiem(word: &str) -> Option<&'static str> {
+    if word.ends_with("am") || word.ends_with("a.m") {
+        Some("am")
+    } else if word.ends_with("pm") || word.ends_with("p.m") {
+        Some("pm")
+    } else {
+        None
+    }
+}
+
+fn has_marker(word: &str) -> bool {
+    meridiem(word).is_some() || word == "noon" || word == "midnight"
+}
+
+/// Parses a clock time. Bare hours ("9") are only accepted when `explicit` is set,
+/// so numbers in titles ("Top 10") are left alone.
+fn parse_clock(word: &str, explicit: bool) -> Option<NaiveTime> {
+    match word {
+        "noon" => return NaiveTime::from_hms_opt(12, 0, 0),
+        "midnight" => return NaiveTime::from_hms_opt(0, 0, 0),
+        _ => {}
+    }
+
+    let (digits, marker) = if let Some(d) = word.strip_suffix("am") {
+        (d.trim_end_matches('.'), Some(false))
+    } else if let Some(d) = word.strip_suffix("a.m") {
+        (d, Some(false))
+    } else if let Some(d) = word.strip_suffix("pm") {
+        (d.trim_end_matches('.'), Some(true))
+    } else if let Some(d) = word.strip_suffix("p.m") {
+        (d, Some(true))
+    } else {
+        (word, None)
+    };
+
+    let (hour, minute) = match digits.split_once([':', '.']) {
+        Some((h, m)) => {
+            if m.len() != 2 {
+                return None;
+            }
+            (h.parse::<u32>().ok()?, m.parse::<u32>().ok()?)
+        }
+        None => {
+            if marker.is_none() && !explicit {
+                return None;
+            }
+            (digits.parse::<u32>().ok()?, 0)
+        }
+    };
+
+    let hour = match marker {
+        Some(pm) => {
+            if !(1..=12).contains(&hour) {
+                return None;
+            }
+            match (pm, hour) {
+                (false, 12) => 0,
+                (true, 12) => 12,
+                (true, h) => h + 12,
+                (false, h) => h,
+            }
+        }
+        None => hour,
+    };
+
+    NaiveTime::from_hms_opt(hour, minute, 0)
+}
+
+/// Without am/pm on a 12-hour locale, small hours are read as afternoon ("at 3" is 15:00)
+fn apply_12h_guess(time: NaiveTime, word: &str, locale: &LocaleSettings) -> NaiveTime {
+    use chrono::Timelike;
+    if locale.use_24h || has_marker(word) || !(1..=7).contains(&time.hour()) {
+        return time;
+    }
+    time + Duration::hours(12)
+}
+
+fn parse_clock_range(word: &str, locale: &LocaleSettings) -> Option<(NaiveTime, NaiveTime)> {
+    // ISO dates also contain dashes
+    if word.matches('-').count() != 1 && !word.contains('–') {
+        return None;
+    }
+    let (a, b) = word.split_once(['-', '–'])?;
+    if a.is_empty() || b.is_empty() {
+        return None;
+    }
+    parse_clock_pair(a, b, true, locale)
+}
+
+fn parse_clock_pair(a: &str, b: &str, explicit: bool, locale: &LocaleSettings) -> Option<(NaiveTime, NaiveTime)> {
+    let end = parse_clock(b, explicit)?;
+    let end = apply_12h_guess(end, b, locale);
+
+    // "9-10am" and "11-1pm": the start borrows the end's marker, picking the reading
+    // that keeps the start before the end
+    if let (false, Some(marker)) = (has_marker(a), meridiem(b)) {
+        let am = parse_clock(&format!("{}am", a), true);
+        let pm = parse_clock(&format!("{}pm", a), true);
+        let (same, other) = if marker == "pm" { (pm, am) } else { (am, pm) };
+        let start = match same {
+            Some(t) if t < end => Some(t),
+            _ => other.or(same),
+        }?;
+        return Some((start, end));
+    }
+
+    let start = parse_clock(a, explicit)?;
+    let start = apply_12h_guess(start, a, locale);
+    Some((start, end))
+}
+
+/// "2h", "90 min", "1.5 hours", "30m"
+fn match_duration(words: &[&str]) -> Option<(Duration, usize)> {
+    let first = normalize(words.first()?);
+
+    let (number, unit, n) = match first.find(|c: char| !(c.is_ascii_digit() || c == '.')) {
+        Some(0) => return None,
+        Some(split) => (first[..split].to_string(), first[split..].to_string(), 1),
+        None => (first.clone(), words.get(1).map(|w| normalize(w))?, 2),
+    };
+    let number: f64 = number.parse().ok()?;
+
+    let minutes = match unit.as_str() {
+        "h" | "hr" | "hrs" | "hour" | "hours" => number * 60.0,
+        "m" | "min" | "mins" | "minute" | "minutes" => number,
+        _ => return None,
+    };
+    Some((Duration::minutes(minutes.round() as i64), n))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn locale(language: &str, use_24h: bool) -> LocaleSettings {
+        LocaleSettings {
+            language: language.to_string(),
+            use_24h,
+            timezone: "Europe/Oslo".to_string(),
+            ..LocaleSettings::default()
+        }
+    }
+
+    /// Wednesday 2026-10-14, 12:00
+    fn now() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 10, 14).unwrap().and_hms_opt(12, 0, 0).unwrap()
+    }
+
+    /// Input, then the expected title, start, end and location. Starts without a time are all-day.
+    fn check(locale: &LocaleSettings, cases: &[(&str, &str, &str, &str, &str)]) {
+        for (input, title, start, end, location) in cases {
+            let draft = parse(input, now(), locale).unwrap_or_else(|e| panic!("{}: {}", input, e));
+            assert_eq!(
+                (draft.title.as_str(), draft.start.as_str(), draft.end.as_str(), draft.location.as_str()),
+                (*title, *start, *end, *location),
+                "{}",
+                input
+            );
+            assert_eq!(draft.is_all_day, !start.contains('T'), "{}", input);
+        }
+    }
+
+    #[test]
+    fn dates() {
+        check(
+            &locale("en_US", true),
+            &[
+                ("Dentist", "Dentist", "2026-10-14", "2026-10-14", ""),
+                ("Dentist tomorrow", "Dentist", "2026-10-15", "2026-10-15", ""),
+                ("Dentist day after tomorrow", "Dentist", "2026-10-16", "2026-10-16", ""),
+                ("Dentist in 3 days", "Dentist", "2026-10-17", "2026-10-17", ""),
+                ("Dentist in 2 weeks", "Dentist", "2026-10-28", "2026-10-28", ""),
+                ("Dentist on Friday", "Dentist", "2026-10-16", "2026-10-16", ""),
+                ("Dentist wednesday", "Dentist", "2026-10-14", "2026-10-14", ""),
+                ("Dentist next wednesday", "Dentist", "2026-10-21", "2026-10-21", ""),
+                ("Dentist next week", "Dentist", "2026-10-19", "2026-10-19", ""),
+                ("Dentist 2026-11-02", "Dentist", "2026-11-02", "2026-11-02", ""),
+                ("Dentist 24.10.", "Dentist", "2026-10-24", "2026-10-24", ""),
+                ("Dentist 10/20", "Dentist", "2026-10-20", "2026-10-20", ""),
+                ("Dentist oct 20", "Dentist", "2026-10-20", "2026-10-20", ""),
+                ("Dentist october 20th 2027", "Dentist", "2027-10-20", "2027-10-20", ""),
+                ("Dentist 20th of october", "Dentist", "2026-10-20", "2026-10-20", ""),
+                ("Dentist Jan 5", "Dentist", "2027-01-05", "2027-01-05", ""),
+                ("Dentist Friday 20 Nov", "Dentist", "2026-11-20", "2026-11-20", ""),
+            ],
+        );
+    }
+
+    #[test]
+    fn weekday_abbreviations() {
+        check(
+            &locale("en_US", true),
+            &[
+                ("Picnic in the sun tomorrow", "Picnic in the sun", "2026-10-15", "2026-10-15", ""),
+                ("Brunch sun.", "Brunch", "2026-10-18", "2026-10-18", ""),
+                ("Brunch next sun", "Brunch", "2026-10-18", "2026-10-18", ""),
+                ("Gig fri 20 nov", "Gig", "2026-11-20", "2026-11-20", ""),
+                ("Gig sat 21:00", "Gig", "2026-10-17T21:00:00+02:00", "2026-10-17T22:00:00+02:00", ""),
+            ],
+        );
+        check(
+            &locale("de_DE", true),
+            &[
+                ("Things to do tomorrow", "Things to do", "2026-10-15", "2026-10-15", ""),
+                ("Treffen Do. 10:00", "Treffen", "2026-10-15T10:00:00+02:00", "2026-10-15T11:00:00+02:00", ""),
+                ("Treffen Donnerstag", "Treffen", "2026-10-15", "2026-10-15", ""),
+            ],
+        );
+    }
+
+    #[test]
+    fn times() {
+        check(
+            &locale("en_US", true),
+            &[
+                ("Call at 15", "Call", "2026-10-14T15:00:00+02:00", "2026-10-14T16:00:00+02:00", ""),
+                ("Call 15:30", "Call", "2026-10-14T15:30:00+02:00", "2026-10-14T16:30:00+02:00", ""),
+                ("Call at 12.10", "Call", "2026-10-14T12:10:00+02:00", "2026-10-14T13:10:00+02:00", ""),
+                ("Call 3pm", "Call", "2026-10-14T15:00:00+02:00", "2026-10-14T16:00:00+02:00", ""),
+                ("Call noon", "Call", "2026-10-14T12:00:00+02:00", "2026-10-14T13:00:00+02:00", ""),
+                ("Call at 9 for 90 min", "Call", "2026-10-14T09:00:00+02:00", "2026-10-14T10:30:00+02:00", ""),
+                ("Call at 9 for 2h", "Call", "2026-10-14T09:00:00+02:00", "2026-10-14T11:00:00+02:00", ""),
+                ("Read Top 10 list", "Read Top 10 list", "2026-10-14", "2026-10-14", ""),
+                ("Offsite tomorrow all day", "Offsite", "2026-10-15", "2026-10-15", ""),
+                // Daylight saving time ends on 2026-10-25
+                ("Call 2026-10-26 9:00", "Call", "2026-10-26T09:00:00+01:00", "2026-10-26T10:00:00+01:00", ""),
+            ],
+        );
+        check(
+            &locale("en_US", false),
+            &[
+                ("Tea at 3", "Tea", "2026-10-14T15:00:00+02:00", "2026-10-14T16:00:00+02:00", ""),
+                ("Tea at 3am", "Tea", "2026-10-14T03:00:00+02:00", "2026-10-14T04:00:00+02:00", ""),
+                ("Tea at 12am", "Tea", "2026-10-14T00:00:00+02:00", "2026-10-14T01:00:00+02:00", ""),
+            ],
+        );
+    }
+
+    #[test]
+    fn ranges() {
+        check(
+            &locale("en_US", true),
+            &[
+                ("Lunch 12-13", "Lunch", "2026-10-14T12:00:00+02:00", "2026-10-14T13:00:00+02:00", ""),
+                ("Lunch at 12 - 13", "Lunch", "2026-10-14T12:00:00+02:00", "2026-10-14T13:00:00+02:00", ""),
+                ("Read pages 10 - 20", "Read pages 10 - 20", "2026-10-14", "2026-10-14", ""),
+                ("Lunch 9:30-10:15", "Lunch", "2026-10-14T09:30:00+02:00", "2026-10-14T10:15:00+02:00", ""),
+                ("Lunch from 9 to 11", "Lunch", "2026-10-14T09:00:00+02:00", "2026-10-14T11:00:00+02:00", ""),
+                ("Lunch 9-10am", "Lunch", "2026-10-14T09:00:00+02:00", "2026-10-14T10:00:00+02:00", ""),
+                ("Lunch 11-1pm", "Lunch", "2026-10-14T11:00:00+02:00", "2026-10-14T13:00:00+02:00", ""),
+                ("Party 23-1", "Party", "2026-10-14T23:00:00+02:00", "2026-10-15T01:00:00+02:00", ""),
+            ],
+        );
+    }
+
+    #[test]
+    fn locations() {
+        check(
+            &locale("en_US", true),
+            &[
+                (
+                    "Lunch with Sam tomorrow 12-13 @Cafe",
+                    "Lunch with Sam",
+                    "2026-10-15T12:00:00+02:00",
+                    "2026-10-15T13:00:00+02:00",
+                    "Cafe",
+                ),
+                ("Lunch @ Cafe Central", "Lunch", "2026-10-14", "2026-10-14", "Cafe Central"),
+                ("Lunch @3pm", "Lunch", "2026-10-14T15:00:00+02:00", "2026-10-14T16:00:00+02:00", ""),
+                ("Mail bob@example.com", "Mail bob@example.com", "2026-10-14", "2026-10-14", ""),
+            ],
+        );
+    }
+
+    #[test]
+    fn locales() {
+        check(
+            &locale("nb_NO", true),
+            &[
+                ("Møte fredag kl 14", "Møte", "2026-10-16T14:00:00+02:00", "2026-10-16T15:00:00+02:00", ""),
+                ("Møte 20. oktober", "Møte", "2026-10-20", "2026-10-20", ""),
+                ("Møte 10/11", "Møte", "2026-11-10", "2026-11-10", ""),
+            ],
+        );
+        check(
+            &locale("de_DE", true),
+            &[("Termin Montag 9:00", "Termin", "2026-10-19T09:00:00+02:00", "2026-10-19T10:00:00+02:00", "")],
+        );
+    }
+
+    #[test]
+    fn needs_a_title() {
+        assert!(parse("tomorrow 12-13", now(), &locale("en_US", true)).is_err());
+        assert!(parse("@Cafe", now(), &locale("en_US", true)).is_err());
+    }
+}
@@ -0,0 +1,654 @@
+//! RFC 5545 recurrence rules: parsing, human-readable summaries and expansion into occurrences.
+
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, Weekday};
+
+const WEEKDAYS: [Weekday; 5] = [Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri];
+
+// Upper bound on rule periods walked while expanding, so a bad rule can't loop forever
+const MAX_PERIODS: i64 = 100_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+#[derive(Debug, Clone)]
+pub struct RRule {
+    pub freq: Frequency,
+    pub interval: u32,
+    /// BYDAY entries; the number is the ordinal for monthly/yearly rules ("2TU", "-1FR")
+    pub by_day: Vec<(Option<i32>, Weekday)>,
+    pub by_month_day: Vec<i32>,
+    pub by_month: Vec<u32>,
+    pub by_set_pos: Vec<i32>,
+    pub count: Option<u32>,
+    /// UNTIL as wall-clock time; date-only values are taken as the end of that day
+    pub until: Option<NaiveDateTime>,
+    /// Whether UNTIL was given in UTC ("...Z") rather than in the event's own zone
+    pub until_utc: bool,
+}
+
+impl RRule {
+    /// Parses a rule with or without the leading "RRULE:"
+    pub fn parse(line: &str) -> Result<RRule, String> {
+        let body = line.strip_prefix("RRULE:").unwrap_or(line);
+
+        let mut freq = None;
+        let mut rule = RRule {
+            freq: Frequency::Daily,
+            interval: 1,
+            by_day: Vec::new(),
+            by_month_day: Vec::new(),
+            by_month: Vec::new(),
+            by_set_pos: Vec::new(),
+            count: None,
+            until: None,
+            until_utc: false,
+        };
+
+        for part in body.split(';').filter(|p| !p.is_empty()) {
+            let (key, value) = part
+                .split_once('=')
+                .ok_or_else(|| format!("Invalid RRULE part '{}'", part))?;
+            match key.to_ascii_uppercase().as_str() {
+                "FREQ" => {
+                    freq = Some(match value.to_ascii_uppercase().as_str() {
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        "YEARLY" => Frequency::Yearly,
+                        other => return Err(format!("Unsupported FREQ '{}'", other)),
+                    })
+                }
+                "INTERVAL" => {
+                    rule.interval = value
+                        .parse()
+                        .map_err(|_| format!("Invalid INTERVAL '{}'", value))?;
+                }
+                "COUNT" => {
+                    rule.count = Some(value.parse().map_err(|_| format!("Invalid COUNT '{}'", value))?);
+                }
+                "UNTIL" => {
+                    rule.until = Some(parse_until(value)?);
+                    rule.until_utc = value.ends_with('Z');
+                }
+                "BYDAY" => {
+                    for day in value.split(',') {
+                        rule.by_day.push(parse_by_day(day)?);
+                    }
+                }
+                "BYMONTHDAY" => {
+                    for day in value.split(',') {
+                        rule.by_month_day
+                            .push(day.parse().map_err(|_| format!("Invalid BYMONTHDAY '{}'", day))?);
+                    }
+                }
+                "BYMONTH" => {
+                    for month in value.split(',') {
+                        rule.by_month
+                            .push(month.parse().map_err(|_| format!("Invalid BYMONTH '{}'", month))?);
+                    }
+                }
+                "BYSETPOS" => {
+                    for pos in value.split(',') {
+                        rule.by_set_pos
+                            .push(pos.parse().map_err(|_| format!("Invalid BYSETPOS '{}'", pos))?);
+                    }
+                }
+                // WKST, BYHOUR etc. are rare in calendar data and ignored
+                _ => {}
+            }
+        }
+
+        rule.freq = freq.ok_or_else(|| "RRULE without FREQ".to_string())?;
+        rule.interval = rule.interval.max(1);
+        Ok(rule)
+    }
+
+    /// Human-readable summary such as "Every weekday" or "Monthly on the 2nd Tuesday".
+    /// `start` is the first occurrence, which supplies the defaults the rule leaves out. The whole
+    /// summary is in English, names included, so it never mixes languages.
+    pub fn summary(&self, start: NaiveDate) -> String {
+        let mut text = match self.freq {
+            // Only on the BYDAY days, which for a daily rule reads like a weekly one
+            Frequency::Daily if !self.by_day.is_empty() => {
+                if self.interval == 1 {
+                    self.weekly_summary(start)
+                } else {
+                    let base = every(self.interval, "Daily", "day", "days");
+                    format!("{} on {}", base, self.weekday_names())
+                }
+            }
+            Frequency::Daily => every(self.interval, "Daily", "day", "days"),
+            Frequency::Weekly => self.weekly_summary(start),
+            Frequency::Monthly => {
+                let base = every(self.interval, "Monthly", "month", "months");
+                format!("{} {}", base, self.month_position(start))
+            }
+            Frequency::Yearly => {
+                let base = every(self.interval, "Yearly", "year", "years");
+                let month = self.by_month.first().copied().unwrap_or(start.month());
+                let month_name = NaiveDate::from_ymd_opt(2000, month, 1)
+                    .map(|d| d.format("%B").to_string())
+                    .unwrap_or_default();
+                match self.positional() {
+                    Some(position) => format!("{} on {} in {}", base, position, month_name),
+                    None => {
+                        let day = self.by_month_day.first().copied().unwrap_or(start.day() as i32);
+                        format!("{} on {} {}", base, month_name, day)
+                    }
+                }
+            }
+        };
+
+        if let Some(count) = self.count {
+            text.push_str(&format!(", {} times", count));
+        } else if let Some(until) = self.until {
+            text.push_str(&format!(", until {}", until.date().format("%-d %B %Y")));
+        }
+
+        text
+    }
+
+    fn weekly_summary(&self, start: NaiveDate) -> String {
+        let mut days: Vec<Weekday> = self.by_day.iter().map(|(_, d)| *d).collect();
+        if days.is_empty() {
+            days.push(start.weekday());
+        }
+        days.sort_by_key(|d| d.num_days_from_monday());
+        days.dedup();
+
+        if self.interval == 1 && days == WEEKDAYS {
+            return "Every weekday".to_string();
+        }
+        if self.interval == 1 && days.len() == 7 {
+            return "Daily".to_string();
+        }
+
+        let base = every(self.interval, "Weekly", "week", "weeks");
+        let names: Vec<String> = days.iter().map(|d| weekday_name(*d)).collect();
+        format!("{} on {}", base, join_names(&names))
+    }
+
+    /// The BYDAY days in week order, e.g. "Monday and Wednesday"
+    fn weekday_names(&self) -> String {
+        let mut days: Vec<Weekday> = self.by_day.iter().map(|(_, d)| *d).collect();
+        days.sort_by_key(|d| d.num_days_from_monday());
+        days.dedup();
+        join_names(&days.iter().map(|d| weekday_name(*d)).collect::<Vec<_>>())
+    }
+
+    /// "the 2nd Tuesday", or with BYSETPOS picking from the BYDAY days "the last weekday";
+    /// `None` when the rule picks days of the month instead
+    fn positional(&self) -> Option<String> {
+        if !self.by_set_pos.is_empty() && !self.by_day.is_empty() {
+            let mut days: Vec<Weekday> = self.by_day.iter().map(|(_, d)| *d).collect();
+            days.sort_by_key(|d| d.num_days_from_monday());
+            days.dedup();
+            let kind = match days.as_slice() {
+                d if d == WEEKDAYS => "weekday".to_string(),
+                [Weekday::Sat, Weekday::Sun] => "weekend day".to_string(),
+                d if d.len() == 7 => "day".to_string(),
+                d => d.iter().map(|d| weekday_name(*d)).collect::<Vec<_>>().join(" or "),
+            };
+            let positions: Vec<String> = self.by_set_pos.iter().map(|n| ordinal_word(*n)).collect();
+            return Some(format!("the {} {}", join_names(&positions), kind));
+        }
+
+        let positional: Vec<String> = self
+            .by_day
+            .iter()
+            .filter_map(|(n, d)| n.map(|n| format!("the {} {}", ordinal_word(n), weekday_name(*d))))
+            .collect();
+        (!positional.is_empty()).then(|| join_names(&positional))
+    }
+
+    /// "on the 2nd Tuesday", "on the last weekday", "on the last day", "on day 15"
+    fn month_position(&self, start: NaiveDate) -> String {
+        if let Some(position) = self.positional() {
+            return format!("on {}", position);
+        }
+
+        let days: Vec<String> = if self.by_month_day.is_empty() {
+            vec![start.day().to_string()]
+        } else {
+            self.by_month_day
+                .iter()
+                .map(|d| if *d == -1 { "the last day".to_string() } else { d.to_string() })
+                .collect()
+        };
+        if days.len() == 1 && days[0] == "the last day" {
+            "on the last day".to_string()
+        } else if days.len() == 1 {
+            format!("on day {}", days[0])
+        } else {
+            format!("on days {}", join_names(&days))
+        }
+    }
+}
+
+impl RRule {
+    /// Occurrence start times in wall-clock time from `from` on, beginning with `start` (DTSTART)
+    /// if it's in range and ending at UNTIL, COUNT or the first occurrence after `limit`,
+    /// whichever comes first
+    pub fn occurrences(&self, start: NaiveDateTime, from: NaiveDateTime, limit: NaiveDateTime) -> Vec<NaiveDateTime> {
+        // DTSTART is always the first instance, even when the rule wouldn't produce it
+        let mut result = Vec::new();
+        if start >= from {
+            result.push(start);
+        }
+        if start > limit {
+            return result;
+        }
+
+        // Periods before `from` are skipped, unless they have to be counted for COUNT, which
+        // keeps the walk short by itself
+        let first_period = match self.count {
+            Some(_) => 0,
+            None => self.periods_between(start.date(), from.date()).saturating_sub(1).max(0),
+        };
+        let mut counted = 1;
+        for period in first_period..first_period.saturating_add(MAX_PERIODS) {
+            let Some(mut days) = self.period_days(start.date(), period) else {
+                break;
+            };
+            days.sort();
+            days.dedup();
+
+            for day in self.apply_set_pos(days) {
+                let occurrence = day.and_time(start.time());
+                if occurrence <= start {
+                    continue;
+                }
+                if occurrence > limit
+                    || self.until.is_some_and(|until| occurrence > until)
+                    || self.count.is_some_and(|count| counted >= count)
+                {
+                    return result;
+                }
+                counted += 1;
+                if occurrence >= from {
+                    result.push(occurrence);
+                }
+            }
+        }
+        result
+    }
+
+    /// Whole periods from the one holding `start` to the one holding `day`
+    fn periods_between(&self, start: NaiveDate, day: NaiveDate) -> i64 {
+        let units = match self.freq {
+            Frequency::Daily => (day - start).num_days(),
+            Frequency::Weekly => {
+                let monday = |d: NaiveDate| d - Duration::days(d.weekday().num_days_from_monday() as i64);
+                (monday(day) - monday(start)).num_weeks()
+            }
+            Frequency::Monthly => {
+                (day.year() as i64 * 12 + day.month0() as i64) - (start.year() as i64 * 12 + start.month0() as i64)
+            }
+            Frequency::Yearly => (day.year() - start.year()) as i64,
+        };
+        units.div_euclid(self.interval as i64)
+    }
+
+    /// Candidate days for the `period`th period after the one holding `start`, or `None` once
+    /// the dates run out of range
+    fn period_days(&self, start: NaiveDate, period: i64) -> Option<Vec<NaiveDate>> {
+        let step = period.checked_mul(self.interval as i64)?;
+        let days = match self.freq {
+            Frequency::Daily => {
+                let day = start.checked_add_signed(Duration::days(step))?;
+                let matches = self.in_months(day)
+                    && (self.by_month_day.is_empty() || self.month_day_matches(day))
+                    && (self.by_day.is_empty() || self.by_day.iter().any(|(_, d)| *d == day.weekday()));
+                if matches {
+                    vec![day]
+                } else {
+                    Vec::new()
+                }
+            }
+            Frequency::Weekly => {
+                let week_start = start
+                    .checked_sub_signed(Duration::days(start.weekday().num_days_from_monday() as i64))?
+                    .checked_add_signed(Duration::weeks(step))?;
+                let weekdays: Vec<Weekday> = if self.by_day.is_empty() {
+                    vec![start.weekday()]
+                } else {
+                    self.by_day.iter().map(|(_, d)| *d).collect()
+                };
+                weekdays
+                    .into_iter()
+                    .filter_map(|d| week_start.checked_add_signed(Duration::days(d.num_days_from_monday() as i64)))
+                    .filter(|day| self.in_months(*day))
+                    .collect()
+            }
+            Frequency::Monthly => {
+                let months = (start.year() as i64) * 12 + start.month0() as i64 + step;
+                let year = i32::try_from(months.div_euclid(12)).ok()?;
+                let month = months.rem_euclid(12) as u32 + 1;
+                NaiveDate::from_ymd_opt(year, month, 1)?;
+                if !self.by_month.is_empty() && !self.by_month.contains(&month) {
+                    Vec::new()
+                } else {
+                    self.month_days(year, month, start.day())
+                }
+            }
+            Frequency::Yearly => {
+                let year = i32::try_from(start.year() as i64 + step).ok()?;
+                NaiveDate::from_ymd_opt(year, 1, 1)?;
+                if self.by_month.is_empty() && !self.by_day.is_empty() && self.by_month_day.is_empty() {
+                    // BYDAY ordinals count within the year when there is no BYMONTH
+                    self.year_weekdays(year)
+                } else {
+                    let months = if self.by_month.is_empty() {
+                        vec![start.month()]
+                    } else {
+                        self.by_month.clone()
+                    };
+                    months
+                        .into_iter()
+                        .flat_map(|month| self.month_days(year, month, start.day()))
+                        .collect()
+                }
+            }
+        };
+        Some(days)
+    }
+
+    fn in_months(&self, day: NaiveDate) -> bool {
+        self.by_month.is_empty() || self.by_month.contains(&day.month())
+    }
+
+    fn month_day_matches(&self, day: NaiveDate) -> bool {
+        let last = days_in_month(day.year(), day.month());
+        self.by_month_day
+            .iter()
+            .any(|d| resolve_month_day(*d, last) == Some(day.day()))
+    }
+
+    fn month_days(&self, year: i32, month: u32, default_day: u32) -> Vec<NaiveDate> {
+        let last = days_in_month(year, month);
+        let from_month_days: Vec<u32> = self
+            .by_month_day
+            .iter()
+            .filter_map(|d| resolve_month_day(*d, last))
+            .collect();
+
+        let days: Vec<u32> = if !self.by_day.is_empty() {
+            let weekday_days: Vec<u32> = self
+                .by_day
+                .iter()
+                .flat_map(|(n, weekday)| {
+                    let matching: Vec<u32> = (1..=last)
+                        .filter(|d| {
+                            NaiveDate::from_ymd_opt(year, month, *d).is_some_and(|date| date.weekday() == *weekday)
+                        })
+                        .collect();
+                    pick_ordinal(matching, *n)
+                })
+                .collect();
+            if from_month_days.is_empty() {
+                weekday_days
+            } else {
+                weekday_days.into_iter().filter(|d| from_month_days.contains(d)).collect()
+            }
+        } else if !from_month_days.is_empty() {
+            from_month_days
+        } else if default_day <= last {
+            vec![default_day]
+        } else {
+            // The 31st doesn't exist in every month; those months are skipped
+            Vec::new()
+        };
+
+        days.into_iter()
+            .filter_map(|d| NaiveDate::from_ymd_opt(year, month, d))
+            .collect()
+    }
+
+    fn year_weekdays(&self, year: i32) -> Vec<NaiveDate> {
+        let Some(first) = NaiveDate::from_ymd_opt(year, 1, 1) else {
+            return Vec::new();
+        };
+        let all_days: Vec<NaiveDate> = first.iter_days().take_while(|d| d.year() == year).collect();
+        self.by_day
+            .iter()
+            .flat_map(|(n, weekday)| {
+                let matching: Vec<NaiveDate> = all_days.iter().copied().filter(|d| d.weekday() == *weekday).collect();
+                pick_ordinal(matching, *n)
+            })
+            .collect()
+    }
+
+    fn apply_set_pos(&self, days: Vec<NaiveDate>) -> Vec<NaiveDate> {
+        if self.by_set_pos.is_empty() {
+            return days;
+        }
+        let mut picked: Vec<NaiveDate> = self
+            .by_set_pos
+            .iter()
+            .filter_map(|pos| pick_ordinal(days.clone(), Some(*pos)).into_iter().next())
+            .collect();
+        picked.sort();
+        picked
+    }
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    let (next_year, next_month) = if month == 12 { (year + 1, 1) } else { (year, month + 1) };
+    NaiveDate::from_ymd_opt(next_year, next_month, 1)
+        .and_then(|d| d.pred_opt())
+        .map(|d| d.day())
+        .unwrap_or(28)
+}
+
+/// BYMONTHDAY value to a day of the month; negative values count from the end
+fn resolve_month_day(day: i32, last: u32) -> Option<u32> {
+    let resolved = if day < 0 { last as i32 + 1 + day } else { day };
+    (1..=last as i32).contains(&resolved).then_some(resolved as u32)
+}
+
+/// All items, or just the nth (1-based, negative from the end) when an ordinal is given
+fn pick_ordinal<T>(mut items: Vec<T>, n: Option<i32>) -> Vec<T> {
+    match n {
+        None => items,
+        Some(n) if n > 0 && (n as usize) <= items.len() => vec![items.swap_remove(n as usize - 1)],
+        Some(n) if n < 0 && n.unsigned_abs() as usize <= items.len() => {
+            let index = items.len() - n.unsigned_abs() as usize;
+            vec![items.swap_remove(index)]
+        }
+        Some(_) => Vec::new(),
+    }
+}
+
+/// Summary for the first RRULE in a recurrence list, or an empty string if there is none
+pub fn summarize(recurrence: &[String], start: NaiveDate) -> String {
+    recurrence
+        .iter()
+        .find(|line| line.starts_with("RRULE:"))
+        .and_then(|line| RRule::parse(line).ok())
+        .map(|rule| rule.summary(start))
+        .unwrap_or_default()
+}
+
+fn every(interval: u32, single: &str, unit: &str, units: &str) -> String {
+    match interval {
+        1 => single.to_string(),
+        2 if unit == "day" => "Every other day".to_string(),
+        2 => format!("Every other {}", unit),
+        n => format!("Every {} {}", n, units),
+    }
+}
+
+fn parse_until(value: &str) -> Result<NaiveDateTime, String> {
+    let value = value.trim_end_matches('Z');
+    if let Ok(dt) = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S") {
+        return Ok(dt);
+    }
+    NaiveDate::parse_from_str(value, "%Y%m%d")
+        .map(|d| d.and_hms_opt(23, 59, 59).unwrap())
+        .map_err(|_| format!("Invalid UNTIL '{}'", value))
+}
+
+fn parse_by_day(value: &str) -> Result<(Option<i32>, Weekday), String> {
+    let value = value.trim();
+    if value.len() < 2 {
+        return Err(format!("Invalid BYDAY '{}'", value));
+    }
+    let (number, code) = value.split_at(value.len() - 2);
+    let day = match code.to_ascii_uppercase().as_str() {
+        "MO" => Weekday::Mon,
+        "TU" => Weekday::Tue,
+        "WE" => Weekday::Wed,
+        "TH" => Weekday::Thu,
+        "FR" => Weekday::Fri,
+        "SA" => Weekday::Sat,
+        "SU" => Weekday::Sun,
+        _ => return Err(format!("Invalid BYDAY '{}'", value)),
+    };
+    let number = if number.is_empty() {
+        None
+    } else {
+        Some(number.parse().map_err(|_| format!("Invalid BYDAY '{}'", value))?)
+    };
+    Ok((number, day))
+}
+
+fn weekday_name(day: Weekday) -> String {
+    NaiveDate::from_isoywd_opt(2024, 1, day)
+        .map(|d| d.format("%A").to_string())
+        .unwrap_or_default()
+}
+
+fn ordinal_word(n: i32) -> String {
+    match n {
+        -1 => "last".to_string(),
+        -2 => "second to last".to_string(),
+        n => {
+            let suffix = match (n % 10, n % 100) {
+                (1, r) if r != 11 => "st",
+                (2, r) if r != 12 => "nd",
+                (3, r) if r != 13 => "rd",
+                _ => "th",
+            };
+            format!("{}{}", n, suffix)
+        }
+    }
+}
+
+fn join_names(names: &[impl AsRef<str>]) -> String {
+    match names.len() {
+        0 => String::new(),
+        1 => names[0].as_ref().to_string(),
+        n => {
+            let head: Vec<&str> = names[..n - 1].iter().map(|s| s.as_ref()).collect();
+            format!("{} and {}", head.join(", "), names[n - 1].as_ref())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn at(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    fn dates(times: &[NaiveDateTime]) -> Vec<String> {
+        times.iter().map(|t| t.format("%Y-%m-%d").to_string()).collect()
+    }
+
+    #[test]
+    fn occurrences() {
+        // Friday, October 30th
+        let start = at("2026-10-30 09:00");
+        let limit = at("2028-12-31 00:00");
+        for (rule, expected) in [
+            ("FREQ=DAILY;COUNT=3", vec!["2026-10-30", "2026-10-31", "2026-11-01"]),
+            ("FREQ=DAILY;INTERVAL=2;UNTIL=20261105T090000", vec!["2026-10-30", "2026-11-01", "2026-11-03", "2026-11-05"]),
+            ("FREQ=DAILY;BYDAY=MO,WE;COUNT=4", vec!["2026-10-30", "2026-11-02", "2026-11-04", "2026-11-09"]),
+            ("FREQ=WEEKLY;BYDAY=TU,FR;COUNT=4", vec!["2026-10-30", "2026-11-03", "2026-11-06", "2026-11-10"]),
+            ("FREQ=MONTHLY;COUNT=4", vec!["2026-10-30", "2026-11-30", "2026-12-30", "2027-01-30"]),
+            // February has no 30th and is skipped
+            ("FREQ=MONTHLY;BYMONTHDAY=30;COUNT=5", vec!["2026-10-30", "2026-11-30", "2026-12-30", "2027-01-30", "2027-03-30"]),
+            ("FREQ=MONTHLY;BYDAY=2TU;COUNT=3", vec!["2026-10-30", "2026-11-10", "2026-12-08"]),
+            ("FREQ=MONTHLY;BYDAY=MO,TU,WE,TH,FR;BYSETPOS=-1;COUNT=3", vec!["2026-10-30", "2026-11-30", "2026-12-31"]),
+            ("FREQ=YEARLY;BYMONTH=3;BYDAY=-1SU;COUNT=3", vec!["2026-10-30", "2027-03-28", "2028-03-26"]),
+            ("FREQ=YEARLY;UNTIL=20271030", vec!["2026-10-30", "2027-10-30"]),
+        ] {
+            let times = RRule::parse(rule).unwrap().occurrences(start, start, limit);
+            assert_eq!(dates(&times), expected, "{}", rule);
+            assert!(times.iter().all(|t| t.time() == start.time()), "{}", rule);
+        }
+    }
+
+    #[test]
+    fn skips_ahead_to_the_window() {
+        let start = at("1990-01-01 08:00");
+        let (from, limit) = (at("2026-10-12 00:00"), at("2026-10-19 00:00"));
+        for rule in [
+            "FREQ=DAILY",
+            "FREQ=DAILY;INTERVAL=3",
+            "FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,TH",
+            "FREQ=MONTHLY;BYDAY=MO,TU,WE,TH,FR;BYSETPOS=-1",
+            "FREQ=YEARLY;BYMONTH=10;BYDAY=3WE",
+        ] {
+            let rule = RRule::parse(rule).unwrap();
+            let walked: Vec<NaiveDateTime> =
+                rule.occurrences(start, start, limit).into_iter().filter(|t| *t >= from).collect();
+            assert_eq!(rule.occurrences(start, from, limit), walked);
+        }
+
+        let daily = RRule::parse("FREQ=DAILY").unwrap().occurrences(start, from, limit);
+        assert_eq!(daily.first(), Some(&at("2026-10-12 08:00")));
+        assert_eq!(daily.len(), 7);
+
+        // COUNT still counts the occurrences before the window
+        let counted = RRule::parse("FREQ=DAILY;COUNT=13436").unwrap();
+        assert_eq!(dates(&counted.occurrences(start, from, limit)), ["2026-10-12", "2026-10-13", "2026-10-14"]);
+    }
+
+    #[test]
+    fn summaries() {
+        // Friday, October 30th
+        let start = date("2026-10-30");
+        for (rule, expected) in [
+            ("FREQ=DAILY", "Daily"),
+            ("FREQ=DAILY;INTERVAL=2", "Every other day"),
+            ("FREQ=DAILY;BYDAY=MO,WE", "Weekly on Monday and Wednesday"),
+            ("FREQ=DAILY;BYDAY=MO,TU,WE,TH,FR", "Every weekday"),
+            ("FREQ=DAILY;INTERVAL=3;BYDAY=SA", "Every 3 days on Saturday"),
+            ("FREQ=WEEKLY", "Weekly on Friday"),
+            ("FREQ=WEEKLY;INTERVAL=2;BYDAY=TU,TH", "Every other week on Tuesday and Thursday"),
+            ("FREQ=MONTHLY", "Monthly on day 30"),
+            ("FREQ=MONTHLY;BYMONTHDAY=-1", "Monthly on the last day"),
+            ("FREQ=MONTHLY;BYDAY=2TU", "Monthly on the 2nd Tuesday"),
+            ("FREQ=MONTHLY;BYDAY=MO,TU,WE,TH,FR;BYSETPOS=-1", "Monthly on the last weekday"),
+            ("FREQ=MONTHLY;BYDAY=SA,SU;BYSETPOS=1", "Monthly on the 1st weekend day"),
+            ("FREQ=MONTHLY;BYDAY=MO,FR;BYSETPOS=1,-1", "Monthly on the 1st and last Monday or Friday"),
+            ("FREQ=YEARLY", "Yearly on October 30"),
+            ("FREQ=YEARLY;BYMONTH=3;BYDAY=-1SU", "Yearly on the last Sunday in March"),
+            ("FREQ=WEEKLY;COUNT=4", "Weekly on Friday, 4 times"),
+            ("FREQ=WEEKLY;UNTIL=20261231T225959Z", "Weekly on Friday, until 31 December 2026"),
+            ("FREQ=YEARLY;UNTIL=20301231", "Yearly on October 30, until 31 December 2030"),
+        ] {
+            let summary = RRule::parse(rule).unwrap().summary(start);
+            assert_eq!(summary, expected, "{}", rule);
+        }
+    }
+
+    #[test]
+    fn summarizes_the_first_rrule() {
+        let recurrence = ["EXDATE:20261106".to_string(), "RRULE:FREQ=WEEKLY".to_string()];
+        assert_eq!(summarize(&recurrence, date("2026-10-30")), "Weekly on Friday");
+        assert_eq!(summarize(&[], date("2026-10-30")), "");
+    }
+}
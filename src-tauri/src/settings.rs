@@ -0,0 +1,359 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+use chrono::{DateTime, FixedOffset, Local, Locale, NaiveDate, NaiveDateTime, TimeZone, Weekday};
+use chrono_tz::Tz;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub locale: LocaleSettings,
+    pub events: EventSettings,
+    pub tasks: TaskSettings,
+    pub providers: ProviderSettings,
+    pub agenda: AgendaSettings,
+}
+
+/// Which calendar and task backends `get_data` merges
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProviderSettings {
+    /// Google Calendar and Google Tasks
+    pub google: bool,
+    pub caldav: Vec<CalDavAccount>,
+    /// Read-only .ics subscriptions
+    pub ics: Vec<IcsFeed>,
+    /// .ics files or vdir directories on disk
+    pub local: Vec<LocalCalendar>,
+    /// Outlook calendars and Microsoft To Do through Microsoft Graph
+    pub microsoft: MicrosoftAccount,
+    pub todoist: TodoistAccount,
+}
+
+impl Default for ProviderSettings {
+    fn default() -> Self {
+        ProviderSettings {
+            google: true,
+            caldav: Vec::new(),
+            ics: Vec::new(),
+            local: Vec::new(),
+            microsoft: MicrosoftAccount::default(),
+            todoist: TodoistAccount::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CalDavAccount {
+    /// Label for the account, also part of the provider id
+    pub name: String,
+    /// Server root, principal, calendar home or calendar URL
+    pub url: String,
+    pub username: String,
+    /// Account password or app-specific password
+    pub password: String,
+    /// Address to recognise yourself among attendees; defaults to the username
+    pub email: String,
+    /// Display names of the calendars to show; empty shows all
+    pub calendars: Vec<String>,
+    /// Display names of the task lists (VTODO collections) to show; empty shows all
+    pub task_lists: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MicrosoftAccount {
+    pub enabled: bool,
+    /// Application (client) id of an app registration with `http://localhost` as a
+    /// "Mobile and desktop" redirect URI
+    pub client_id: String,
+    /// "common", "organizations", "consumers" or a tenant id
+    pub tenant: String,
+    /// Identity platform root; overridable for testing against a mock server
+    pub login_url: String,
+    /// Graph API root; overridable for testing against a mock server
+    pub graph_url: String,
+    /// Names of the calendars to show; empty shows all
+    pub calendars: Vec<String>,
+    /// Names of the To Do lists to show; empty shows all
+    pub task_lists: Vec<String>,
+}
+
+impl Default for MicrosoftAccount {
+    fn default() -> Self {
+        MicrosoftAccount {
+            enabled: false,
+            client_id: String::new(),
+            tenant: "common".to_string(),
+            login_url: "https://login.microsoftonline.com".to_string(),
+            graph_url: "https://graph.microsoft.com/v1.0".to_string(),
+            calendars: Vec::new(),
+            task_lists: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TodoistAccount {
+    /// API token from Todoist's Settings > Integrations > Developer; empty disables Todoist
+    pub token: String,
+    /// Names of the projects to show; empty shows all
+    pub projects: Vec<String>,
+    /// REST API root; overridable for testing against a mock server
+    pub base_url: String,
+}
+
+impl Default for TodoistAccount {
+    fn default() -> Self {
+        TodoistAccount {
+            token: String::new(),
+            projects: Vec::new(),
+            base_url: "https://api.todoist.com/rest/v2".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IcsFeed {
+    /// http(s):// or webcal:// URL of the feed
+    pub url: String,
+    /// Calendar name; empty uses the feed's own X-WR-CALNAME
+    pub name: String,
+    /// Hex color such as "#22c55e"; empty uses the feed's color or gray
+    pub color: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LocalCalendar {
+    /// An .ics file, a vdir collection, or a directory of collections (vdirsyncer's storage
+    /// path); a leading `~` is the home directory. The paths configured at startup are watched
+    /// for changes; one added later, or created after the widget started, is only read on refresh
+    /// until the widget is restarted.
+    pub path: String,
+    /// Calendar name for a single file or collection; otherwise each vdir's `displayname` is used
+    pub name: String,
+    /// Hex color; empty uses the vdir's `color` file, the calendar's own color or gray
+    pub color: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TaskSettings {
+    /// Safety limit on tasks fetched per list; larger lists are cut off and reported as truncated
+    pub max_tasks_per_list: usize,
+    /// Seconds a completed task can still be undone before the change is sent; 0 sends it right away
+    pub undo_seconds: u64,
+    /// How far back to fetch completed tasks for the "recently completed" section
+    pub show_completed: CompletedWindow,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CompletedWindow {
+    #[default]
+    Off,
+    Today,
+    /// Since the start of the week, per `locale.first_day_of_week`
+    Week,
+}
+
+impl Default for TaskSettings {
+    fn default() -> Self {
+        TaskSettings {
+            max_tasks_per_list: 1000,
+            undo_seconds: 5,
+            show_completed: CompletedWindow::Off,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EventSettings {
+    pub hide_declined: bool,
+    pub dim_tentative: bool,
+    /// Enables creating, editing and deleting events; requests calendar write access
+    pub allow_editing: bool,
+}
+
+/// Defaults for the agenda export
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AgendaSettings {
+    pub format: AgendaFormat,
+    /// One heading (or JSON object) per day instead of a flat list with dates
+    pub group_by_day: bool,
+    pub include_location: bool,
+    pub include_calendar: bool,
+    /// Tasks due in the range, with undated tasks listed at the end
+    pub include_tasks: bool,
+}
+
+impl Default for AgendaSettings {
+    fn default() -> Self {
+        AgendaSettings {
+            format: AgendaFormat::Markdown,
+            group_by_day: true,
+            include_location: true,
+            include_calendar: false,
+            include_tasks: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AgendaFormat {
+    #[default]
+    Markdown,
+    Text,
+    Csv,
+    Json,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LocaleSettings {
+    /// POSIX locale name used for day and month names, e.g. "en_US" or "nb_NO"
+    pub language: String,
+    /// strftime-style pattern used for `date_formatted`
+    pub date_format: String,
+    pub use_24h: bool,
+    pub first_day_of_week: Weekday,
+    pub all_day_label: String,
+    /// Put before the start time on the first day of a timed event spanning several days in
+    /// agendas, e.g. "from 22:00"
+    pub from_label: String,
+    /// Put before the end time on the last day of such an event, e.g. "until 06:00"
+    pub until_label: String,
+    /// Heading for tasks without a due date in agendas
+    pub no_due_date_label: String,
+    /// IANA time zone (e.g. "Europe/Oslo") for interpreting typed times; empty uses the system zone
+    pub timezone: String,
+}
+
+impl Default for LocaleSettings {
+    fn default() -> Self {
+        LocaleSettings {
+            language: "en_US".to_string(),
+            date_format: "%A, %d. %B".to_string(),
+            use_24h: true,
+            first_day_of_week: Weekday::Mon,
+            all_day_label: "All day".to_string(),
+            from_label: "from".to_string(),
+            until_label: "until".to_string(),
+            no_due_date_label: "No due date".to_string(),
+            timezone: String::new(),
+        }
+    }
+}
+
+impl LocaleSettings {
+    pub fn chrono_locale(&self) -> Locale {
+        Locale::try_from(self.language.as_str()).unwrap_or(Locale::en_US)
+    }
+
+    fn time_format(&self) -> &'static str {
+        if self.use_24h {
+            "%H:%M"
+        } else {
+            "%-I:%M %p"
+        }
+    }
+
+    pub fn format_date(&self, date: NaiveDate) -> String {
+        date.format_localized(&self.date_format, self.chrono_locale())
+            .to_string()
+    }
+
+    pub fn format_time<Tz: TimeZone>(&self, dt: &DateTime<Tz>) -> String
+    where
+        Tz::Offset: std::fmt::Display,
+    {
+        dt.format_localized(self.time_format(), self.chrono_locale())
+            .to_string()
+    }
+
+    fn tz(&self) -> Option<Tz> {
+        if self.timezone.is_empty() {
+            return None;
+        }
+        match self.timezone.parse::<Tz>() {
+            Ok(tz) => Some(tz),
+            Err(e) => {
+                eprintln!("Invalid timezone '{}': {}", self.timezone, e);
+                None
+            }
+        }
+    }
+
+    /// The configured time zone, or the system's when none is set
+    pub fn zone(&self) -> Option<Tz> {
+        self.tz()
+            .or_else(|| iana_time_zone::get_timezone().ok()?.parse().ok())
+    }
+
+    /// Current wall-clock time in the configured time zone
+    pub fn now(&self) -> NaiveDateTime {
+        match self.tz() {
+            Some(tz) => chrono::Utc::now().with_timezone(&tz).naive_local(),
+            None => Local::now().naive_local(),
+        }
+    }
+
+    /// Wall-clock time in the configured time zone for an absolute time
+    pub fn to_local<T: TimeZone>(&self, dt: &DateTime<T>) -> NaiveDateTime {
+        self.in_zone(dt).naive_local()
+    }
+
+    /// The same instant with the configured time zone's offset
+    pub fn in_zone<T: TimeZone>(&self, dt: &DateTime<T>) -> DateTime<FixedOffset> {
+        match self.tz() {
+            Some(tz) => dt.with_timezone(&tz).fixed_offset(),
+            None => dt.with_timezone(&Local).fixed_offset(),
+        }
+    }
+
+    /// Attaches the configured time zone to a wall-clock time
+    pub fn localize(&self, naive: NaiveDateTime) -> Option<DateTime<FixedOffset>> {
+        match self.tz() {
+            Some(tz) => tz.from_local_datetime(&naive).earliest().map(|dt| dt.fixed_offset()),
+            None => Local.from_local_datetime(&naive).earliest().map(|dt| dt.fixed_offset()),
+        }
+    }
+
+    /// Number of days between `day` and the most recent configured first day of week
+    pub fn days_since_week_start(&self, day: Weekday) -> i64 {
+        let day = day.num_days_from_monday() as i64;
+        let first = self.first_day_of_week.num_days_from_monday() as i64;
+        (day - first).rem_euclid(7)
+    }
+}
+
+fn get_base_dir() -> PathBuf {
+    let exe_path = std::env::current_exe().unwrap_or_default();
+    exe_path.parent().unwrap_or(&exe_path).to_path_buf()
+}
+
+fn get_settings_path() -> PathBuf {
+    get_base_dir().join("settings.json")
+}
+
+pub fn load_settings() -> Settings {
+    let path = get_settings_path();
+    if path.exists() {
+        match fs::read_to_string(&path) {
+            Ok(content) => match serde_json::from_str(&content) {
+                Ok(settings) => return settings,
+                Err(e) => eprintln!("Failed to parse settings.json: {}", e),
+            },
+            Err(e) => eprintln!("Failed to read settings.json: {}", e),
+        }
+    }
+    Settings::default()
+}
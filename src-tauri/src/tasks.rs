@@ -1,23 +1,91 @@
 use serde::{Deserialize, Serialize};
+use chrono::Datelike;
 
 use crate::auth::get_access_token;
+use crate::provider;
+use crate::settings::{CompletedWindow, Settings};
 
 const TASKS_API_BASE: &str = "https://tasks.googleapis.com/tasks/v1";
 
+// Largest page size the Tasks API accepts
+const PAGE_SIZE: usize = 100;
+
 // Only show tasks from these lists
 const ALLOWED_LISTS: &[&str] = &["I dag", "Min huskeliste"];
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Task {
     pub id: String,
     pub title: String,
     pub completed: bool,
     pub tasklist_id: String,
+    /// Id of the provider the task came from, see `provider.rs`
+    pub provider: String,
+    pub notes: String,
+    /// Due date as `YYYY-MM-DD`; the Tasks API has no due time
+    pub due: String,
+    /// Id of the parent task, empty for top-level tasks
+    pub parent: String,
+    /// Server ordering key among siblings
+    pub position: String,
+    pub updated: String,
+    pub links: Vec<TaskLink>,
+    pub web_view_link: String,
+    pub subtasks: Vec<Task>,
+    /// RFC 3339 completion time, empty for open tasks
+    pub completed_at: String,
+    /// 1 (highest) to 9 (lowest) as in iCalendar, 0 when unset
+    pub priority: u8,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskLink {
+    #[serde(rename = "type")]
+    pub link_type: String,
+    pub description: String,
+    pub link: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskList {
+    pub id: String,
+    pub title: String,
+}
+
+/// Task fields editable from the widget. `due` is `YYYY-MM-DD`, empty for no due date.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TaskDraft {
+    pub title: String,
+    pub notes: String,
+    pub due: String,
+}
+
+impl TaskDraft {
+    fn to_request_body(&self) -> Result<serde_json::Value, String> {
+        // The Tasks API only keeps the date part of `due`
+        let due = if self.due.is_empty() {
+            serde_json::Value::Null
+        } else {
+            let date = chrono::NaiveDate::parse_from_str(&self.due, "%Y-%m-%d")
+                .map_err(|e| format!("Invalid due date '{}': {}", self.due, e))?;
+            serde_json::Value::String(format!("{}T00:00:00.000Z", date.format("%Y-%m-%d")))
+        };
+
+        Ok(serde_json::json!({
+            "title": self.title,
+            "notes": self.notes,
+            "due": due,
+        }))
+    }
 }
 
 #[derive(Debug, Deserialize)]
 struct TaskListsResponse {
     items: Option<Vec<TaskListEntry>>,
+    #[serde(rename = "nextPageToken")]
+    next_page_token: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -29,6 +97,17 @@ struct TaskListEntry {
 #[derive(Debug, Deserialize)]
 struct TasksResponse {
     items: Option<Vec<TaskEntry>>,
+    #[serde(rename = "nextPageToken")]
+    next_page_token: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TaskFetch {
+    pub tasks: Vec<Task>,
+    /// Titles of lists that hit `max_tasks_per_list` and were cut off
+    pub truncated_lists: Vec<String>,
+    /// Tasks completed within `tasks.show_completed`, most recent first
+    pub completed: Vec<Task>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -36,116 +115,519 @@ struct TaskEntry {
     id: Option<String>,
     title: Option<String>,
     status: Option<String>,
+    notes: Option<String>,
+    due: Option<String>,
+    parent: Option<String>,
+    position: Option<String>,
+    updated: Option<String>,
+    links: Option<Vec<TaskLinkEntry>>,
+    #[serde(rename = "webViewLink")]
+    web_view_link: Option<String>,
+    completed: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TaskLinkEntry {
+    #[serde(rename = "type")]
+    link_type: Option<String>,
+    description: Option<String>,
+    link: Option<String>,
 }
 
-pub async fn get_tasks() -> Result<Vec<Task>, String> {
+pub async fn get_tasks(settings: &Settings) -> Result<TaskFetch, String> {
     let access_token = get_access_token().await?;
     let client = reqwest::Client::new();
+    let limit = settings.tasks.max_tasks_per_list;
+    let completed_min = completed_min(settings);
 
-    // Get all task lists
-    let url = format!("{}/users/@me/lists", TASKS_API_BASE);
-    let response = client
-        .get(&url)
-        .bearer_auth(&access_token)
-        .send()
-        .await
-        .map_err(|e| format!("Failed to fetch task lists: {}", e))?;
-
-    if !response.status().is_success() {
-        let error = response.text().await.unwrap_or_default();
-        return Err(format!("Tasks API error: {}", error));
-    }
-
-    let tasklists: TaskListsResponse = response
-        .json()
-        .await
-        .map_err(|e| format!("Failed to parse task lists: {}", e))?;
-
-    let mut all_tasks = Vec::new();
+    let lists = fetch_tasklist_entries(&client, &access_token).await?;
 
-    if let Some(lists) = tasklists.items {
-        for list in lists {
-            // Only process allowed lists
-            let list_title = list.title.as_deref().unwrap_or("");
-            if !ALLOWED_LISTS.contains(&list_title) {
-                continue;
-            }
+    let mut fetch = TaskFetch::default();
 
-            // Get tasks from this list
-            let url = format!(
-                "{}/lists/{}/tasks?showCompleted=false&maxResults=50",
-                TASKS_API_BASE,
-                urlencoding::encode(&list.id)
-            );
+    for list in lists {
+        // Only process allowed lists
+        let list_title = list.title.as_deref().unwrap_or("");
+        if !ALLOWED_LISTS.contains(&list_title) {
+            continue;
+        }
 
-            let response = match client
-                .get(&url)
-                .bearer_auth(&access_token)
-                .send()
-                .await
-            {
-                Ok(r) => r,
+        let (items, truncated) =
+            match fetch_list_entries(&client, &access_token, &list.id, "showCompleted=false", limit).await {
+                Ok(result) => result,
                 Err(e) => {
                     eprintln!("Failed to fetch tasks from {}: {}", list_title, e);
                     continue;
                 }
             };
 
-            if !response.status().is_success() {
-                continue;
+        if truncated {
+            eprintln!(
+                "Task list {} has more than {} tasks; the rest were skipped",
+                list_title, limit
+            );
+            fetch.truncated_lists.push(list_title.to_string());
+        }
+
+        if let Some(completed_min) = &completed_min {
+            // Tasks cleared from the list are hidden, so those have to be asked for too
+            let query = format!(
+                "showCompleted=true&showHidden=true&completedMin={}",
+                urlencoding::encode(completed_min)
+            );
+            match fetch_list_entries(&client, &access_token, &list.id, &query, limit).await {
+                Ok((items, _)) => fetch.completed.extend(
+                    items
+                        .into_iter()
+                        .map(|item| to_task(item, &list.id))
+                        .filter(|t| !t.title.is_empty() && t.completed),
+                ),
+                Err(e) => eprintln!("Failed to fetch completed tasks from {}: {}", list_title, e),
             }
+        }
 
-            let tasks_response: TasksResponse = match response.json().await {
-                Ok(t) => t,
-                Err(_) => continue,
-            };
+        let list_tasks: Vec<Task> = items
+            .into_iter()
+            .map(|item| to_task(item, &list.id))
+            // Filter out completed and untitled tasks
+            .filter(|t| !t.title.is_empty() && !t.completed)
+            .collect();
 
-            if let Some(items) = tasks_response.items {
-                for item in items {
-                    let title = item.title.unwrap_or_default();
-                    if title.is_empty() {
-                        continue;
-                    }
-
-                    all_tasks.push(Task {
-                        id: item.id.unwrap_or_default(),
-                        title,
-                        completed: item.status.as_deref() == Some("completed"),
-                        tasklist_id: list.id.clone(),
-                    });
-                }
+        fetch.tasks.extend(nest_tasks(list_tasks));
+    }
+
+    fetch.completed.sort_by(|a, b| b.completed_at.cmp(&a.completed_at));
+
+    Ok(fetch)
+}
+
+/// Lower bound for completed tasks as RFC 3339, or `None` when the section is off
+pub fn completed_min(settings: &Settings) -> Option<String> {
+    let today = settings.locale.now().date();
+    let since = match settings.tasks.show_completed {
+        CompletedWindow::Off => return None,
+        CompletedWindow::Today => today,
+        CompletedWindow::Week => {
+            today - chrono::Duration::days(settings.locale.days_since_week_start(today.weekday()))
+        }
+    };
+    settings
+        .locale
+        .localize(since.and_hms_opt(0, 0, 0)?)
+        .map(|dt| dt.to_rfc3339())
+}
+
+/// All task lists, following `nextPageToken`
+async fn fetch_tasklist_entries(
+    client: &reqwest::Client,
+    access_token: &str,
+) -> Result<Vec<TaskListEntry>, String> {
+    let mut lists = Vec::new();
+    let mut page_token: Option<String> = None;
+
+    loop {
+        let mut url = format!("{}/users/@me/lists?maxResults={}", TASKS_API_BASE, PAGE_SIZE);
+        if let Some(token) = &page_token {
+            url.push_str(&format!("&pageToken={}", urlencoding::encode(token)));
+        }
+
+        let response = client
+            .get(&url)
+            .bearer_auth(access_token)
+            .send()
+            .await
+            .map_err(|e| format!("Failed to fetch task lists: {}", e))?;
+
+        if !response.status().is_success() {
+            let error = response.text().await.unwrap_or_default();
+            return Err(format!("Tasks API error: {}", error));
+        }
+
+        let page: TaskListsResponse = response
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse task lists: {}", e))?;
+
+        lists.extend(page.items.unwrap_or_default());
+
+        match page.next_page_token {
+            Some(token) => page_token = Some(token),
+            None => break,
+        }
+    }
+
+    Ok(lists)
+}
+
+/// Tasks in one list matching `query`, following `nextPageToken` up to `limit` entries.
+/// The flag is set when the list had more than `limit` tasks.
+async fn fetch_list_entries(
+    client: &reqwest::Client,
+    access_token: &str,
+    tasklist_id: &str,
+    query: &str,
+    limit: usize,
+) -> Result<(Vec<TaskEntry>, bool), String> {
+    let mut entries = Vec::new();
+    let mut page_token: Option<String> = None;
+
+    loop {
+        // Up to one entry past the limit, which tells a full list from a cut-off one
+        let page_size = PAGE_SIZE.min(limit + 1 - entries.len());
+        let mut url = format!(
+            "{}/lists/{}/tasks?{}&maxResults={}",
+            TASKS_API_BASE,
+            urlencoding::encode(tasklist_id),
+            query,
+            page_size
+        );
+        if let Some(token) = &page_token {
+            url.push_str(&format!("&pageToken={}", urlencoding::encode(token)));
+        }
+
+        let response = client
+            .get(&url)
+            .bearer_auth(access_token)
+            .send()
+            .await
+            .map_err(|e| format!("Failed to fetch tasks: {}", e))?;
+
+        if !response.status().is_success() {
+            let error = response.text().await.unwrap_or_default();
+            return Err(format!("Tasks API error: {}", error));
+        }
+
+        let page: TasksResponse = response
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse tasks: {}", e))?;
+
+        entries.extend(page.items.unwrap_or_default());
+
+        if entries.len() > limit {
+            entries.truncate(limit);
+            return Ok((entries, true));
+        }
+
+        match page.next_page_token {
+            Some(token) => page_token = Some(token),
+            None => break,
+        }
+    }
+
+    Ok((entries, false))
+}
+
+/// Orders tasks by server position and moves subtasks under their parents.
+/// Subtasks whose parent isn't in the list stay at the top level.
+pub fn nest_tasks(mut tasks: Vec<Task>) -> Vec<Task> {
+    tasks.sort_by(|a, b| a.position.cmp(&b.position));
+
+    let ids: std::collections::HashSet<String> = tasks.iter().map(|t| t.id.clone()).collect();
+    let (children, mut top_level): (Vec<Task>, Vec<Task>) = tasks
+        .into_iter()
+        .partition(|t| !t.parent.is_empty() && ids.contains(&t.parent));
+
+    // Google Tasks only allows one level of nesting
+    for child in children {
+        if let Some(parent) = top_level.iter_mut().find(|t| t.id == child.parent) {
+            parent.subtasks.push(child);
+        }
+    }
+
+    top_level
+}
+
+/// For sources with deeper nesting than the widget shows: hangs every subtask off its top-level ancestor
+pub fn flatten_parents(mut tasks: Vec<Task>) -> Vec<Task> {
+    let parents: std::collections::HashMap<String, String> = tasks
+        .iter()
+        .filter(|t| !t.parent.is_empty())
+        .map(|t| (t.id.clone(), t.parent.clone()))
+        .collect();
+
+    for task in &mut tasks {
+        let mut depth = 0;
+        while let Some(next) = parents.get(&task.parent) {
+            // Guards against cycles in the parent links
+            depth += 1;
+            if depth > parents.len() {
+                break;
             }
+            task.parent = next.clone();
         }
     }
+    tasks
+}
 
-    // Filter out completed tasks and sort
-    all_tasks.retain(|t| !t.completed);
+pub fn is_task(task: &Task, tasklist_id: &str, task_id: &str) -> bool {
+    task.id == task_id && task.tasklist_id == tasklist_id
+}
 
-    Ok(all_tasks)
+/// The task at any depth, since subtasks are nested under their parents
+pub fn find_task<'a>(tasks: &'a [Task], tasklist_id: &str, task_id: &str) -> Option<&'a Task> {
+    tasks.iter().find_map(|t| {
+        if is_task(t, tasklist_id, task_id) {
+            Some(t)
+        } else {
+            find_task(&t.subtasks, tasklist_id, task_id)
+        }
+    })
 }
 
-pub async fn complete_task(task_id: &str, tasklist_id: &str) -> Result<bool, String> {
-    let access_token = get_access_token().await?;
+pub fn find_task_mut<'a>(tasks: &'a mut [Task], tasklist_id: &str, task_id: &str) -> Option<&'a mut Task> {
+    for task in tasks.iter_mut() {
+        if is_task(task, tasklist_id, task_id) {
+            return Some(task);
+        }
+        if let Some(found) = find_task_mut(&mut task.subtasks, tasklist_id, task_id) {
+            return Some(found);
+        }
+    }
+    None
+}
+
+pub fn for_each_task_mut(tasks: &mut [Task], f: &mut impl FnMut(&mut Task)) {
+    for task in tasks.iter_mut() {
+        f(task);
+        for_each_task_mut(&mut task.subtasks, f);
+    }
+}
+
+/// Removes the task, along with its subtasks, at any depth
+pub fn retain_task(tasks: &mut Vec<Task>, tasklist_id: &str, task_id: &str) {
+    tasks.retain(|t| !is_task(t, tasklist_id, task_id));
+    for task in tasks.iter_mut() {
+        retain_task(&mut task.subtasks, tasklist_id, task_id);
+    }
+}
+
+/// Adds a task under its parent when that is in `tasks`, otherwise at the top
+pub fn insert_under_parent(tasks: &mut Vec<Task>, task: Task) {
+    match find_task_mut(tasks, &task.tasklist_id, &task.parent) {
+        Some(parent) if !task.parent.is_empty() => parent.subtasks.insert(0, task),
+        _ => tasks.insert(0, task),
+    }
+}
+
+pub async fn complete_task(task_id: &str, tasklist_id: &str) -> Result<Task, String> {
+    let body = serde_json::json!({
+        "status": "completed"
+    });
+
     let client = reqwest::Client::new();
+    send_task_request(client.patch(task_url(tasklist_id, task_id)).json(&body), tasklist_id).await
+}
 
-    let url = format!(
+fn task_url(tasklist_id: &str, task_id: &str) -> String {
+    format!(
         "{}/lists/{}/tasks/{}",
         TASKS_API_BASE,
         urlencoding::encode(tasklist_id),
         urlencoding::encode(task_id)
+    )
+}
+
+fn to_task(entry: TaskEntry, tasklist_id: &str) -> Task {
+    Task {
+        id: entry.id.unwrap_or_default(),
+        title: entry.title.unwrap_or_default(),
+        completed: entry.status.as_deref() == Some("completed"),
+        tasklist_id: tasklist_id.to_string(),
+        provider: provider::GOOGLE.to_string(),
+        notes: entry.notes.unwrap_or_default(),
+        // RFC 3339, but only the date part is meaningful
+        due: entry.due.map(|d| d.chars().take(10).collect()).unwrap_or_default(),
+        parent: entry.parent.unwrap_or_default(),
+        position: entry.position.unwrap_or_default(),
+        updated: entry.updated.unwrap_or_default(),
+        links: entry
+            .links
+            .unwrap_or_default()
+            .into_iter()
+            .map(|l| TaskLink {
+                link_type: l.link_type.unwrap_or_default(),
+                description: l.description.unwrap_or_default(),
+                link: l.link.unwrap_or_default(),
+            })
+            .collect(),
+        web_view_link: entry.web_view_link.unwrap_or_default(),
+        subtasks: Vec::new(),
+        completed_at: entry.completed.unwrap_or_default(),
+        priority: 0,
+    }
+}
+
+/// Builds the task shown while a request is in flight
+pub fn draft_task(draft: &TaskDraft, id: &str, tasklist_id: &str) -> Task {
+    Task {
+        id: id.to_string(),
+        title: draft.title.clone(),
+        tasklist_id: tasklist_id.to_string(),
+        provider: provider::GOOGLE.to_string(),
+        notes: draft.notes.clone(),
+        due: draft.due.clone(),
+        ..Task::default()
+    }
+}
+
+async fn send_task_request(request: reqwest::RequestBuilder, tasklist_id: &str) -> Result<Task, String> {
+    let access_token = get_access_token().await?;
+
+    let response = request
+        .bearer_auth(&access_token)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send task request: {}", e))?;
+
+    if !response.status().is_success() {
+        let error = response.text().await.unwrap_or_default();
+        return Err(format!("Tasks API error: {}", error));
+    }
+
+    let entry: TaskEntry = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse task: {}", e))?;
+
+    Ok(to_task(entry, tasklist_id))
+}
+
+pub async fn get_tasklists() -> Result<Vec<TaskList>, String> {
+    let access_token = get_access_token().await?;
+    let client = reqwest::Client::new();
+
+    let lists = fetch_tasklist_entries(&client, &access_token).await?;
+
+    Ok(lists
+        .into_iter()
+        .map(|list| TaskList {
+            title: list.title.unwrap_or_default(),
+            id: list.id,
+        })
+        .collect())
+}
+
+pub async fn insert_task(tasklist_id: &str, draft: &TaskDraft) -> Result<Task, String> {
+    let client = reqwest::Client::new();
+    let url = format!(
+        "{}/lists/{}/tasks",
+        TASKS_API_BASE,
+        urlencoding::encode(tasklist_id)
     );
+    let body = draft.to_request_body()?;
+
+    send_task_request(client.post(&url).json(&body), tasklist_id).await
+}
+
+pub async fn update_task(task_id: &str, tasklist_id: &str, draft: &TaskDraft) -> Result<Task, String> {
+    let client = reqwest::Client::new();
+    let body = draft.to_request_body()?;
+
+    send_task_request(client.patch(task_url(tasklist_id, task_id)).json(&body), tasklist_id).await
+}
 
+pub async fn uncomplete_task(task_id: &str, tasklist_id: &str) -> Result<Task, String> {
+    let client = reqwest::Client::new();
+
+    // Clearing `completed` is what moves the task back to the open list
     let body = serde_json::json!({
-        "status": "completed"
+        "status": "needsAction",
+        "completed": null
     });
 
+    send_task_request(client.patch(task_url(tasklist_id, task_id)).json(&body), tasklist_id).await
+}
+
+pub async fn delete_task(task_id: &str, tasklist_id: &str) -> Result<(), String> {
+    let access_token = get_access_token().await?;
+    let client = reqwest::Client::new();
+
     let response = client
-        .patch(&url)
+        .delete(task_url(tasklist_id, task_id))
         .bearer_auth(&access_token)
-        .json(&body)
         .send()
         .await
-        .map_err(|e| format!("Failed to complete task: {}", e))?;
+        .map_err(|e| format!("Failed to delete task: {}", e))?;
+
+    if !response.status().is_success() {
+        let error = response.text().await.unwrap_or_default();
+        return Err(format!("Tasks API error: {}", error));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(id: &str, parent: &str) -> Task {
+        Task {
+            id: id.to_string(),
+            title: id.to_string(),
+            tasklist_id: "list".to_string(),
+            provider: "caldav:Work".to_string(),
+            parent: parent.to_string(),
+            position: id.to_string(),
+            ..Task::default()
+        }
+    }
+
+    /// a (with subtasks a1 and a2), b
+    fn tree() -> Vec<Task> {
+        nest_tasks(vec![task("a", ""), task("a1", "a"), task("a2", "a"), task("b", "")])
+    }
 
-    Ok(response.status().is_success())
+    fn ids(tasks: &[Task]) -> Vec<String> {
+        tasks
+            .iter()
+            .map(|t| {
+                let subtasks = ids(&t.subtasks);
+                if subtasks.is_empty() {
+                    t.id.clone()
+                } else {
+                    format!("{}({})", t.id, subtasks.join(" "))
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn nests_and_flattens() {
+        assert_eq!(ids(&tree()), ["a(a1 a2)", "b"]);
+
+        // Deeper levels hang off the top-level ancestor
+        let deep = flatten_parents(vec![task("a", ""), task("a1", "a"), task("a1x", "a1")]);
+        assert_eq!(ids(&nest_tasks(deep)), ["a(a1 a1x)"]);
+    }
+
+    #[test]
+    fn finds_subtasks() {
+        let mut tasks = tree();
+        assert_eq!(find_task(&tasks, "list", "a2").map(|t| t.provider.as_str()), Some("caldav:Work"));
+        assert!(find_task(&tasks, "other", "a2").is_none());
+
+        find_task_mut(&mut tasks, "list", "a1").unwrap().completed = true;
+        assert!(tasks[0].subtasks[0].completed);
+
+        let mut count = 0;
+        for_each_task_mut(&mut tasks, &mut |_| count += 1);
+        assert_eq!(count, 4);
+    }
+
+    #[test]
+    fn removes_and_reinserts_subtasks() {
+        let mut tasks = tree();
+        let removed = find_task(&tasks, "list", "a1").cloned().unwrap();
+        retain_task(&mut tasks, "list", "a1");
+        assert_eq!(ids(&tasks), ["a(a2)", "b"]);
+
+        insert_under_parent(&mut tasks, removed);
+        assert_eq!(ids(&tasks), ["a(a1 a2)", "b"]);
+
+        // Without its parent a task goes to the top
+        retain_task(&mut tasks, "list", "a");
+        insert_under_parent(&mut tasks, task("a2", "a"));
+        assert_eq!(ids(&tasks), ["a2", "b"]);
+    }
 }
@@ -0,0 +1,229 @@
+//! Todoist tasks over the REST API, authenticated with a personal API token.
+//! Projects map to task lists and completion goes through the close endpoint.
+
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+use crate::provider::{TaskProvider, TODOIST};
+use crate::settings::{Settings, TodoistAccount};
+use crate::tasks::{self, Task, TaskFetch};
+
+#[derive(Debug, Deserialize)]
+struct ProjectEntry {
+    id: String,
+    name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TaskEntry {
+    id: String,
+    content: Option<String>,
+    description: Option<String>,
+    is_completed: Option<bool>,
+    project_id: Option<String>,
+    parent_id: Option<String>,
+    order: Option<i64>,
+    /// 4 is the most urgent (shown as "p1" in the apps), 1 is normal
+    priority: Option<u8>,
+    due: Option<DueEntry>,
+    url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DueEntry {
+    /// Due date in the user's time zone, also set for tasks with a due time
+    date: Option<String>,
+}
+
+fn to_task(entry: TaskEntry) -> Task {
+    Task {
+        id: entry.id,
+        title: entry.content.unwrap_or_default(),
+        completed: entry.is_completed.unwrap_or(false),
+        tasklist_id: entry.project_id.unwrap_or_default(),
+        provider: TODOIST.to_string(),
+        notes: entry.description.unwrap_or_default(),
+        due: entry.due.and_then(|d| d.date).unwrap_or_default(),
+        parent: entry.parent_id.unwrap_or_default(),
+        // Zero-padded so the string order matches Todoist's
+        position: format!("{:010}", entry.order.unwrap_or(0).max(0)),
+        web_view_link: entry.url.unwrap_or_default(),
+        priority: match entry.priority {
+            Some(4) => 1,
+            Some(3) => 5,
+            Some(2) => 9,
+            _ => 0,
+        },
+        ..Task::default()
+    }
+}
+
+pub struct TodoistProvider {
+    account: TodoistAccount,
+}
+
+impl TodoistProvider {
+    pub fn new(account: TodoistAccount) -> Self {
+        TodoistProvider { account }
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.account.base_url.trim_end_matches('/'), path)
+    }
+
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<reqwest::Response, String> {
+        let response = request
+            .bearer_auth(&self.account.token)
+            .send()
+            .await
+            .map_err(|e| format!("Failed to reach Todoist: {}", e))?;
+
+        if response.status() == reqwest::StatusCode::UNAUTHORIZED {
+            return Err("Todoist rejected the API token".to_string());
+        }
+        if !response.status().is_success() {
+            let error = response.text().await.unwrap_or_default();
+            return Err(format!("Todoist API error: {}", error));
+        }
+        Ok(response)
+    }
+
+    async fn get<T: DeserializeOwned>(&self, client: &reqwest::Client, path: &str) -> Result<T, String> {
+        self.send(client.get(self.url(path)))
+            .await?
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse Todoist response: {}", e))
+    }
+}
+
+#[async_trait]
+impl TaskProvider for TodoistProvider {
+    fn id(&self) -> String {
+        TODOIST.to_string()
+    }
+
+    async fn get_tasks(&self, settings: &Settings) -> Result<TaskFetch, String> {
+        let client = reqwest::Client::new();
+        let limit = settings.tasks.max_tasks_per_list;
+
+        let projects: Vec<ProjectEntry> = self.get(&client, "/projects").await?;
+
+        let mut fetch = TaskFetch::default();
+        for project in projects {
+            let project_name = project.name.unwrap_or_default();
+            if !self.account.projects.is_empty() && !self.account.projects.contains(&project_name) {
+                continue;
+            }
+
+            // Only open tasks are listed by the REST API
+            let path = format!("/tasks?project_id={}", urlencoding::encode(&project.id));
+            let entries: Vec<TaskEntry> = match self.get(&client, &path).await {
+                Ok(entries) => entries,
+                Err(e) => {
+                    eprintln!("Failed to fetch tasks from {}: {}", project_name, e);
+                    continue;
+                }
+            };
+
+            let mut list_tasks: Vec<Task> = entries
+                .into_iter()
+                .map(to_task)
+                .filter(|t| !t.title.is_empty() && !t.completed)
+                .collect();
+
+            if list_tasks.len() > limit {
+                eprintln!(
+                    "Task list {} has more than {} tasks; the rest were skipped",
+                    project_name, limit
+                );
+                list_tasks.sort_by(|a, b| a.position.cmp(&b.position));
+                list_tasks.truncate(limit);
+                fetch.truncated_lists.push(project_name);
+            }
+
+            // Todoist nests subtasks to any depth
+            fetch.tasks.extend(tasks::nest_tasks(tasks::flatten_parents(list_tasks)));
+        }
+
+        Ok(fetch)
+    }
+
+    async fn complete_task(&self, task_id: &str, _tasklist_id: &str, _settings: &Settings) -> Result<Task, String> {
+        let client = reqwest::Client::new();
+        let path = format!("/tasks/{}", urlencoding::encode(task_id));
+
+        // The close endpoint answers 204 without a body, so read the task first
+        let entry: TaskEntry = self.get(&client, &path).await?;
+        self.send(client.post(self.url(&format!("{}/close", path)))).await?;
+
+        Ok(Task {
+            completed: true,
+            completed_at: chrono::Utc::now().to_rfc3339(),
+            ..to_task(entry)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Open tasks of one project, as `GET /tasks?project_id=` returns them
+    const TASKS: &str = r#"[
+        {
+            "id": "7001", "project_id": "220", "content": "Plan the offsite", "description": "Book a venue",
+            "is_completed": false, "order": 2, "priority": 4, "parent_id": null,
+            "due": { "date": "2026-10-16", "string": "Oct 16", "is_recurring": false },
+            "url": "https://app.todoist.com/app/task/7001"
+        },
+        {
+            "id": "7002", "project_id": "220", "content": "Ask for budget", "is_completed": false,
+            "order": 1, "priority": 3, "parent_id": "7001",
+            "due": { "date": "2026-10-14", "datetime": "2026-10-14T13:00:00Z", "string": "today 15:00" }
+        },
+        {
+            "id": "7003", "project_id": "220", "content": "Compare quotes", "is_completed": false,
+            "order": 1, "priority": 2, "parent_id": "7002", "due": null
+        },
+        { "id": "7004", "project_id": "220", "content": "Tidy desk", "order": 1, "priority": 1 }
+    ]"#;
+
+    fn tasks() -> Vec<Task> {
+        serde_json::from_str::<Vec<TaskEntry>>(TASKS).unwrap().into_iter().map(to_task).collect()
+    }
+
+    #[test]
+    fn maps_todoist_tasks() {
+        let tasks = tasks();
+        // Todoist's 4 is the most urgent, the widget's 1
+        let priorities: Vec<u8> = tasks.iter().map(|t| t.priority).collect();
+        assert_eq!(priorities, [1, 5, 9, 0]);
+
+        // The date is kept for tasks with a time as well
+        let due: Vec<&str> = tasks.iter().map(|t| t.due.as_str()).collect();
+        assert_eq!(due, ["2026-10-16", "2026-10-14", "", ""]);
+
+        let offsite = &tasks[0];
+        assert_eq!((offsite.tasklist_id.as_str(), offsite.provider.as_str()), ("220", TODOIST));
+        assert_eq!(offsite.notes, "Book a venue");
+        assert_eq!(offsite.position, "0000000002");
+        assert_eq!(offsite.web_view_link, "https://app.todoist.com/app/task/7001");
+        assert!(!offsite.completed);
+    }
+
+    #[test]
+    fn nests_subtasks_under_their_top_level_task() {
+        let nested = tasks::nest_tasks(tasks::flatten_parents(tasks()));
+        let outline: Vec<(&str, Vec<&str>)> = nested
+            .iter()
+            .map(|t| (t.title.as_str(), t.subtasks.iter().map(|s| s.title.as_str()).collect()))
+            .collect();
+        // The sub-subtask is hung off the top-level task, as the widget shows one level
+        assert_eq!(
+            outline,
+            [("Tidy desk", vec![]), ("Plan the offsite", vec!["Ask for budget", "Compare quotes"])]
+        );
+    }
+}